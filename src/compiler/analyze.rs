@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use crate::compiler::ast::{Node, Program};
+
+/// Aggregate metrics over a parsed program.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ProgramStats {
+    /// Number of nodes of each kind, keyed by [`node_kind`].
+    pub node_counts: BTreeMap<&'static str, usize>,
+
+    /// Deepest nesting of structural scopes (blocks, block expressions,
+    /// function bodies). Top-level statements are at depth 0.
+    pub max_scope_depth: usize,
+
+    /// Deepest expression tree. A lone literal or identifier has depth 1.
+    pub max_expr_depth: usize,
+
+    /// Every function definition as `(name, arity)`, in source order.
+    pub functions: Vec<(String, usize)>,
+}
+
+impl ProgramStats {
+    /// Number of nodes of the given kind.
+    pub fn count(&self, kind: &str) -> usize {
+        self.node_counts.get(kind).copied().unwrap_or(0)
+    }
+}
+
+/// Collect [`ProgramStats`] for a program. This is a read-only pass.
+pub fn analyze(program: &Program) -> ProgramStats {
+    let mut stats = ProgramStats::default();
+
+    for node in &program.nodes {
+        walk(node, 0, &mut stats);
+    }
+
+    stats
+}
+
+/// Stable name of a node's kind; see [`NodeKind::name`](crate::compiler::ast::NodeKind::name).
+pub fn node_kind(node: &Node) -> &'static str {
+    node.kind().name()
+}
+
+// Visits `node` at scope depth `depth` and returns its expression depth.
+fn walk(node: &Node, depth: usize, stats: &mut ProgramStats) -> usize {
+    *stats.node_counts.entry(node_kind(node)).or_insert(0) += 1;
+    stats.max_scope_depth = stats.max_scope_depth.max(depth);
+
+    let expr_depth = match node {
+        Node::Ident(_) | Node::Lit(_) => 1,
+
+        Node::Not(inner) | Node::Neg(inner) => 1 + walk(inner, depth, stats),
+
+        Node::Add(l, r)
+        | Node::Sub(l, r)
+        | Node::Mul(l, r)
+        | Node::Div(l, r)
+        | Node::Mod(l, r)
+        | Node::Eq(l, r)
+        | Node::Ne(l, r)
+        | Node::Lt(l, r)
+        | Node::Le(l, r)
+        | Node::Gt(l, r)
+        | Node::Ge(l, r)
+        | Node::And(l, r)
+        | Node::Or(l, r)
+        | Node::Has(l, r)
+        | Node::Present(l, r)
+        | Node::Pipe(l, r)
+        | Node::Coalesce(l, r) => 1 + walk(l, depth, stats).max(walk(r, depth, stats)),
+
+        Node::Call(call) => {
+            let mut deepest = walk(&call.callee, depth, stats);
+            for arg in &call.args {
+                deepest = deepest.max(walk(arg, depth, stats));
+            }
+            1 + deepest
+        }
+
+        Node::BlockExpr(block) => {
+            stats.max_scope_depth = stats.max_scope_depth.max(depth + 1);
+            for n in &block.nodes {
+                walk(n, depth + 1, stats);
+            }
+            1 + block
+                .value
+                .as_ref()
+                .map(|v| walk(v, depth + 1, stats))
+                .unwrap_or(0)
+        }
+
+        Node::Block(block) => {
+            stats.max_scope_depth = stats.max_scope_depth.max(depth + 1);
+            for segment in &block.segments {
+                for n in &segment.nodes {
+                    walk(n, depth + 1, stats);
+                }
+            }
+            0
+        }
+
+        Node::Func(func) => {
+            stats.functions.push((func.name.clone(), func.params.len()));
+            stats.max_scope_depth = stats.max_scope_depth.max(depth + 1);
+            for param in &func.params {
+                if let Some(default) = &param.default {
+                    walk(default, depth, stats);
+                }
+            }
+            for n in &func.body {
+                walk(n, depth + 1, stats);
+            }
+            0
+        }
+
+        Node::Local(inner) => {
+            walk(inner, depth, stats);
+            0
+        }
+        Node::Ret(ret) => {
+            if let Some(value) = &ret.value {
+                walk(value, depth, stats);
+            }
+            0
+        }
+        Node::Define(def) => {
+            walk(&def.value, depth, stats);
+            0
+        }
+        Node::Guard(guard) => {
+            for branch in &guard.branches {
+                walk(&branch.expr, depth, stats);
+            }
+            0
+        }
+        Node::DefineEmpty(_) | Node::Copy(_) | Node::Bind(_) => 0,
+    };
+
+    stats.max_expr_depth = stats.max_expr_depth.max(expr_depth);
+    expr_depth
+}
@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+
+use crate::compiler::analyze::{analyze, node_kind};
+use crate::compiler::ast::{Literal, Node, NodeKind, Program};
+use crate::compiler::lexer::Lexer;
+use crate::compiler::parser::Parser;
+
+fn parse_program(src: &str) -> Program {
+    let tokens = Lexer::new(src).tokenize().expect("lexing failed");
+    let mut parser = Parser::new(&tokens);
+    parser.parse_program().expect("failed to parse program")
+}
+
+#[test]
+fn analyze_mixed_program() {
+    let program = parse_program(
+        "\
+x = 1 + 2 * 3;
+y =;
+z := x;
+fn add :(a, b = 2)( ret a + b; ):
+fn noop :()( ret; ):
+:{ w = :[ v = 1; v ]:; }:
+r = add(1);
+",
+    );
+
+    let stats = analyze(&program);
+
+    assert_eq!(stats.count("define"), 4);
+    assert_eq!(stats.count("define_empty"), 1);
+    assert_eq!(stats.count("copy"), 1);
+    assert_eq!(stats.count("func"), 2);
+    assert_eq!(stats.count("ret"), 2);
+    assert_eq!(stats.count("block"), 1);
+    assert_eq!(stats.count("block_expr"), 1);
+    assert_eq!(stats.count("call"), 1);
+    assert_eq!(stats.count("add"), 2);
+    assert_eq!(stats.count("mul"), 1);
+    assert_eq!(stats.count("guard"), 0);
+
+    // block -> block expression
+    assert_eq!(stats.max_scope_depth, 2);
+    // `1 + 2 * 3` is add(lit, mul(lit, lit))
+    assert_eq!(stats.max_expr_depth, 3);
+
+    assert_eq!(
+        stats.functions,
+        vec![("add".to_string(), 2), ("noop".to_string(), 0)]
+    );
+}
+
+#[test]
+fn analyze_empty_program() {
+    let stats = analyze(&Program { nodes: vec![] });
+
+    assert!(stats.node_counts.is_empty());
+    assert_eq!(stats.max_scope_depth, 0);
+    assert_eq!(stats.max_expr_depth, 0);
+    assert!(stats.functions.is_empty());
+}
+
+#[test]
+fn node_kind_is_a_fieldless_discriminant() {
+    let one = || Box::new(Node::Lit(Literal::Num(1)));
+
+    assert_eq!(Node::Add(one(), one()).kind(), NodeKind::Add);
+    assert_eq!(Node::Neg(one()).kind(), NodeKind::Neg);
+    assert_ne!(Node::Add(one(), one()).kind(), NodeKind::Sub);
+
+    let program = parse_program("a = 1 + 2; b = 3 + 4; c =;");
+    let kinds: HashSet<NodeKind> = program.nodes.iter().map(Node::kind).collect();
+    assert_eq!(kinds, HashSet::from([NodeKind::Define, NodeKind::DefineEmpty]));
+}
+
+#[test]
+fn node_kind_names_match_analyze_counts() {
+    let node = Node::Coalesce(Box::new(Node::Ident("a".into())), Box::new(Node::Lit(Literal::Void)));
+
+    assert_eq!(node_kind(&node), "coalesce");
+    assert_eq!(NodeKind::BlockExpr.name(), "block_expr");
+}
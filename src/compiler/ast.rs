@@ -1,4 +1,10 @@
+use std::ops::Deref;
+
+use crate::compiler::error::Span;
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
 pub enum Literal {
     /// Integer number.
     Num(i64),
@@ -17,13 +23,68 @@ pub enum Literal {
     /// `void` always evaluates to a false flag.
     /// There is no `undefined` in Druim.
     Void,
+
+    /// Empty value.
+    ///
+    /// Like `void`, `emp` marks absence and evaluates to a false flag.
+    Emp,
 }
 
+/// Where a node was written in the source.
+///
+/// A span is bookkeeping, not structure: any two spans compare equal, so
+/// a program that is formatted and parsed again still equals the
+/// original. Nodes built by hand can use the default, an empty span at
+/// the start of the source.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct NodeSpan(pub Span);
+
+impl PartialEq for NodeSpan {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
 
+impl Deref for NodeSpan {
+    type Target = Span;
+
+    fn deref(&self) -> &Span {
+        &self.0
+    }
+}
+
+/// A name read in an expression.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ident {
+    pub name: String,
+    pub span: NodeSpan,
+}
+
+impl From<&str> for Ident {
+    fn from(name: &str) -> Self {
+        Ident {
+            name: name.to_string(),
+            span: NodeSpan::default(),
+        }
+    }
+}
+
+impl PartialEq<str> for Ident {
+    fn eq(&self, other: &str) -> bool {
+        self.name == other
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
 pub enum Node {
     // ===== Atoms =====
-    Ident(String),
+    Ident(Ident),
     Lit(Literal),
 
     // ===== Unary =====
@@ -54,7 +115,12 @@ pub enum Node {
 
     // ===== Flow =====
     Pipe(Box<Node>, Box<Node>),      // |>
+
+    // ===== Absence =====
+    Coalesce(Box<Node>, Box<Node>),  // ??
+
     Block(Block),
+    BlockExpr(BlockExpr),
     Local(Box<Node>),
     Ret(Ret),
     Define(Define),
@@ -66,77 +132,315 @@ pub enum Node {
     Call(Call)
 }
 
+/// Which variant a [`Node`] is, without its contents.
+///
+/// Cheap to copy, compare and hash, for counting or filtering nodes
+/// without matching every variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    Ident,
+    Lit,
+    Not,
+    Neg,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    Has,
+    Present,
+    Pipe,
+    Coalesce,
+    Block,
+    BlockExpr,
+    Local,
+    Ret,
+    Define,
+    DefineEmpty,
+    Copy,
+    Bind,
+    Guard,
+    Func,
+    Call,
+}
+
+impl NodeKind {
+    /// Stable snake_case name of the kind, e.g. `"block_expr"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            NodeKind::Ident => "ident",
+            NodeKind::Lit => "literal",
+            NodeKind::Not => "not",
+            NodeKind::Neg => "neg",
+            NodeKind::Add => "add",
+            NodeKind::Sub => "sub",
+            NodeKind::Mul => "mul",
+            NodeKind::Div => "div",
+            NodeKind::Mod => "mod",
+            NodeKind::Eq => "eq",
+            NodeKind::Ne => "ne",
+            NodeKind::Lt => "lt",
+            NodeKind::Le => "le",
+            NodeKind::Gt => "gt",
+            NodeKind::Ge => "ge",
+            NodeKind::And => "and",
+            NodeKind::Or => "or",
+            NodeKind::Has => "has",
+            NodeKind::Present => "present",
+            NodeKind::Pipe => "pipe",
+            NodeKind::Coalesce => "coalesce",
+            NodeKind::Block => "block",
+            NodeKind::BlockExpr => "block_expr",
+            NodeKind::Local => "local",
+            NodeKind::Ret => "ret",
+            NodeKind::Define => "define",
+            NodeKind::DefineEmpty => "define_empty",
+            NodeKind::Copy => "copy",
+            NodeKind::Bind => "bind",
+            NodeKind::Guard => "guard",
+            NodeKind::Func => "func",
+            NodeKind::Call => "call",
+        }
+    }
+}
+
+impl Node {
+    pub fn kind(&self) -> NodeKind {
+        match self {
+            Node::Ident(_) => NodeKind::Ident,
+            Node::Lit(_) => NodeKind::Lit,
+            Node::Not(_) => NodeKind::Not,
+            Node::Neg(_) => NodeKind::Neg,
+            Node::Add(..) => NodeKind::Add,
+            Node::Sub(..) => NodeKind::Sub,
+            Node::Mul(..) => NodeKind::Mul,
+            Node::Div(..) => NodeKind::Div,
+            Node::Mod(..) => NodeKind::Mod,
+            Node::Eq(..) => NodeKind::Eq,
+            Node::Ne(..) => NodeKind::Ne,
+            Node::Lt(..) => NodeKind::Lt,
+            Node::Le(..) => NodeKind::Le,
+            Node::Gt(..) => NodeKind::Gt,
+            Node::Ge(..) => NodeKind::Ge,
+            Node::And(..) => NodeKind::And,
+            Node::Or(..) => NodeKind::Or,
+            Node::Has(..) => NodeKind::Has,
+            Node::Present(..) => NodeKind::Present,
+            Node::Pipe(..) => NodeKind::Pipe,
+            Node::Coalesce(..) => NodeKind::Coalesce,
+            Node::Block(_) => NodeKind::Block,
+            Node::BlockExpr(_) => NodeKind::BlockExpr,
+            Node::Local(_) => NodeKind::Local,
+            Node::Ret(_) => NodeKind::Ret,
+            Node::Define(_) => NodeKind::Define,
+            Node::DefineEmpty(_) => NodeKind::DefineEmpty,
+            Node::Copy(_) => NodeKind::Copy,
+            Node::Bind(_) => NodeKind::Bind,
+            Node::Guard(_) => NodeKind::Guard,
+            Node::Func(_) => NodeKind::Func,
+            Node::Call(_) => NodeKind::Call,
+        }
+    }
+
+    /// Where the node was written, for nodes that record it.
+    ///
+    /// Statements, names, calls and block expressions record their span,
+    /// and an infix operator spans its two operands when both do.
+    /// Literals and prefix operators record none.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Node::Ident(ident) => Some(*ident.span),
+            Node::Lit(_) => None,
+
+            // The operator's own position is not recorded.
+            Node::Not(_) | Node::Neg(_) => None,
+
+            Node::Local(inner) => inner.span(),
+
+            Node::Add(l, r)
+            | Node::Sub(l, r)
+            | Node::Mul(l, r)
+            | Node::Div(l, r)
+            | Node::Mod(l, r)
+            | Node::Eq(l, r)
+            | Node::Ne(l, r)
+            | Node::Lt(l, r)
+            | Node::Le(l, r)
+            | Node::Gt(l, r)
+            | Node::Ge(l, r)
+            | Node::And(l, r)
+            | Node::Or(l, r)
+            | Node::Has(l, r)
+            | Node::Present(l, r)
+            | Node::Pipe(l, r)
+            | Node::Coalesce(l, r) => Some(l.span()?.merge(r.span()?)),
+
+            Node::Block(block) => Some(*block.span),
+            Node::BlockExpr(block) => Some(*block.span),
+            Node::Ret(ret) => Some(*ret.span),
+            Node::Define(def) => Some(*def.span),
+            Node::DefineEmpty(def) => Some(*def.span),
+            Node::Copy(copy) => Some(*copy.span),
+            Node::Bind(bind) => Some(*bind.span),
+            Node::Guard(guard) => Some(*guard.span),
+            Node::Func(func) => Some(*func.span),
+            Node::Call(call) => Some(*call.span),
+        }
+    }
+}
+
+/// Function definition `fn name :( params )( body ):`.
+///
+/// Canon allows exactly one body; a second `)(` is rejected by the parser
+/// as function chaining. The body runs statement by statement until a
+/// `ret` leaves it. A body that falls through its last statement returns
+/// `void`.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Func {
     pub name: String,
     pub params: Vec<Param>,
     pub body: Vec<Node>,
+    pub span: NodeSpan,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Call {
     pub callee: Box<Node>,
     pub args: Vec<Node>,
+    pub span: NodeSpan,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
     pub segments: Vec<BlockSegment>,
+    pub span: NodeSpan,
+}
+
+/// Block expression `:[ ... ]:`.
+///
+/// Runs its statements in a fresh scope and evaluates to its trailing
+/// expression, or `void` when there is none.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockExpr {
+    pub nodes: Vec<Node>,
+    pub value: Option<Box<Node>>,
+    pub span: NodeSpan,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlockSegment {
     pub nodes: Vec<Node>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Program {
     pub nodes: Vec<Node>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Param {
     pub name: String,
     pub default: Option<Node>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ret {
     pub value: Option<Box<Node>>,
+    pub span: NodeSpan,
+}
+
+/// Declared type of a binding, written as a leading type keyword:
+/// `num x = 42;`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Type {
+    Num,
+    Dec,
+    Flag,
+    Text,
+}
+
+impl Type {
+    /// The Druim spelling of the type.
+    pub fn name(self) -> &'static str {
+        match self {
+            Type::Num => "num",
+            Type::Dec => "dec",
+            Type::Flag => "flag",
+            Type::Text => "text",
+        }
+    }
 }
 
+/// `name = value;`, optionally annotated with a declared type.
+///
+/// A declared type is checked, not coerced: the value must already be
+/// of that type.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Define {
     pub name: String,
+    pub ty: Option<Type>,
     pub value: Box<Node>,
+    pub span: NodeSpan,
 }
 
+/// `name =;`, optionally annotated with a declared type.
+///
+/// The binding is `void` whatever its declared type.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DefineEmpty {
     pub name: String,
+    pub ty: Option<Type>,
+    pub span: NodeSpan,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Copy {
     pub name: String,
     pub target: String,
+    pub span: NodeSpan,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bind {
     pub name: String,
     pub target: String,
+    pub span: NodeSpan,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Guard {
     pub target: String,
     pub branches: Vec<GuardBranch>,
+    pub span: NodeSpan,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GuardBranch {
-    pub expr: Node
+    pub expr: Node,
+    pub span: NodeSpan,
 }
 
 
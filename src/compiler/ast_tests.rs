@@ -0,0 +1,31 @@
+use crate::compiler::ast::Program;
+use crate::compiler::lexer::Lexer;
+use crate::compiler::parser::Parser;
+
+fn parse_program(src: &str) -> Program {
+    let tokens = Lexer::new(src).tokenize().expect("lexing failed");
+    let mut parser = Parser::new(&tokens);
+    parser.parse_program().expect("failed to parse program")
+}
+
+#[test]
+fn program_round_trips_through_json() {
+    let program = parse_program(
+        "fn add :(a, b = -1)( ret a + b; ): x = add(1, 2); y =; z := x; :{ loc w ?= y : \"t\" : void; }:",
+    );
+
+    let json = serde_json::to_string(&program).expect("serialize failed");
+    let back: Program = serde_json::from_str(&json).expect("deserialize failed");
+
+    assert_eq!(back, program);
+}
+
+#[test]
+fn variants_are_tagged() {
+    let program = parse_program("x = 1;");
+
+    let json = serde_json::to_value(&program).expect("serialize failed");
+
+    assert_eq!(json["nodes"][0]["kind"], "Define");
+    assert_eq!(json["nodes"][0]["value"]["value"]["kind"], "Lit");
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use crate::compiler::error::{Diagnostic, Note, Severity, Source, Span};
 
 #[derive(Copy, Clone)]
@@ -10,28 +12,74 @@ enum Style {
     Plain,
 }
 
-#[cfg(feature = "ansi")]
-fn apply_ansi(style: Style, text: &str) -> String {
-    if !ansi_enabled() {
-        return text.to_string();
+/// Options controlling how diagnostics are rendered.
+///
+/// The default renders plain ASCII with tabs four columns wide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Emit ANSI color escape codes.
+    pub color: bool,
+
+    /// Colors used when `color` is on.
+    pub theme: Theme,
+
+    /// Number of columns a tab occupies in rendered source lines.
+    pub tab_width: usize,
+
+    /// Draw the gutter, location arrow and label lines with box-drawing
+    /// characters (`│`, `╭─▶`, `╰──`) instead of `|`, `-->` and `--`.
+    pub unicode_box: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            color: false,
+            theme: Theme::default(),
+            tab_width: 4,
+            unicode_box: false,
+        }
     }
+}
 
-    let code = match style {
-        Style::Error => "\x1b[38;5;88m",
-        Style::Warning => "\x1b[38;5;202m",
-        Style::Note => "\x1b[38;5;94m",
-        Style::Help => "\x1b[38;5;64m",
-        Style::Caret => "\x1b[38;5;135m",
-        Style::Plain => "",
-    };
+/// ANSI escape sequences used for each part of a diagnostic.
+///
+/// Each field is the sequence written before the styled text; the
+/// foreground color is reset after it. An empty string leaves that part
+/// uncolored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub error: &'static str,
+    pub warning: &'static str,
+    pub note: &'static str,
+    pub help: &'static str,
+    pub caret: &'static str,
+}
 
-    if code.is_empty() {
-        text.to_string()
-    } else {
-        format!("{code}{text}\x1b[39m")
+impl Default for Theme {
+    /// 256-color palette tuned for dark terminals.
+    fn default() -> Self {
+        Self {
+            error: "\x1b[38;5;88m",
+            warning: "\x1b[38;5;202m",
+            note: "\x1b[38;5;94m",
+            help: "\x1b[38;5;64m",
+            caret: "\x1b[38;5;135m",
+        }
     }
 }
 
+impl RenderOptions {
+    /// Options used by `render`: color follows the `DRUIM_ANSI`
+    /// environment variable when the `ansi` feature is enabled, and is
+    /// off otherwise.
+    pub fn from_env() -> Self {
+        Self {
+            color: ansi_enabled(),
+            ..Self::default()
+        }
+    }
+}
 
 #[cfg(feature = "ansi")]
 fn ansi_enabled() -> bool {
@@ -43,28 +91,80 @@ fn ansi_enabled() -> bool {
     false
 }
 
-#[cfg(not(feature = "ansi"))]
-fn apply_ansi(_style: Style, text: &str) -> String {
-    text.to_string()
+fn apply_ansi(opts: &RenderOptions, style: Style, text: &str) -> String {
+    if !opts.color {
+        return text.to_string();
+    }
+
+    let theme = &opts.theme;
+    let code = match style {
+        Style::Error => theme.error,
+        Style::Warning => theme.warning,
+        Style::Note => theme.note,
+        Style::Help => theme.help,
+        Style::Caret => theme.caret,
+        Style::Plain => "",
+    };
+
+    if code.is_empty() {
+        text.to_string()
+    } else {
+        format!("{code}{text}\x1b[39m")
+    }
 }
 
-fn write_styled(out: &mut String, style: Style, text: &str) {
-    let rendered = apply_ansi(style, text);
-    out.push_str(&rendered);
+fn write_styled(out: &mut dyn fmt::Write, opts: &RenderOptions, style: Style, text: &str) -> fmt::Result {
+    let rendered = apply_ansi(opts, style, text);
+    out.write_str(&rendered)
 }
 
+impl RenderOptions {
+    // Gutter bar between line numbers and source text.
+    fn bar(&self) -> &'static str {
+        if self.unicode_box { "│" } else { "|" }
+    }
+
+    // Arrow in front of the ` --> line, column` location.
+    fn arrow(&self) -> &'static str {
+        if self.unicode_box { "╭─▶" } else { "-->" }
+    }
+
+    // Underline of `len` columns for a secondary label.
+    fn label_line(&self, len: usize) -> String {
+        if self.unicode_box {
+            format!("╰{}", "─".repeat(len.saturating_sub(1)))
+        } else {
+            "-".repeat(len)
+        }
+    }
+
+    // Source lines are printed with tabs expanded so that caret offsets,
+    // which are computed with the same rule, line up with the text above.
+    fn expand_tabs(&self, text: &str) -> String {
+        text.replace('\t', &" ".repeat(self.tab_width))
+    }
+
+    // Rendered width of the bytes `from..to` of a source line.
+    // Every character occupies one column, except tabs.
+    fn rendered_width(&self, text: &str, from: usize, to: usize) -> usize {
+        text.char_indices()
+            .filter(|(i, _)| *i >= from && *i < to)
+            .map(|(_, c)| if c == '\t' { self.tab_width } else { 1 })
+            .sum()
+    }
+}
 
 // Renders a source span and returns `start_col`, the zero-based column
 // of the first caret derived solely from `span.start`.
 // This value is authoritative and must never be influenced by
 // secondary labels, notes, or other annotations.
-fn render_span_block(out: &mut String, source: &Source, span: Span) -> usize {
+fn render_span_block(out: &mut dyn fmt::Write, opts: &RenderOptions, source: &Source, span: Span) -> Result<usize, fmt::Error> {
     let (line, col) = source.line_col(span.start);
-    write_styled(
-        out,
-        Style::Plain,
-        &format!(" --> line {}, column {}\n", line, col),
-);
+    let location = match source.name() {
+        Some(name) => format!(" {} {}:{}:{}\n", opts.arrow(), name, line, col),
+        None => format!(" {} line {}, column {}\n", opts.arrow(), line, col),
+    };
+    write_styled(out, opts, Style::Plain, &location)?;
 
 
     let line_text = source.line_text(line);
@@ -72,20 +172,23 @@ fn render_span_block(out: &mut String, source: &Source, span: Span) -> usize {
 
     write_styled(
         out,
+        opts,
         Style::Plain,
-        &format!("{:>width$} |\n", "", width = gutter_width),
-    );
+        &format!("{:>width$} {}\n", "", opts.bar(), width = gutter_width),
+    )?;
 
     write_styled(
         out,
+        opts,
         Style::Plain,
         &format!(
-            "{:>width$} | {}\n",
+            "{:>width$} {} {}\n",
             line,
-            line_text,
+            opts.bar(),
+            opts.expand_tabs(line_text),
             width = gutter_width
         ),
-    );
+    )?;
 
 
     let line_len = line_text.len();
@@ -94,7 +197,7 @@ fn render_span_block(out: &mut String, source: &Source, span: Span) -> usize {
     let start_col = if span_starts_on_newline {
         line_len
     } else {
-        (col.saturating_sub(1)).min(line_len)
+        (span.start - source.line_start(line)).min(line_len)
     };
 
     let width = span
@@ -103,42 +206,44 @@ fn render_span_block(out: &mut String, source: &Source, span: Span) -> usize {
         .min(line_len.saturating_sub(start_col))
         .max(1);
 
+    // Convert byte columns into rendered (tab-expanded) columns.
+    let width = opts.rendered_width(line_text, start_col, start_col + width).max(1);
+    let start_col = opts.rendered_width(line_text, 0, start_col);
+
     // Prefix: gutter + bar + spaces before caret (PLAIN)
-    let mut prefix = format!("{:>width$} | ", "", width = gutter_width);
+    let mut prefix = format!("{:>width$} {} ", "", opts.bar(), width = gutter_width);
 
     for _ in 0..start_col {
         prefix.push(' ');
     }
 
-    write_styled(out, Style::Plain, &prefix);
+    write_styled(out, opts, Style::Plain, &prefix)?;
 
     // Caret run: ONLY the carets (STYLED)
     let carets = "^".repeat(width);
-    write_styled(out, Style::Caret, &carets);
+    write_styled(out, opts, Style::Caret, &carets)?;
 
     // Newline (PLAIN)
-    out.push('\n');
+    out.write_char('\n')?;
 
-
-
-    start_col
+    Ok(start_col)
 }
 
 fn render_secondary_labels(
-    out: &mut String,
+    out: &mut dyn fmt::Write,
+    opts: &RenderOptions,
     source: &Source,
     primary_span: Span,
-    secondary: &[(Span, &'static str)],
-) {
+    secondary: &[(Span, String)],
+) -> fmt::Result {
     if secondary.is_empty() {
-        return;
+        return Ok(());
     }
 
     let (line, _) = source.line_col(primary_span.start);
     let gutter_width = format!("{}", line).len();
 
     // Compute primary caret start column (same logic as render_span_block)
-    let (_, col) = source.line_col(primary_span.start);
     let line_text = source.line_text(line);
     let line_len = line_text.len();
     let span_starts_on_newline = source.is_newline_at(primary_span.start);
@@ -146,14 +251,25 @@ fn render_secondary_labels(
     let start_col = if span_starts_on_newline {
         line_len
     } else {
-        col.saturating_sub(1).min(line_len.saturating_sub(1))
+        (primary_span.start - source.line_start(line)).min(line_len.saturating_sub(1))
     };
+    let start_col = opts.rendered_width(line_text, 0, start_col);
 
-    // Label rule you locked in:
+    // Label rule you locked in (labels on the primary line):
     // - dashline ends 1 column before first caret
     // - max 8 dashes (shorter if near start)
     // - then single space, then label
-    for (_span, label) in secondary {
+    //
+    // Labels on any other line are rendered against that line's own
+    // source text, with dashes under the label's own span.
+    for (span, label) in secondary {
+        let (label_line, _) = source.line_col(span.start);
+
+        if label_line != line {
+            render_label_on_own_line(out, opts, source, *span, label_line, label, gutter_width)?;
+            continue;
+        }
+
         if start_col == 0 {
             continue;
         }
@@ -163,31 +279,86 @@ fn render_secondary_labels(
 
         write_styled(
             out,
+            opts,
             Style::Plain,
-            &format!("{:>width$} | ", "", width = gutter_width),
-        );
+            &format!("{:>width$} {} ", "", opts.bar(), width = gutter_width),
+        )?;
 
 
         for _ in 0..dash_start {
-            out.push(' ');
-        }
-        for _ in 0..dash_len {
-            out.push('-');
+            out.write_char(' ')?;
         }
+        out.write_str(&opts.label_line(dash_len))?;
 
-        out.push(' ');
-        write_styled(out, Style::Plain, label);
-        out.push('\n');
+        out.write_char(' ')?;
+        write_styled(out, opts, Style::Plain, label)?;
+        out.write_char('\n')?;
     }
+
+    Ok(())
 }
 
-fn render_note(out: &mut String, note: &Note, source: &Source) {
-    let severity = match note.severity {
+// Renders a secondary label whose span lives on a different line than
+// the primary span: the label's line is printed, then dashes under the
+// label's own columns.
+fn render_label_on_own_line(
+    out: &mut dyn fmt::Write,
+    opts: &RenderOptions,
+    source: &Source,
+    span: Span,
+    line: usize,
+    label: &str,
+    primary_gutter_width: usize,
+) -> fmt::Result {
+    let gutter_width = primary_gutter_width.max(format!("{}", line).len());
+    let line_text = source.line_text(line);
+    let line_len = line_text.len();
+
+    write_styled(
+        out,
+        opts,
+        Style::Plain,
+        &format!(
+            "{:>width$} {} {}\n",
+            line,
+            opts.bar(),
+            opts.expand_tabs(line_text),
+            width = gutter_width
+        ),
+    )?;
+
+    let start_col = (span.start - source.line_start(line)).min(line_len);
+    let dash_len = span
+        .end
+        .saturating_sub(span.start)
+        .min(line_len.saturating_sub(start_col))
+        .max(1);
+    let dash_len = opts.rendered_width(line_text, start_col, start_col + dash_len).max(1);
+    let start_col = opts.rendered_width(line_text, 0, start_col);
+
+    let mut prefix = format!("{:>width$} {} ", "", opts.bar(), width = gutter_width);
+    for _ in 0..start_col {
+        prefix.push(' ');
+    }
+    write_styled(out, opts, Style::Plain, &prefix)?;
+
+    out.write_str(&opts.label_line(dash_len))?;
+    out.write_char(' ')?;
+    write_styled(out, opts, Style::Plain, label)?;
+    out.write_char('\n')
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
         Severity::Note => "note",
         Severity::Help => "help",
-        Severity::Warning => "warning",
-        Severity::Error => "error",
-    };
+    }
+}
+
+fn render_note(out: &mut dyn fmt::Write, opts: &RenderOptions, note: &Note, source: &Source) -> fmt::Result {
+    let severity = severity_name(note.severity);
 
     let style = match note.severity {
         Severity::Error => Style::Error,
@@ -198,30 +369,50 @@ fn render_note(out: &mut String, note: &Note, source: &Source) {
 
     write_styled(
         out,
+        opts,
         style,
         &format!("{severity}: {}\n", note.message),
-    );
+    )?;
 
 
     let span = match note.span {
         Some(s) => s,
-        None => return,
+        None => return Ok(()),
     };
 
-    render_span_block(out, source, span);
+    render_span_block(out, opts, source, span)?;
+    Ok(())
 }
 
 /// Render a diagnostic into a human-readable message.
-/// This is the ONLY place where user-facing formatting occurs.
+///
+/// Color follows [`RenderOptions::from_env`].
 pub fn render(diagnostic: &Diagnostic, source: &Source) -> String {
+    render_with(diagnostic, source, &RenderOptions::from_env())
+}
+
+/// Render a diagnostic with explicit options, ignoring the environment.
+pub fn render_with(diagnostic: &Diagnostic, source: &Source, opts: &RenderOptions) -> String {
     let mut out = String::new();
+    write_diagnostic(&mut out, opts, diagnostic, source).expect("writing to a String cannot fail");
+    out
+}
 
-    let severity = match diagnostic.severity {
-        Severity::Error => "error",
-        Severity::Warning => "warning",
-        Severity::Note => "note",
-        Severity::Help => "help",
-    };
+/// Render a diagnostic into any `fmt::Write` sink.
+///
+/// Color follows [`RenderOptions::from_env`].
+pub fn render_to(out: &mut dyn fmt::Write, diagnostic: &Diagnostic, source: &Source) -> fmt::Result {
+    write_diagnostic(out, &RenderOptions::from_env(), diagnostic, source)
+}
+
+// This is the ONLY place where user-facing formatting occurs.
+fn write_diagnostic(
+    out: &mut dyn fmt::Write,
+    opts: &RenderOptions,
+    diagnostic: &Diagnostic,
+    source: &Source,
+) -> fmt::Result {
+    let severity = severity_name(diagnostic.severity);
 
     let style = match diagnostic.severity {
         Severity::Error => Style::Error,
@@ -230,50 +421,277 @@ pub fn render(diagnostic: &Diagnostic, source: &Source) -> String {
         Severity::Help => Style::Help,
     };
 
-    write_styled(
-        &mut out,
-        style,
-        &format!("{severity}: {}\n", diagnostic.message),
-    );
+    let header = match diagnostic.code {
+        Some(code) => format!("{severity}[{code}]: {}\n", diagnostic.message),
+        None => format!("{severity}: {}\n", diagnostic.message),
+    };
+
+    write_styled(out, opts, style, &header)?;
 
     // Top-level Note/Help diagnostics:
     // - If span is empty (start==end), do not render source.
     // - Otherwise, render source block.
     if matches!(diagnostic.severity, Severity::Note | Severity::Help) {
         if diagnostic.span.start == diagnostic.span.end {
-            return out;
+            return Ok(());
         }
-        render_span_block(&mut out, source, diagnostic.span);
-        return out;
+        render_span_block(out, opts, source, diagnostic.span)?;
+        return Ok(());
     }
 
     // ----- Errors / Warnings only below -----
 
-    render_span_block(&mut out, source, diagnostic.span);
+    render_span_block(out, opts, source, diagnostic.span)?;
 
     // Secondary labels (must render after the primary caret block)
     render_secondary_labels(
-        &mut out,
+        out,
+        opts,
         source,
         diagnostic.span,
         &diagnostic.secondary,
-    );
+    )?;
 
     // Notes
     for note in &diagnostic.notes {
-        out.push('\n');
-        render_note(&mut out, note, source);
+        out.write_char('\n')?;
+        render_note(out, opts, note, source)?;
     }
 
     // Help (always last, always separated)
-    if let Some(help) = diagnostic.help {
-        out.push('\n');
+    if let Some(help) = &diagnostic.help {
+        out.write_char('\n')?;
         write_styled(
-            &mut out, 
+            out,
+            opts, 
             Style::Plain, 
             &format!("help: {}\n", help)
-        );
+        )?;
+    }
+
+    // Suggested fix, shown applied to its source line
+    if let Some((span, replacement)) = &diagnostic.suggestion {
+        out.write_char('\n')?;
+        render_suggestion(out, opts, source, *span, replacement)?;
+    }
+
+    Ok(())
+}
+
+// Renders `help: replace `old` with `new`` followed by the source line
+// with the replacement applied and `~` under the inserted text.
+// Spans that cross a line boundary only get the help line.
+fn render_suggestion(
+    out: &mut dyn fmt::Write,
+    opts: &RenderOptions,
+    source: &Source,
+    span: Span,
+    replacement: &str,
+) -> fmt::Result {
+    let (line, _) = source.line_col(span.start);
+    let line_text = source.line_text(line);
+    let line_start = source.line_start(line);
+
+    let start = (span.start - line_start).min(line_text.len());
+    let end = span.end.saturating_sub(line_start);
+
+    if end > line_text.len() {
+        write_styled(
+            out,
+            opts,
+            Style::Help,
+            &format!("help: replace with `{}`\n", replacement),
+        )?;
+        return Ok(());
+    }
+
+    write_styled(
+        out,
+        opts,
+        Style::Help,
+        &format!(
+            "help: replace `{}` with `{}`\n",
+            source.snippet(span),
+            replacement
+        ),
+    )?;
+
+    let fixed = format!("{}{}{}", &line_text[..start], replacement, &line_text[end..]);
+    let gutter_width = format!("{}", line).len();
+    let pad = opts.rendered_width(line_text, 0, start);
+    let width = opts.rendered_width(replacement, 0, replacement.len()).max(1);
+
+    write_styled(
+        out,
+        opts,
+        Style::Plain,
+        &format!("{:>width$} {}\n", "", opts.bar(), width = gutter_width),
+    )?;
+    write_styled(
+        out,
+        opts,
+        Style::Plain,
+        &format!("{:>width$} {} {}\n", line, opts.bar(), opts.expand_tabs(&fixed), width = gutter_width),
+    )?;
+    write_styled(
+        out,
+        opts,
+        Style::Plain,
+        &format!("{:>width$} {} ", "", opts.bar(), width = gutter_width),
+    )?;
+    write_styled(
+        out,
+        opts,
+        Style::Help,
+        &format!("{}{}\n", " ".repeat(pad), "~".repeat(width)),
+    )
+}
+
+/// Render a batch of diagnostics in source order.
+///
+/// Diagnostics are sorted by `span.start` (ties keep their input order)
+/// and separated by a blank line. Exact repeats (same severity, message
+/// and span) are rendered once. When any of them is an error, a closing
+/// summary line reports how many errors were found.
+pub fn render_all(diagnostics: &[Diagnostic], source: &Source) -> String {
+    let mut sorted: Vec<&Diagnostic> = Vec::with_capacity(diagnostics.len());
+    for diagnostic in diagnostics {
+        if !sorted.iter().any(|seen| is_repeat(seen, diagnostic)) {
+            sorted.push(diagnostic);
+        }
     }
+    sorted.sort_by_key(|d| d.span.start);
+
+    let opts = &RenderOptions::from_env();
+    let mut out = String::new();
+
+    for (i, diagnostic) in sorted.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        write_diagnostic(&mut out, opts, diagnostic, source)
+            .expect("writing to a String cannot fail");
+    }
+
+    let errors = sorted
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+
+    if errors > 0 {
+        let plural = if errors == 1 { "" } else { "s" };
+        out.push('\n');
+        write_styled(
+            &mut out,
+            opts,
+            Style::Error,
+            &format!("error: aborting due to {errors} previous error{plural}\n"),
+        )
+        .expect("writing to a String cannot fail");
+    }
+
+    out
+}
 
+/// Whether two diagnostics report the same thing at the same place.
+fn is_repeat(a: &Diagnostic, b: &Diagnostic) -> bool {
+    a.severity == b.severity && a.message == b.message && a.span == b.span
+}
+
+/// Render a diagnostic as a JSON object for editor and LSP integrations.
+///
+/// Line and column values are one-based and identical to the ones in the
+/// `render` header. Spans are byte offsets into the source.
+#[cfg(feature = "json")]
+pub fn render_json(diagnostic: &Diagnostic, source: &Source) -> String {
+    let mut out = String::from("{");
+
+    out.push_str(&format!(
+        "\"severity\":{},\"message\":{},",
+        json_string(severity_name(diagnostic.severity)),
+        json_string(&diagnostic.message),
+    ));
+    out.push_str(&json_location(source, diagnostic.span));
+
+    out.push_str(",\"code\":");
+    match diagnostic.code {
+        Some(code) => out.push_str(&json_string(code)),
+        None => out.push_str("null"),
+    }
+
+    out.push_str(",\"help\":");
+    match &diagnostic.help {
+        Some(help) => out.push_str(&json_string(help)),
+        None => out.push_str("null"),
+    }
+
+    out.push_str(",\"suggestion\":");
+    match &diagnostic.suggestion {
+        Some((span, replacement)) => out.push_str(&format!(
+            "{{\"replacement\":{},{}}}",
+            json_string(replacement),
+            json_location(source, *span),
+        )),
+        None => out.push_str("null"),
+    }
+
+    out.push_str(",\"secondary\":[");
+    for (i, (span, label)) in diagnostic.secondary.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&format!(
+            "{{\"label\":{},{}}}",
+            json_string(label),
+            json_location(source, *span),
+        ));
+    }
+
+    out.push_str("],\"notes\":[");
+    for (i, note) in diagnostic.notes.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&format!(
+            "{{\"severity\":{},\"message\":{}",
+            json_string(severity_name(note.severity)),
+            json_string(&note.message),
+        ));
+        if let Some(span) = note.span {
+            out.push(',');
+            out.push_str(&json_location(source, span));
+        }
+        out.push('}');
+    }
+    out.push_str("]}");
+
+    out
+}
+
+#[cfg(feature = "json")]
+fn json_location(source: &Source, span: Span) -> String {
+    let (line, column) = source.line_col(span.start);
+    format!(
+        "\"span\":{{\"start\":{},\"end\":{}}},\"line\":{},\"column\":{}",
+        span.start, span.end, line, column,
+    )
+}
+
+#[cfg(feature = "json")]
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
     out
 }
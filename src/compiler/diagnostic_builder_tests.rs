@@ -11,11 +11,13 @@ mod tests {
         // Manual construction (ground truth)
         let manual = Diagnostic {
             severity: Severity::Error,
+            code: None,
             message: "unexpected token".to_string(),
             span,
             help: None,
             secondary: vec![],
             notes: vec![],
+            suggestion: None,
         };
 
         // Builder construction
@@ -37,11 +39,13 @@ mod tests {
         // Manual construction (ground truth)
         let manual = Diagnostic {
             severity: Severity::Error,
+            code: None,
             message: "unknown variable `qty`".to_string(),
             span: primary_span,
             help: None,
-            secondary: vec![(secondary_span, "defined here")],
+            secondary: vec![(secondary_span, "defined here".into())],
             notes: vec![],
+            suggestion: None,
         };
 
         // Builder construction
@@ -70,11 +74,13 @@ mod tests {
         // Manual construction (ground truth)
         let manual = Diagnostic {
             severity: Severity::Error,
+            code: None,
             message: "unknown variable `qty`".to_string(),
             span: primary_span,
             help: None,
             secondary: vec![],
             notes: vec![note.clone()],
+            suggestion: None,
         };
 
         // Builder construction
@@ -95,11 +101,13 @@ mod tests {
         // Manual construction (ground truth)
         let manual = Diagnostic {
             severity: Severity::Error,
+            code: None,
             message: "expected expression".to_string(),
             span,
-            help: Some("expressions cannot be empty"),
+            help: Some("expressions cannot be empty".into()),
             secondary: vec![],
             notes: vec![],
+            suggestion: None,
         };
 
         // Builder construction
@@ -129,5 +137,41 @@ mod tests {
             
         assert_eq!(a, b);
     }
-    
-}
\ No newline at end of file
+
+    #[test]
+    fn bulk_builders_match_repeated_single_builders() {
+        let source = Source::new("a = b + c;".to_string());
+        let span = Span { start: 4, end: 9 };
+
+        let single = Diagnostic::error("bad sum", span)
+            .with_secondary(Span { start: 4, end: 5 }, "left")
+            .with_secondary(Span { start: 8, end: 9 }, "right")
+            .with_note(Note::note("first", None))
+            .with_note(Note::help("second", None));
+
+        let bulk = Diagnostic::error("bad sum", span)
+            .with_secondaries([
+                (Span { start: 4, end: 5 }, "left"),
+                (Span { start: 8, end: 9 }, "right"),
+            ])
+            .with_notes(vec![Note::note("first", None), Note::help("second", None)]);
+
+        assert_eq!(bulk, single);
+        assert_eq!(render(&bulk, &source), render(&single, &source));
+    }
+
+    #[test]
+    fn dynamic_help_and_labels_render() {
+        let source = Source::new("total = qty * 2;".to_string());
+        let name = "qty";
+
+        let diag = Diagnostic::error(format!("unknown variable `{name}`"), Span { start: 8, end: 11 })
+            .with_secondary(Span { start: 8, end: 11 }, format!("`{name}` is read here"))
+            .with_help(format!("define `{name}` before `total`"));
+
+        let rendered = render(&diag, &source);
+
+        assert!(rendered.contains("`qty` is read here"), "{rendered}");
+        assert!(rendered.contains("help: define `qty` before `total`"), "{rendered}");
+    }
+}
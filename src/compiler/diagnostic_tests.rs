@@ -1,4 +1,4 @@
-use crate::compiler::diagnostic::render;
+use crate::compiler::diagnostic::{render, render_all, render_to, render_with, RenderOptions, Theme};
 use crate::compiler::error::{Diagnostic, Severity, Source, Span, Note};
 
 fn assert_render(diag: &Diagnostic, source: &Source, expected: &str) {
@@ -12,30 +12,65 @@ fn assert_render(diag: &Diagnostic, source: &Source, expected: &str) {
     );
 }
 
+fn assert_render_all(diags: &[Diagnostic], source: &Source, expected: &str) {
+    let got = render_all(diags, source);
+    assert_eq!(
+        got,
+        expected,
+        "\n--- expected ---\n{}\n--- got ---\n{}\n",
+        expected,
+        got
+    );
+}
+
+/// Compare `render` output against a golden file under `tests/golden/`.
+///
+/// Set `DRUIM_UPDATE_GOLDEN=1` to rewrite the file from the current output
+/// instead of comparing.
+fn assert_render_golden(diag: &Diagnostic, source: &Source, path: &str) {
+    let got = render(diag, source);
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+
+    if std::env::var_os("DRUIM_UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &got).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "cannot read golden file {}: {e}\n\
+             (run with DRUIM_UPDATE_GOLDEN=1 to create it)",
+            path.display()
+        )
+    });
+
+    assert_eq!(
+        got,
+        expected,
+        "\n--- expected ({}) ---\n{}\n--- got ---\n{}\n",
+        path.display(),
+        expected,
+        got
+    );
+}
+
 #[test]
 fn render_simple_error_single_caret() {
     let source = Source::new("let x = ;\n".to_string());
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "unexpected token".to_string(),
         span: Span { start: 8, end: 9 },
         help: None,
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
-    assert_render(
-        &diag,
-        &source,
-        "\
-error: unexpected token
- --> line 1, column 9
-  |
-1 | let x = ;
-  |         ^
-",
-    );
+    assert_render_golden(&diag, &source, "tests/golden/simple_error_single_caret.txt");
 }
 
 
@@ -45,11 +80,13 @@ fn render_error_with_help() {
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "expected expression".to_string(),
         span: Span { start: 10, end: 10 },
-        help: Some("expressions cannot be empty"),
+        help: Some("expressions cannot be empty".into()),
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -75,11 +112,13 @@ fn render_multi_character_span() {
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "invalid number".to_string(),
         span: Span { start: 12, end: 15 }, // highlights "123"
         help: None,
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -119,11 +158,13 @@ line 11
     // 9 * 7 = 63, so line 10 starts at byte 63
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "invalid syntax".to_string(),
         span: Span { start: 63, end: 66 }, // highlights "bad"
         help: None,
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -145,11 +186,13 @@ fn render_warning_severity() {
 
     let diag = Diagnostic {
         severity: Severity::Warning,
+        code: None,
         message: "unused variable".to_string(),
         span: Span { start: 4, end: 5 }, // highlights "x"
         help: None,
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -171,11 +214,13 @@ fn render_span_at_column_one() {
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "unexpected identifier".to_string(),
         span: Span { start: 0, end: 4 }, // highlights "oops"
         help: None,
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -197,12 +242,14 @@ fn render_span_at_end_of_line_clamped() {
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "unexpected end of input".to_string(),
         // Start at the last character ('2'), end goes past the line
         span: Span { start: 9, end: 20 }, // start on '2', not '\n'
         help: None,
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -224,11 +271,13 @@ fn render_note_without_source_span() {
 
     let diag = Diagnostic {
         severity: Severity::Note,
+        code: None,
         message: "this value is inferred".to_string(),
         span: Span { start: 0, end: 0 }, // ignored for note-only diagnostics
         help: None,
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
     let got = render(&diag, &source);
@@ -259,11 +308,13 @@ let c = 3;
     // "let b = ;" -> ';' is at byte 18
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "expected expression".to_string(),
         span: Span { start: 19, end: 20 },
-        help: Some("expressions cannot be empty"),
+        help: Some("expressions cannot be empty".into()),
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -293,15 +344,17 @@ let price = 10;
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "unknown variable `qty`".to_string(),
         span: Span { start: 20, end: 23 }, // "qty"
         help: None,
         secondary: vec![(
             Span { start: 11 , end: 19 },
-            "defined here",
+            "defined here".into(),
         )],
         notes: vec![],
 
+        suggestion: None,
     };
 
     assert_render(
@@ -331,14 +384,16 @@ let tax = 2;
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "unknown variables".to_string(),
         span: Span { start: 20, end: 29 }, // "qty + tax"
         help: None,
         secondary: vec![
-            (Span { start: 12, end: 17 }, "defined here"), // price
-            (Span { start: 33, end: 36 }, "defined here"), // tax
+            (Span { start: 12, end: 17 }, "defined here".into()), // price
+            (Span { start: 51, end: 54 }, "defined here".into()), // tax, line 3
         ],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -351,7 +406,8 @@ error: unknown variables
 1 | let total = price * qty + tax;
   |                     ^^^^^^^^^
   |             -------- defined here
-  |             -------- defined here
+3 | let tax = 2;
+  |     --- defined here
 ",
     );
 }
@@ -362,6 +418,7 @@ fn render_error_with_note_and_help() {
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "unknown variable `y`".to_string(),
         span: Span { start: 4, end: 5 },
         help: None,
@@ -378,23 +435,10 @@ fn render_error_with_note_and_help() {
                 span: None,
             },
         ],
+        suggestion: None,
     };
 
-    assert_render(
-        &diag,
-        &source,
-        "\
-error: unknown variable `y`
- --> line 1, column 5
-  |
-1 | x = y;
-  |     ^
-
-note: `y` must be declared before use
-
-help: try defining `y` earlier in the file
-",
-    );
+    assert_render_golden(&diag, &source, "tests/golden/error_with_note_and_help.txt");
 }
 
 #[test]
@@ -409,6 +453,7 @@ let price = 10;
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "unknown variable `qty`".to_string(),
         span: Span { start: 20, end: 23 },
         help: None,
@@ -420,6 +465,7 @@ let price = 10;
                 span: Some(Span { start: 12, end: 17 }),
             }
         ],
+        suggestion: None,
     };
 
     assert_render(
@@ -453,9 +499,10 @@ let price = 10;
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "unknown variable `qty`".to_string(),
         span: Span { start: 20, end: 23 }, // qty
-        help: Some("declare `qty` before use"),
+        help: Some("declare `qty` before use".into()),
         secondary: vec![],
         notes: vec![
             Note {
@@ -469,6 +516,7 @@ let price = 10;
                 span: None,
             },
         ],
+        suggestion: None,
     };
 
     assert_render(
@@ -500,11 +548,13 @@ fn caret_renders_for_zero_width_span() {
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "test".to_string(),
         span: Span { start: 1, end: 1 },
         help: None,
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -526,11 +576,13 @@ fn caret_clamps_to_line_end() {
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "test".to_string(),
         span: Span { start: 1, end: 99 },
         help: None,
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -552,11 +604,13 @@ fn span_starting_on_newline_renders_at_eol() {
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "test".to_string(),
         span: Span { start: 3, end: 3 }, // '\n'
         help: None,
         secondary: vec![],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -578,11 +632,13 @@ fn secondary_labels_do_not_shift_caret() {
 
     let diag = Diagnostic {
         severity: Severity::Error,
+        code: None,
         message: "unknown variable".to_string(),
         span: Span { start: 8, end: 9 },
         help: None,
-        secondary: vec![(Span { start: 4, end: 5 }, "defined here")],
+        secondary: vec![(Span { start: 4, end: 5 }, "defined here".into())],
         notes: vec![],
+        suggestion: None,
     };
 
     assert_render(
@@ -599,9 +655,533 @@ error: unknown variable
     );
 }
 
+#[test]
+fn secondary_label_on_later_line_uses_its_own_line() {
+    let source = Source::new(
+        "\
+total = price * qty;
+qty_total = 10;
+"
+        .to_string(),
+    );
 
+    let diag = Diagnostic {
+        severity: Severity::Error,
+        code: None,
+        message: "unknown variable `qty`".to_string(),
+        span: Span { start: 16, end: 19 }, // "qty"
+        help: None,
+        secondary: vec![(Span { start: 21, end: 30 }, "similar name defined here".into())], // "qty_total"
+        notes: vec![],
+        suggestion: None,
+    };
 
+    assert_render(
+        &diag,
+        &source,
+        "\
+error: unknown variable `qty`
+ --> line 1, column 17
+  |
+1 | total = price * qty;
+  |                 ^^^
+2 | qty_total = 10;
+  | --------- similar name defined here
+",
+    );
+}
 
+#[test]
+fn caret_aligns_under_tab_indented_statement() {
+    // "\tx = ;" -> ';' is at byte 5, rendered column 8 once the tab expands.
+    let source = Source::new("\tx = ;\n".to_string());
 
+    let diag = Diagnostic {
+        severity: Severity::Error,
+        code: None,
+        message: "invalid define statement".to_string(),
+        span: Span { start: 5, end: 6 },
+        help: None,
+        secondary: vec![],
+        notes: vec![],
+        suggestion: None,
+    };
+
+    assert_render(
+        &diag,
+        &source,
+        "\
+error: invalid define statement
+ --> line 1, column 6
+  |
+1 |     x = ;
+  |         ^
+",
+    );
+}
+
+#[test]
+fn caret_counts_characters_not_bytes() {
+    // 'ï' is two bytes: n0 a1 ï2..4 v4 e5 ' '6 '='7 ' '8 x9
+    let source = Source::new("naïve = x;\n".to_string());
+
+    let diag = Diagnostic {
+        severity: Severity::Error,
+        code: None,
+        message: "invalid define statement".to_string(),
+        span: Span { start: 9, end: 10 },
+        help: None,
+        secondary: vec![],
+        notes: vec![],
+        suggestion: None,
+    };
+
+    assert_render(
+        &diag,
+        &source,
+        "\
+error: invalid define statement
+ --> line 1, column 9
+  |
+1 | naïve = x;
+  |         ^
+",
+    );
+}
+
+#[test]
+fn single_multibyte_character_gets_one_caret() {
+    let source = Source::new("naïve = x;\n".to_string());
+
+    let diag = Diagnostic {
+        severity: Severity::Error,
+        code: None,
+        message: "unexpected character".to_string(),
+        span: Span { start: 2, end: 4 }, // 'ï'
+        help: None,
+        secondary: vec![],
+        notes: vec![],
+        suggestion: None,
+    };
+
+    assert_render(
+        &diag,
+        &source,
+        "\
+error: unexpected character
+ --> line 1, column 3
+  |
+1 | naïve = x;
+  |   ^
+",
+    );
+}
+
+#[test]
+fn named_source_prints_name_in_header() {
+    let source = Source::with_name("let x = ;\n".to_string(), "src/main.dr");
+
+    let diag = Diagnostic {
+        severity: Severity::Error,
+        code: None,
+        message: "unexpected token".to_string(),
+        span: Span { start: 8, end: 9 },
+        help: None,
+        secondary: vec![],
+        notes: vec![],
+        suggestion: None,
+    };
+
+    assert_render(
+        &diag,
+        &source,
+        "\
+error: unexpected token
+ --> src/main.dr:1:9
+  |
+1 | let x = ;
+  |         ^
+",
+    );
+}
+
+#[test]
+fn coded_error_prints_code_in_header() {
+    let source = Source::new("x = 1\n".to_string());
+
+    let diag = Diagnostic::error("unterminated define statement", Span { start: 4, end: 5 })
+        .with_code("E0001");
+
+    assert_render(
+        &diag,
+        &source,
+        "\
+error[E0001]: unterminated define statement
+ --> line 1, column 5
+  |
+1 | x = 1
+  |     ^
+",
+    );
+}
+
+#[test]
+fn render_all_sorts_by_position_and_summarizes() {
+    let source = Source::new("a = ;\nb = ;\n".to_string());
 
+    let diags = vec![
+        Diagnostic::error("second", Span { start: 10, end: 11 }),
+        Diagnostic::error("first", Span { start: 4, end: 5 }),
+    ];
 
+    assert_render_all(
+        &diags,
+        &source,
+        "\
+error: first
+ --> line 1, column 5
+  |
+1 | a = ;
+  |     ^
+
+error: second
+ --> line 2, column 5
+  |
+2 | b = ;
+  |     ^
+
+error: aborting due to 2 previous errors
+",
+    );
+}
+
+#[test]
+fn render_all_drops_exact_duplicates() {
+    let source = Source::new("a = ;\n".to_string());
+
+    let diags = vec![
+        Diagnostic::error("missing value", Span { start: 4, end: 5 }),
+        Diagnostic::warning("missing value", Span { start: 4, end: 5 }),
+        Diagnostic::error("missing value", Span { start: 4, end: 5 }),
+    ];
+
+    assert_render_all(
+        &diags,
+        &source,
+        "\
+error: missing value
+ --> line 1, column 5
+  |
+1 | a = ;
+  |     ^
+
+warning: missing value
+ --> line 1, column 5
+  |
+1 | a = ;
+  |     ^
+
+error: aborting due to 1 previous error
+",
+    );
+}
+
+#[test]
+fn render_to_writes_same_output_as_render() {
+    let source = Source::new("x = y;\n".to_string());
+
+    let diag = Diagnostic::error("unknown variable `y`", Span { start: 4, end: 5 })
+        .with_secondary(Span { start: 0, end: 1 }, "assigned here")
+        .with_note(Note::note("`y` must be declared before use", None))
+        .with_help("try defining `y` earlier");
+
+    let mut buf = String::from("previous output\n");
+    render_to(&mut buf, &diag, &source).unwrap();
+
+    assert_eq!(buf, format!("previous output\n{}", render(&diag, &source)));
+}
+
+#[test]
+fn forced_color_emits_escape_codes() {
+    let source = Source::new("let x = ;\n".to_string());
+    let diag = Diagnostic::error("unexpected token", Span { start: 8, end: 9 });
+
+    let opts = RenderOptions {
+        color: true,
+        ..RenderOptions::default()
+    };
+
+    let got = render_with(&diag, &source, &opts);
+
+    assert!(got.contains("\x1b[38;5;88merror: unexpected token\n\x1b[39m"), "got:\n{got:?}");
+    assert!(got.contains("\x1b[38;5;135m^\x1b[39m"), "got:\n{got:?}");
+}
+
+#[test]
+fn disabled_color_emits_plain_text() {
+    let source = Source::new("let x = ;\n".to_string());
+    let diag = Diagnostic::error("unexpected token", Span { start: 8, end: 9 });
+
+    let got = render_with(&diag, &source, &RenderOptions::default());
+
+    assert!(!got.contains('\x1b'), "got:\n{got:?}");
+    assert!(got.starts_with("error: unexpected token\n"));
+}
+
+#[test]
+fn theme_controls_emitted_colors() {
+    let source = Source::new("let x = ;\n".to_string());
+    let diag = Diagnostic::error("unexpected token", Span { start: 8, end: 9 });
+
+    let opts = RenderOptions {
+        color: true,
+        theme: Theme {
+            error: "\x1b[31m",
+            ..Theme::default()
+        },
+        ..RenderOptions::default()
+    };
+
+    let got = render_with(&diag, &source, &opts);
+
+    assert!(got.starts_with("\x1b[31merror: unexpected token\n\x1b[39m"), "got:\n{got:?}");
+    assert!(!got.contains("\x1b[38;5;88m"), "got:\n{got:?}");
+}
+
+#[test]
+fn unicode_box_draws_gutter_arrow_and_labels() {
+    let source = Source::new("let total = price * qty;\n".to_string());
+    let diag = Diagnostic::error("unknown variable `qty`", Span { start: 20, end: 23 })
+        .with_secondary(Span { start: 11, end: 19 }, "defined here");
+
+    let opts = RenderOptions {
+        unicode_box: true,
+        ..RenderOptions::default()
+    };
+
+    assert_eq!(
+        render_with(&diag, &source, &opts),
+        "\
+error: unknown variable `qty`
+ ╭─▶ line 1, column 21
+  │
+1 │ let total = price * qty;
+  │                     ^^^
+  │             ╰─────── defined here
+"
+    );
+}
+
+#[test]
+fn tab_width_controls_tab_expansion() {
+    let source = Source::new("\tx = ;\n".to_string());
+    let diag = Diagnostic::error("invalid define statement", Span { start: 5, end: 6 });
+
+    let opts = RenderOptions {
+        tab_width: 2,
+        ..RenderOptions::default()
+    };
+
+    assert_eq!(
+        render_with(&diag, &source, &opts),
+        "\
+error: invalid define statement
+ --> line 1, column 6
+  |
+1 |   x = ;
+  |       ^
+"
+    );
+}
+
+#[test]
+fn crlf_source_renders_like_lf_source() {
+    let lf = Source::new("a = 1;\nb = ;\nc = 2\n".to_string());
+    let crlf = Source::new("a = 1;\r\nb = ;\r\nc = 2\r\n".to_string());
+
+    // `;` on line 2, then the end of line 3
+    let lf_diags = [
+        Diagnostic::error("invalid define statement", Span { start: 11, end: 12 })
+            .with_secondary(Span { start: 0, end: 1 }, "first defined here"),
+        Diagnostic::error("unterminated define statement", Span { start: 18, end: 19 }),
+    ];
+    let crlf_diags = [
+        Diagnostic::error("invalid define statement", Span { start: 12, end: 13 })
+            .with_secondary(Span { start: 0, end: 1 }, "first defined here"),
+        Diagnostic::error("unterminated define statement", Span { start: 20, end: 21 }),
+    ];
+
+    for (a, b) in lf_diags.iter().zip(&crlf_diags) {
+        let expected = render(a, &lf);
+        assert!(!expected.contains('\r'));
+        assert_render(b, &crlf, &expected);
+    }
+}
+
+#[test]
+fn source_from_file_uses_path_as_name() {
+    let path = std::env::temp_dir().join(format!("druim-from-file-{}.dr", std::process::id()));
+    std::fs::write(&path, "let x = ;\n").unwrap();
+
+    let source = Source::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let diag = Diagnostic::error("unexpected token", Span { start: 8, end: 9 });
+
+    assert_render(
+        &diag,
+        &source,
+        &format!(
+            "\
+error: unexpected token
+ --> {}:1:9
+  |
+1 | let x = ;
+  |         ^
+",
+            path.display()
+        ),
+    );
+}
+
+#[test]
+fn source_from_missing_file_is_io_error() {
+    let path = std::env::temp_dir().join("druim-definitely-missing.dr");
+
+    assert!(Source::from_file(path).is_err());
+}
+
+#[test]
+fn snippet_returns_spanned_text() {
+    let source = Source::new("let price = 10;\n".to_string());
+
+    assert_eq!(source.snippet(Span { start: 4, end: 9 }), "price");
+}
+
+#[test]
+fn zero_width_snippet_is_empty() {
+    let source = Source::new("let price = 10;\n".to_string());
+
+    assert_eq!(source.snippet(Span { start: 4, end: 4 }), "");
+}
+
+#[test]
+fn snippet_clamps_out_of_range_end() {
+    let source = Source::new("x = é;".to_string());
+
+    assert_eq!(source.snippet(Span { start: 4, end: 100 }), "é;");
+    assert_eq!(source.snippet(Span { start: 50, end: 100 }), "");
+    // an end inside `é` stops before it
+    assert_eq!(source.snippet(Span { start: 0, end: 5 }), "x = ");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn render_json_matches_rendered_columns() {
+    use crate::compiler::diagnostic::render_json;
+
+    let source = Source::new("\tx = \"é\" + ;\n".to_string());
+
+    let diag = Diagnostic {
+        severity: Severity::Error,
+        code: None,
+        message: "expected \"value\"".to_string(),
+        span: Span { start: 12, end: 13 },
+        help: Some("add an operand".into()),
+        secondary: vec![(Span { start: 1, end: 2 }, "target".into())],
+        notes: vec![
+            Note {
+                severity: Severity::Note,
+                message: "in statement".to_string(),
+                span: Some(Span { start: 1, end: 13 }),
+            },
+            Note {
+                severity: Severity::Help,
+                message: "see docs".to_string(),
+                span: None,
+            },
+        ],
+        suggestion: None,
+    };
+
+    assert!(render(&diag, &source).contains(" --> line 1, column 12\n"));
+
+    assert_eq!(
+        render_json(&diag, &source),
+        "{\"severity\":\"error\",\"message\":\"expected \\\"value\\\"\",\
+\"span\":{\"start\":12,\"end\":13},\"line\":1,\"column\":12,\"code\":null,\
+\"help\":\"add an operand\",\"suggestion\":null,\
+\"secondary\":[{\"label\":\"target\",\"span\":{\"start\":1,\"end\":2},\"line\":1,\"column\":2}],\
+\"notes\":[{\"severity\":\"note\",\"message\":\"in statement\",\"span\":{\"start\":1,\"end\":13},\"line\":1,\"column\":2},\
+{\"severity\":\"help\",\"message\":\"see docs\"}]}"
+    );
+}
+
+#[test]
+fn line_col_at_line_boundaries() {
+    let source = Source::new("ab\ncd\n".to_string());
+
+    assert_eq!(source.line_col(0), (1, 1));
+    assert_eq!(source.line_col(1), (1, 2));
+    // the newline byte stays on the line it terminates
+    assert_eq!(source.line_col(2), (1, 3));
+    // the first byte after it starts the next line
+    assert_eq!(source.line_col(3), (2, 1));
+    assert_eq!(source.line_col(5), (2, 3));
+    // past the trailing newline: an empty third line
+    assert_eq!(source.line_col(6), (3, 1));
+}
+
+#[test]
+fn line_col_at_crlf_and_empty_lines() {
+    let source = Source::new("a\r\n\nb".to_string());
+
+    assert_eq!(source.line_col(1), (1, 2));
+    assert_eq!(source.line_col(2), (1, 2));
+    assert_eq!(source.line_col(3), (2, 1));
+    assert_eq!(source.line_col(4), (3, 1));
+    assert_eq!(source.line_col(5), (3, 2));
+
+    assert_eq!(Source::new(String::new()).line_col(0), (1, 1));
+}
+
+#[test]
+fn line_col_counts_characters_after_multibyte_text() {
+    let source = Source::new("é\nx".to_string());
+
+    assert_eq!(source.line_col(2), (1, 2));
+    assert_eq!(source.line_col(3), (2, 1));
+}
+
+#[test]
+fn span_merge_covers_both_in_either_order() {
+    let a = Span { start: 2, end: 5 };
+    let b = Span { start: 8, end: 10 };
+
+    assert_eq!(a.merge(b), Span { start: 2, end: 10 });
+    assert_eq!(b.merge(a), Span { start: 2, end: 10 });
+}
+
+#[test]
+fn span_merge_of_overlapping_and_nested_spans() {
+    let outer = Span { start: 1, end: 9 };
+
+    assert_eq!(outer.merge(Span { start: 4, end: 12 }), Span { start: 1, end: 12 });
+    assert_eq!(outer.merge(Span { start: 3, end: 5 }), outer);
+    assert_eq!(outer.merge(outer), outer);
+}
+
+#[test]
+fn span_contains_is_half_open() {
+    let span = Span { start: 3, end: 6 };
+
+    assert!(!span.contains(2));
+    assert!(span.contains(3));
+    assert!(span.contains(5));
+    assert!(!span.contains(6));
+    assert_eq!(span.len(), 3);
+
+    let empty = Span { start: 4, end: 4 };
+    assert!(!empty.contains(4));
+    assert!(empty.is_empty());
+}
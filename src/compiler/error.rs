@@ -1,12 +1,41 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
 use crate::compiler::token::TokenKind;
 
 /// A half-open byte range into the source text.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
     pub start: usize,
     pub end: usize,
 }
 
+impl Span {
+    /// The smallest span covering both spans, in either order.
+    pub fn merge(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// Whether the byte offset `pos` lies in this half-open span.
+    pub fn contains(&self, pos: usize) -> bool {
+        self.start <= pos && pos < self.end
+    }
+
+    /// Length in bytes.
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 /// High-level classification of parse errors.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ErrorKind {
@@ -54,14 +83,54 @@ pub struct Note {
     pub span: Option<Span>,
 }
 
+/// Stable diagnostic codes.
+///
+/// Codes identify a family of errors rather than a single message, so
+/// tools can document or suppress them without matching on text. A code
+/// is never reused once assigned.
+pub mod codes {
+    /// A statement is missing its terminating `;`.
+    pub const UNTERMINATED_STATEMENT: &str = "E0001";
+    /// A block, function, or block expression is missing its closing delimiter.
+    pub const UNTERMINATED_DELIMITER: &str = "E0002";
+    /// Malformed define or empty-define statement.
+    pub const INVALID_DEFINE: &str = "E0003";
+    /// Malformed copy statement.
+    pub const INVALID_COPY: &str = "E0004";
+    /// Malformed bind statement.
+    pub const INVALID_BIND: &str = "E0005";
+    /// Malformed guard statement.
+    pub const INVALID_GUARD: &str = "E0006";
+    /// A structural form was chained where canon requires exactly one.
+    pub const CHAINING_NOT_ALLOWED: &str = "E0007";
+    /// A structural form was nested where canon forbids it.
+    pub const NESTING_NOT_ALLOWED: &str = "E0008";
+    /// Malformed function definition, name, or parameter list.
+    pub const INVALID_FUNCTION: &str = "E0009";
+    /// Malformed function call.
+    pub const INVALID_CALL: &str = "E0010";
+    /// Malformed return statement.
+    pub const INVALID_RETURN: &str = "E0011";
+    /// Malformed value expression.
+    pub const INVALID_EXPRESSION: &str = "E0012";
+    /// Malformed comma-separated list (call arguments, parameters).
+    pub const INVALID_LIST: &str = "E0013";
+    /// A closing delimiter with no matching opening delimiter.
+    pub const UNMATCHED_DELIMITER: &str = "E0014";
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Diagnostic {
     pub severity: Severity,
+    /// Stable identifier for this kind of diagnostic (see [`codes`]).
+    pub code: Option<&'static str>,
     pub message: String,
     pub span: Span,
-    pub help: Option<&'static str>,
-    pub secondary: Vec<(Span, &'static str)>,
+    pub help: Option<String>,
+    pub secondary: Vec<(Span, String)>,
     pub notes: Vec<Note>,
+    /// Machine-applicable fix: replace the text at the span with the string.
+    pub suggestion: Option<(Span, String)>,
 }
 
 
@@ -69,6 +138,7 @@ pub struct Diagnostic {
 pub struct Source {
     text: String,
     line_starts: Vec<usize>,
+    name: Option<String>,
 }
 
 impl Source {
@@ -81,19 +151,84 @@ impl Source {
             }
         }
 
-        Self { text, line_starts }
+        Self { text, line_starts, name: None }
+    }
+
+    /// Create a source that reports `name` (typically a file path) in
+    /// diagnostic headers.
+    pub fn with_name(text: String, name: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..Self::new(text)
+        }
+    }
+
+    /// Read a source file, naming it after its path.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        Ok(Self::with_name(text, path.display().to_string()))
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
     }
 
+    /// One-based line and column of a byte offset.
+    ///
+    /// Columns count characters, not bytes, so a multibyte character
+    /// occupies a single column.
+    ///
+    /// A line ending belongs to the line it terminates: in `"ab\ncd"`,
+    /// the `\n` at offset 2 is `(1, 3)`, one past `b`, and offset 3 is
+    /// `(2, 1)`. Both bytes of a `\r\n` report that same column. The
+    /// offset just past a trailing newline starts an empty last line.
     pub fn line_col(&self, pos: usize) -> (usize, usize) {
         let line = match self.line_starts.binary_search(&pos) {
             Ok(i) => i,
             Err(i) => i - 1,
         };
 
-        let col = pos - self.line_starts[line];
+        let start = self.line_starts[line];
+
+        // The `\n` of a `\r\n` ending sits on the same column as the `\r`,
+        // so both endings report the end-of-line column identically.
+        let pos = if pos > start && self.is_crlf_newline_at(pos) {
+            pos - 1
+        } else {
+            pos
+        };
+        let col = match self.text.get(start..pos) {
+            Some(prefix) => prefix.chars().count(),
+            None => pos - start,
+        };
         (line + 1, col + 1)
     }
 
+    /// The source text covered by `span`.
+    ///
+    /// Offsets past the end of the text are clamped to it, and an offset
+    /// inside a multibyte character is moved back to that character's
+    /// start, so this never panics.
+    pub fn snippet(&self, span: Span) -> &str {
+        let clamp = |mut pos: usize| {
+            pos = pos.min(self.text.len());
+            while !self.text.is_char_boundary(pos) {
+                pos -= 1;
+            }
+            pos
+        };
+
+        let end = clamp(span.end);
+        let start = clamp(span.start).min(end);
+        &self.text[start..end]
+    }
+
+    /// Byte offset at which a one-based line begins.
+    pub fn line_start(&self, line: usize) -> usize {
+        self.line_starts[line - 1]
+    }
+
     pub fn line_text(&self, line: usize) -> &str {
         let start = self.line_starts[line - 1];
         let end = self
@@ -102,16 +237,24 @@ impl Source {
             .copied()
             .unwrap_or(self.text.len());
 
-        self.text[start..end]
-            .trim_end_matches('\n')
+        let text = self.text[start..end].trim_end_matches('\n');
+        text.strip_suffix('\r').unwrap_or(text)
     }
 
+    /// Whether `pos` is a line ending: a `\n`, or the `\r` of a `\r\n`.
     pub fn is_newline_at(&self, pos: usize) -> bool {
-        self.text
-            .as_bytes()
-            .get(pos)
-            .map(|b| *b == b'\n')
-            .unwrap_or(false)
+        let bytes = self.text.as_bytes();
+        match bytes.get(pos) {
+            Some(b'\n') => true,
+            Some(b'\r') => bytes.get(pos + 1) == Some(&b'\n'),
+            _ => false,
+        }
+    }
+
+    // The `\n` half of a `\r\n` line ending.
+    fn is_crlf_newline_at(&self, pos: usize) -> bool {
+        let bytes = self.text.as_bytes();
+        bytes.get(pos) == Some(&b'\n') && pos > 0 && bytes[pos - 1] == b'\r'
     }
 }
 
@@ -162,11 +305,13 @@ impl From<ParseError> for Diagnostic {
 
         Diagnostic {
             severity: Severity::Error,
+            code: None,
             message,
             span: err.span,
-            help: err.expected,
+            help: err.expected.map(String::from),
             secondary: vec![],
-            notes: vec![],            
+            notes: vec![],
+            suggestion: None,
         }
     }
 }
@@ -175,54 +320,77 @@ impl Diagnostic {
     pub fn error(message: impl Into<String>, span: Span) -> Self {
         Self {
             severity: Severity::Error,
+            code: None,
             message: message.into(),
             span,
             help: None,
             secondary: vec![],
             notes: vec![],
+            suggestion: None,
         }
     }
 
     pub fn warning(message: impl Into<String>, span: Span) -> Self {
         Self {
             severity: Severity::Warning,
+            code: None,
             message: message.into(),
             span,
             help: None,
             secondary: vec![],
             notes: vec![],
+            suggestion: None,
         }
     }
 
     pub fn note(message: impl Into<String>, span: Span) -> Self {
         Self {
             severity: Severity::Note,
+            code: None,
             message: message.into(),
             span,
             help: None,
             secondary: vec![],
             notes: vec![],
+            suggestion: None,
         }
     }
 
     pub fn help(message: impl Into<String>, span: Span) -> Self {
         Self {
             severity: Severity::Help,
+            code: None,
             message: message.into(),
             span,
             help: None,
             secondary: vec![],
             notes: vec![],
+            suggestion: None,
         }
     }
 
-    pub fn with_help(mut self, help: &'static str) -> Self {
-        self.help = Some(help);
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, label: impl Into<String>) -> Self {
+        self.secondary.push((span, label.into()));
         self
     }
 
-    pub fn with_secondary(mut self, span: Span, label: &'static str) -> Self {
-        self.secondary.push((span, label));
+    /// Add several secondary labels at once, after any existing ones.
+    pub fn with_secondaries<L: Into<String>>(
+        mut self,
+        labels: impl IntoIterator<Item = (Span, L)>,
+    ) -> Self {
+        self.secondary
+            .extend(labels.into_iter().map(|(span, label)| (span, label.into())));
         self
     }
 
@@ -230,9 +398,21 @@ impl Diagnostic {
         self.notes.push(note);
         self
     }
+
+    /// Add several notes at once, after any existing ones.
+    pub fn with_notes(mut self, notes: impl IntoIterator<Item = Note>) -> Self {
+        self.notes.extend(notes);
+        self
+    }
+
+    pub fn with_suggestion(mut self, span: Span, replacement: impl Into<String>) -> Self {
+        self.suggestion = Some((span, replacement.into()));
+        self
+    }
 }
 
 impl Note {
+    #[allow(clippy::self_named_constructors)]
     pub fn note(message: impl Into<String>, span: Option<Span>) -> Self {
         Self {
             severity: Severity::Note,
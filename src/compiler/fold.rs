@@ -0,0 +1,84 @@
+use crate::compiler::ast::{Literal, Node, Program};
+use crate::compiler::semantics::eval::{const_arith, ArithOp};
+use crate::compiler::semantics::value::Value;
+use crate::compiler::visit::{walk_node_mut, VisitorMut};
+
+/// Replace pure operations over literal operands with their result.
+///
+/// Folding is bottom-up, so `1 + 2 * 3` becomes `7`. The folded forms are:
+/// - arithmetic and negation over `num`/`dec` literals, with the same
+///   promotion rules as the evaluator
+/// - comparisons over two `num` literals
+/// - `!`, `&&` and `||` over `flag` literals
+///
+/// Anything that would fail at runtime (division or modulo by zero,
+/// overflow, a type mismatch) is left unfolded so the evaluator still
+/// reports it.
+pub fn fold_constants(program: &mut Program) {
+    Folder.visit_program_mut(program);
+}
+
+struct Folder;
+
+impl VisitorMut for Folder {
+    fn visit_node_mut(&mut self, node: &mut Node) {
+        walk_node_mut(self, node);
+
+        if let Some(lit) = fold(node) {
+            *node = Node::Lit(lit);
+        }
+    }
+}
+
+fn fold(node: &Node) -> Option<Literal> {
+    match node {
+        Node::Neg(inner) => arith(ArithOp::Sub, &Node::Lit(Literal::Num(0)), inner),
+
+        Node::Add(l, r) => arith(ArithOp::Add, l, r),
+        Node::Sub(l, r) => arith(ArithOp::Sub, l, r),
+        Node::Mul(l, r) => arith(ArithOp::Mul, l, r),
+        Node::Div(l, r) => arith(ArithOp::Div, l, r),
+        Node::Mod(l, r) => arith(ArithOp::Mod, l, r),
+
+        Node::Eq(l, r) => compare(l, r, |a, b| a == b),
+        Node::Ne(l, r) => compare(l, r, |a, b| a != b),
+        Node::Lt(l, r) => compare(l, r, |a, b| a < b),
+        Node::Le(l, r) => compare(l, r, |a, b| a <= b),
+        Node::Gt(l, r) => compare(l, r, |a, b| a > b),
+        Node::Ge(l, r) => compare(l, r, |a, b| a >= b),
+
+        Node::Not(inner) => flag(inner).map(|b| Literal::Flag(!b)),
+        Node::And(l, r) => Some(Literal::Flag(flag(l)? && flag(r)?)),
+        Node::Or(l, r) => Some(Literal::Flag(flag(l)? || flag(r)?)),
+
+        _ => None,
+    }
+}
+
+fn arith(op: ArithOp, l: &Node, r: &Node) -> Option<Literal> {
+    let (Node::Lit(l), Node::Lit(r)) = (l, r) else {
+        return None;
+    };
+
+    match const_arith(op, Value::from_literal(l), Value::from_literal(r))? {
+        Value::Num(n) => Some(Literal::Num(n)),
+        Value::Dec(d) => Some(Literal::Dec(d)),
+        _ => None,
+    }
+}
+
+fn compare(l: &Node, r: &Node, op: fn(i64, i64) -> bool) -> Option<Literal> {
+    match (l, r) {
+        (Node::Lit(Literal::Num(a)), Node::Lit(Literal::Num(b))) => {
+            Some(Literal::Flag(op(*a, *b)))
+        }
+        _ => None,
+    }
+}
+
+fn flag(node: &Node) -> Option<bool> {
+    match node {
+        Node::Lit(Literal::Flag(b)) => Some(*b),
+        _ => None,
+    }
+}
@@ -0,0 +1,98 @@
+use crate::compiler::ast::{Define, Literal, Node, Program};
+use crate::compiler::fold::fold_constants;
+use crate::compiler::lexer::Lexer;
+use crate::compiler::parser::Parser;
+use crate::compiler::semantics::eval::Evaluator;
+
+fn parse_program(src: &str) -> Program {
+    let tokens = Lexer::new(src).tokenize().expect("lexing failed");
+    let mut parser = Parser::new(&tokens);
+    parser.parse_program().expect("failed to parse program")
+}
+
+/// Fold `x = <expr>;` and return the folded right-hand side.
+fn fold_value(expr: &str) -> Node {
+    let mut program = parse_program(&format!("x = {expr};"));
+    fold_constants(&mut program);
+
+    match program.nodes.remove(0) {
+        Node::Define(Define { value, .. }) => *value,
+        other => panic!("expected define node, got {:?}", other),
+    }
+}
+
+#[test]
+fn folds_nested_arithmetic() {
+    assert_eq!(fold_value("1 + 2 * 3"), Node::Lit(Literal::Num(7)));
+}
+
+#[test]
+fn folds_mixed_numeric_to_dec() {
+    assert_eq!(fold_value("1 + 0.5"), Node::Lit(Literal::Dec("1.5".into())));
+}
+
+#[test]
+fn folds_negation_and_comparison() {
+    assert_eq!(fold_value("-(2 - 5)"), Node::Lit(Literal::Num(3)));
+    assert_eq!(fold_value("1 < 2"), Node::Lit(Literal::Flag(true)));
+}
+
+#[test]
+fn folds_logical_over_flags() {
+    let mut program = Program {
+        nodes: vec![Node::Or(
+            Box::new(Node::Lit(Literal::Flag(false))),
+            Box::new(Node::Not(Box::new(Node::Lit(Literal::Flag(false))))),
+        )],
+    };
+
+    fold_constants(&mut program);
+
+    assert_eq!(program.nodes, vec![Node::Lit(Literal::Flag(true))]);
+}
+
+#[test]
+fn division_by_zero_is_not_folded() {
+    assert_eq!(
+        fold_value("1 / 0"),
+        Node::Div(
+            Box::new(Node::Lit(Literal::Num(1))),
+            Box::new(Node::Lit(Literal::Num(0))),
+        )
+    );
+}
+
+#[test]
+fn non_literal_operands_are_kept() {
+    assert_eq!(
+        fold_value("a + 2 * 3"),
+        Node::Add(
+            Box::new(Node::Ident("a".into())),
+            Box::new(Node::Lit(Literal::Num(6))),
+        )
+    );
+}
+
+#[test]
+fn folding_never_changes_a_result() {
+    let exprs = [
+        "1 + 2 * 3", "7 - 10", "7 / 2", "-7 % 3", "1 + 0.5", "-(2 - 5)",
+        "1 == 1", "1 != 2", "1 < 2", "2 <= 1", "3 > 2", "2 >= 3",
+        "!true", "!false", "true && false", "false || true", "!(1 < 2) || 2 >= 2",
+    ];
+
+    for expr in exprs {
+        assert!(matches!(fold_value(expr), Node::Lit(_)), "{expr} did not fold");
+
+        let program = parse_program(&format!("x = {expr};"));
+        let mut folded = program.clone();
+        fold_constants(&mut folded);
+
+        let mut plain = Evaluator::new();
+        plain.eval_program(&program).expect("unfolded evaluation failed");
+        let mut fast = Evaluator::new();
+        fast.eval_program(&folded).expect("folded evaluation failed");
+
+        assert_eq!(fast.get("x"), plain.get("x"), "{expr}");
+    }
+}
@@ -0,0 +1,378 @@
+use std::fmt;
+
+use crate::compiler::ast::{Block, Func, Literal, Node, Program, Type};
+use crate::compiler::parser::{infix_binding_power, CALL_BP, PREFIX_BP};
+use crate::compiler::token::{canonical_lexeme, TokenKind};
+
+/// Layout choices for the Druim formatter.
+///
+/// The defaults describe the canonical style:
+/// - four spaces per indentation level
+/// - one blank line around top-level functions and blocks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Number of spaces per indentation level.
+    pub indent: usize,
+
+    /// Separate top-level functions and blocks from their neighbours
+    /// with a single blank line.
+    pub blank_lines_between_items: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            blank_lines_between_items: true,
+        }
+    }
+}
+
+/// Render a parsed program back into Druim source.
+///
+/// Structural forms (blocks, functions) always open and close on their
+/// own lines. Statements are written one per line.
+pub fn format_program(program: &Program, options: &FormatOptions) -> String {
+    let mut f = Formatter {
+        out: String::new(),
+        options,
+        depth: 0,
+    };
+
+    for (i, node) in program.nodes.iter().enumerate() {
+        if i > 0
+            && options.blank_lines_between_items
+            && (is_item(node) || is_item(&program.nodes[i - 1]))
+        {
+            f.out.push('\n');
+        }
+
+        f.node(node);
+    }
+
+    f.out
+}
+
+/// Source form of a literal, e.g. `42`, `"hi"`, `void`.
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Num(n) => write!(f, "{n}"),
+            Literal::Dec(d) => f.write_str(d),
+            Literal::Flag(b) => write!(f, "{b}"),
+            Literal::Text(t) => {
+                f.write_str("\"")?;
+                for ch in t.chars() {
+                    match ch {
+                        '\n' => f.write_str("\\n")?,
+                        '\t' => f.write_str("\\t")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\0' => f.write_str("\\0")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '"' => f.write_str("\\\"")?,
+                        _ => write!(f, "{ch}")?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            Literal::Void => f.write_str("void"),
+            Literal::Emp => f.write_str("emp"),
+        }
+    }
+}
+
+/// Compact, parseable source form of a node on a single line.
+///
+/// Value expressions are written without a terminator (`a + b`);
+/// statements include theirs (`x = a + b;`). Blocks and functions are
+/// written inline with single spaces.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Block(block) => {
+                f.write_str(":{")?;
+                for (i, segment) in block.segments.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" }{")?;
+                    }
+                    for node in &segment.nodes {
+                        write!(f, " {node}")?;
+                    }
+                }
+                f.write_str(" }:")
+            }
+            Node::Func(func) => {
+                write!(f, "fn {} :({})(", func.name, params(func))?;
+                for node in &func.body {
+                    write!(f, " {node}")?;
+                }
+                f.write_str(" ):")
+            }
+            Node::Local(_)
+            | Node::Define(_)
+            | Node::DefineEmpty(_)
+            | Node::Copy(_)
+            | Node::Bind(_)
+            | Node::Guard(_)
+            | Node::Ret(_) => f.write_str(&statement(self)),
+            other => f.write_str(&expr(other)),
+        }
+    }
+}
+
+/// Top-level forms that are separated by blank lines.
+fn is_item(node: &Node) -> bool {
+    matches!(node, Node::Func(_) | Node::Block(_))
+}
+
+struct Formatter<'a> {
+    out: String,
+    options: &'a FormatOptions,
+    depth: usize,
+}
+
+impl Formatter<'_> {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.depth * self.options.indent {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Block(block) => self.block(block),
+            Node::Func(func) => self.func(func),
+            other => {
+                let text = statement(other);
+                self.line(&text);
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.line(":{");
+
+        for (i, segment) in block.segments.iter().enumerate() {
+            if i > 0 {
+                self.line("}{");
+            }
+
+            self.depth += 1;
+            for node in &segment.nodes {
+                self.node(node);
+            }
+            self.depth -= 1;
+        }
+
+        self.line("}:");
+    }
+
+    fn func(&mut self, func: &Func) {
+        self.line(&format!("fn {} :({})(", func.name, params(func)));
+
+        self.depth += 1;
+        for node in &func.body {
+            self.node(node);
+        }
+        self.depth -= 1;
+
+        self.line("):");
+    }
+}
+
+/// Comma-separated parameter list, with defaults.
+fn params(func: &Func) -> String {
+    func.params
+        .iter()
+        .map(|p| match &p.default {
+            Some(default) => format!("{} = {}", p.name, expr(default)),
+            None => p.name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Render a single non-structural statement, including its terminator.
+fn statement(node: &Node) -> String {
+    match node {
+        Node::Local(inner) => format!("loc {}", statement(inner)),
+        Node::Define(def) => format!("{}{} = {};", annotation(def.ty), def.name, expr(&def.value)),
+        Node::DefineEmpty(def) => format!("{}{} =;", annotation(def.ty), def.name),
+        Node::Copy(copy) => format!("{} := {};", copy.name, copy.target),
+        Node::Bind(bind) => format!("{} :> {};", bind.name, bind.target),
+        Node::Guard(guard) => {
+            let branches = guard
+                .branches
+                .iter()
+                .map(|b| expr(&b.expr))
+                .collect::<Vec<_>>()
+                .join(" : ");
+            format!("{} ?= {};", guard.target, branches)
+        }
+        Node::Ret(ret) => match &ret.value {
+            Some(value) => format!("ret {};", expr(value)),
+            None => "ret;".to_string(),
+        },
+        other => format!("{};", expr(other)),
+    }
+}
+
+/// A declared type with its trailing space, or nothing.
+fn annotation(ty: Option<Type>) -> String {
+    ty.map(|ty| format!("{} ", ty.name())).unwrap_or_default()
+}
+
+/// Render a value expression, inserting parentheses only where the
+/// parser's binding powers would otherwise regroup the tree.
+fn expr(node: &Node) -> String {
+    match node {
+        Node::Ident(ident) => ident.name.clone(),
+        Node::Lit(lit) => lit.to_string(),
+
+        Node::Not(inner) => format!("!{}", operand(inner, PREFIX_BP)),
+        // A minus sign directly before a numeric literal parses as part of
+        // the literal, so a negated literal keeps its parentheses.
+        Node::Neg(inner) => match inner.as_ref() {
+            Node::Lit(Literal::Num(_) | Literal::Dec(_)) => format!("-({})", expr(inner)),
+            _ => format!("-{}", operand(inner, PREFIX_BP)),
+        },
+
+        Node::BlockExpr(block) => {
+            let mut parts: Vec<String> = block.nodes.iter().map(statement).collect();
+            if let Some(value) = &block.value {
+                parts.push(expr(value));
+            }
+
+            if parts.is_empty() {
+                ":[ ]:".to_string()
+            } else {
+                format!(":[ {} ]:", parts.join(" "))
+            }
+        }
+
+        Node::Call(call) => {
+            let args = call
+                .args
+                .iter()
+                .map(expr)
+                .collect::<Vec<_>>()
+                .join(", ");
+            // `)(` would lex as a function chain, so a called call is
+            // separated from its arguments.
+            let gap = if matches!(*call.callee, Node::Call(_)) { " " } else { "" };
+            format!("{}{gap}({})", operand(&call.callee, CALL_BP), args)
+        }
+
+        Node::Add(..)
+        | Node::Sub(..)
+        | Node::Mul(..)
+        | Node::Div(..)
+        | Node::Mod(..)
+        | Node::Eq(..)
+        | Node::Ne(..)
+        | Node::Lt(..)
+        | Node::Le(..)
+        | Node::Gt(..)
+        | Node::Ge(..)
+        | Node::And(..)
+        | Node::Or(..)
+        | Node::Has(..)
+        | Node::Present(..)
+        | Node::Pipe(..)
+        | Node::Coalesce(..) => {
+            let (op, lhs, rhs) = infix_parts(node).expect("binary node must have an operator");
+            let (mut l_bp, mut r_bp, _) =
+                infix_binding_power(op).expect("infix node must have binding power");
+
+            // Comparisons do not chain, so a comparison operand of a
+            // comparison is always grouped.
+            if is_comparison(op) {
+                l_bp = l_bp.max(COMPARISON_OPERAND);
+                r_bp = r_bp.max(COMPARISON_OPERAND);
+            }
+
+            format!(
+                "{} {} {}",
+                operand(lhs, l_bp),
+                canonical_lexeme(op).unwrap_or_default(),
+                operand(rhs, r_bp),
+            )
+        }
+
+        // Statements have no value form; they keep their statement text.
+        Node::Block(_)
+        | Node::Func(_)
+        | Node::Local(_)
+        | Node::Ret(_)
+        | Node::Define(_)
+        | Node::DefineEmpty(_)
+        | Node::Copy(_)
+        | Node::Bind(_)
+        | Node::Guard(_) => node.to_string(),
+    }
+}
+
+/// Minimum binding power of a comparison's operands: just above the
+/// tightest comparison, so nested comparisons get parentheses.
+const COMPARISON_OPERAND: u8 = 52;
+
+fn is_comparison(op: TokenKind) -> bool {
+    matches!(
+        op,
+        TokenKind::Eq | TokenKind::Ne | TokenKind::Lt | TokenKind::Le | TokenKind::Gt | TokenKind::Ge
+    )
+}
+
+/// Render `node` as the operand of an operator that binds at `min_bp`,
+/// wrapping it in parentheses when it would not survive a re-parse.
+fn operand(node: &Node, min_bp: u8) -> String {
+    let text = expr(node);
+
+    let bp = match node {
+        Node::Not(_) | Node::Neg(_) => PREFIX_BP,
+        Node::Call(_) => CALL_BP,
+        other => match infix_parts(other) {
+            Some((op, _, _)) => infix_binding_power(op).map(|(l, _, _)| l).unwrap_or(u8::MAX),
+            None => u8::MAX,
+        },
+    };
+
+    if bp < min_bp {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+fn infix_parts(node: &Node) -> Option<(TokenKind, &Node, &Node)> {
+    let (op, lhs, rhs) = match node {
+        Node::Add(l, r) => (TokenKind::Add, l, r),
+        Node::Sub(l, r) => (TokenKind::Sub, l, r),
+        Node::Mul(l, r) => (TokenKind::Mul, l, r),
+        Node::Div(l, r) => (TokenKind::Div, l, r),
+        Node::Mod(l, r) => (TokenKind::Mod, l, r),
+
+        Node::Eq(l, r) => (TokenKind::Eq, l, r),
+        Node::Ne(l, r) => (TokenKind::Ne, l, r),
+        Node::Lt(l, r) => (TokenKind::Lt, l, r),
+        Node::Le(l, r) => (TokenKind::Le, l, r),
+        Node::Gt(l, r) => (TokenKind::Gt, l, r),
+        Node::Ge(l, r) => (TokenKind::Ge, l, r),
+
+        Node::And(l, r) => (TokenKind::And, l, r),
+        Node::Or(l, r) => (TokenKind::Or, l, r),
+
+        Node::Has(l, r) => (TokenKind::Has, l, r),
+        Node::Present(l, r) => (TokenKind::Present, l, r),
+
+        Node::Pipe(l, r) => (TokenKind::Pipe, l, r),
+
+        Node::Coalesce(l, r) => (TokenKind::Coalesce, l, r),
+
+        _ => return None,
+    };
+
+    Some((op, lhs.as_ref(), rhs.as_ref()))
+}
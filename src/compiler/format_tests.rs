@@ -0,0 +1,229 @@
+use crate::compiler::ast::{Literal, Node, Program};
+use crate::compiler::format::{format_program, FormatOptions};
+use crate::compiler::lexer::Lexer;
+use crate::compiler::parser::Parser;
+
+fn parse_program(src: &str) -> Program {
+    let tokens = Lexer::new(src).tokenize().expect("lexing failed");
+    let mut parser = Parser::new(&tokens);
+    parser.parse_program().expect("failed to parse program")
+}
+
+#[test]
+fn formats_statements_with_canonical_spacing() {
+    let program = parse_program("a=1+2;b=;c:=a;d:>a;x?=a:2:void;");
+
+    let got = format_program(&program, &FormatOptions::default());
+
+    assert_eq!(
+        got,
+        "\
+a = 1 + 2;
+b =;
+c := a;
+d :> a;
+x ?= a : 2 : void;
+"
+    );
+}
+
+#[test]
+fn formats_block_with_default_indent() {
+    let program = parse_program(":{ a = 1; }{ loc b = 2; }:");
+
+    let got = format_program(&program, &FormatOptions::default());
+
+    assert_eq!(
+        got,
+        "\
+:{
+    a = 1;
+}{
+    loc b = 2;
+}:
+"
+    );
+}
+
+#[test]
+fn formats_function_with_two_space_indent() {
+    let program = parse_program("fn add :(a, b = 2)( ret a + b; ):");
+
+    let options = FormatOptions {
+        indent: 2,
+        ..FormatOptions::default()
+    };
+
+    let got = format_program(&program, &options);
+
+    assert_eq!(
+        got,
+        "\
+fn add :(a, b = 2)(
+  ret a + b;
+):
+"
+    );
+}
+
+#[test]
+fn blank_lines_separate_top_level_items_by_default() {
+    let program = parse_program("x = 1; fn f :()( ret; ): fn g :()( ret; ): y = 2;");
+
+    let got = format_program(&program, &FormatOptions::default());
+
+    assert_eq!(
+        got,
+        "\
+x = 1;
+
+fn f :()(
+    ret;
+):
+
+fn g :()(
+    ret;
+):
+
+y = 2;
+"
+    );
+}
+
+#[test]
+fn blank_lines_can_be_disabled() {
+    let program = parse_program("x = 1; fn f :()( ret; ): y = 2;");
+
+    let options = FormatOptions {
+        blank_lines_between_items: false,
+        ..FormatOptions::default()
+    };
+
+    let got = format_program(&program, &options);
+
+    assert_eq!(
+        got,
+        "\
+x = 1;
+fn f :()(
+    ret;
+):
+y = 2;
+"
+    );
+}
+
+#[test]
+fn parenthesizes_only_where_precedence_requires() {
+    let program = parse_program("a = (1 + 2) * 3; b = 1 + 2 * 3; c = 1 - (2 - 3);");
+
+    let got = format_program(&program, &FormatOptions::default());
+
+    assert_eq!(
+        got,
+        "\
+a = (1 + 2) * 3;
+b = 1 + 2 * 3;
+c = 1 - (2 - 3);
+"
+    );
+}
+
+fn assert_round_trip(src: &str) {
+    let program = parse_program(src);
+    let formatted = format_program(&program, &FormatOptions::default());
+
+    assert_eq!(
+        parse_program(&formatted),
+        program,
+        "formatted source did not re-parse to the same program:\n{formatted}"
+    );
+}
+
+#[test]
+fn round_trips_statements() {
+    assert_round_trip("a = 1 + 2 * 3; b =; c := a; d :> a; e ?= b : 0 : \"none\" : void;");
+    assert_round_trip("num n = 1; loc text t = \"t\"; flag f =; dec d = 0.5;");
+    assert_round_trip(r#"s = "tab\tnul\0 \"q\" \\";"#);
+}
+
+#[test]
+fn round_trips_precedence_and_prefix_operators() {
+    assert_round_trip("a = (1 + 2) * -(3 - 4); b = !(x || y) && z; c = 1 - (2 - 3) % 4;");
+    assert_round_trip("d = \"a\" :: t; e = v ?? (w ?? 1); f = emp ?? 2.5;");
+    assert_round_trip("g = (a < b) == (c >= d); h = a + 1 < b * 2;");
+    assert_round_trip("i = :[ f ]:(1); make(2) (3);");
+    assert_round_trip("j = -(5) - -5; k = -(-1.5) * -x;");
+}
+
+#[test]
+fn round_trips_blocks_and_functions() {
+    assert_round_trip(
+        "fn add :(a, b = -1)( loc s = a + b; ret s; ): x = add(1, add(2, 3)); :{ loc y = 1; }{ z = :[ q = 2; q * y ]:; }:",
+    );
+}
+
+fn parse_expr(src: &str) -> Node {
+    let tokens = Lexer::new(src).tokenize().expect("lexing failed");
+    Parser::new(&tokens).parse_expr().expect("failed to parse expression")
+}
+
+#[test]
+fn displays_literals() {
+    assert_eq!(Literal::Num(-3).to_string(), "-3");
+    assert_eq!(Literal::Dec("0.5".into()).to_string(), "0.5");
+    assert_eq!(Literal::Flag(true).to_string(), "true");
+    assert_eq!(Literal::Text("hi".into()).to_string(), "\"hi\"");
+    assert_eq!(Literal::Text("a\n\"b\"".into()).to_string(), r#""a\n\"b\"""#);
+    assert_eq!(Literal::Void.to_string(), "void");
+    assert_eq!(Literal::Emp.to_string(), "emp");
+}
+
+#[test]
+fn displays_expressions_with_minimal_parentheses() {
+    assert_eq!(format!("{}", parse_expr("a + b")), "a + b");
+    assert_eq!(format!("{}", parse_expr("(a + b) * c")), "(a + b) * c");
+    assert_eq!(format!("{}", parse_expr("a - (b - c)")), "a - (b - c)");
+    assert_eq!(format!("{}", parse_expr("!(x && y) || f(1, 2)")), "!(x && y) || f(1, 2)");
+}
+
+#[test]
+fn displays_statements_on_one_line() {
+    let program = parse_program(
+        "loc num x = 1 + 2; g ?= a : 0; fn f :(a, b = 2)( ret a; ): :{ y = 1; }{ z =; }:",
+    );
+    let shown: Vec<String> = program.nodes.iter().map(ToString::to_string).collect();
+
+    assert_eq!(
+        shown,
+        [
+            "loc num x = 1 + 2;",
+            "g ?= a : 0;",
+            "fn f :(a, b = 2)( ret a; ):",
+            ":{ y = 1; }{ z =; }:",
+        ]
+    );
+}
+
+#[test]
+fn regroups_parenthesized_expressions_by_precedence() {
+    let formatted = |src: &str| parse_expr(src).to_string();
+
+    assert_eq!(formatted("(1 + 2) * 3"), "(1 + 2) * 3");
+    assert_eq!(formatted("1 - (2 + 3)"), "1 - (2 + 3)");
+    assert_eq!(formatted("-(a + b)"), "-(a + b)");
+    assert_eq!(formatted("(a ?? b) + 1"), "(a ?? b) + 1");
+    // grouping the parser would produce anyway is not meaningful
+    assert_eq!(formatted("(1 * 2) + 3"), "1 * 2 + 3");
+    assert_eq!(formatted("((x))"), "x");
+
+    assert_round_trip("a = (1 + 2) * 3; b = (a - 1) % (a + 1);");
+}
+
+#[test]
+fn statement_in_value_position_keeps_its_text() {
+    let func = parse_program("fn f :()( ret 1; ):").nodes.remove(0);
+    let node = Node::Not(Box::new(func));
+
+    assert_eq!(node.to_string(), "!fn f :()( ret 1; ):");
+}
@@ -1,284 +1,355 @@
-use crate::compiler::token::{Token, TokenKind};
-
+use std::error::Error;
+use std::fmt;
+
+use crate::compiler::token::{canonical_lexeme, Token, TokenKind};
+
+/// Multi-character operators and delimiters, in match order.
+///
+/// Operators are matched by maximal munch: at each position the longest
+/// operator spelled there wins, and lexing resumes right after it. So
+/// `:(:` is `:(` then `:`, and `}{:` is `}{` then `:`. The first match in
+/// this list is taken, so an operator must never come after a shorter one
+/// that is a prefix of it. Today every entry but the bare `:` is two
+/// characters, so only `:` (kept last) could shadow anything; the
+/// single-character operators are tried after this list.
+pub(crate) const MULTI_CHAR_OPERATORS: &[TokenKind] = &[
+    // Block delimiters (must be before single ':')
+    TokenKind::ArrayStart,
+    TokenKind::ArrayEnd,
+    TokenKind::ArrayChain,
+    TokenKind::BlockStart,
+    TokenKind::BlockEnd,
+    TokenKind::BlockChain,
+    TokenKind::FuncStart,
+    TokenKind::FuncEnd,
+    TokenKind::FuncChain,
+    // Other multi-char operators
+    TokenKind::Guard,
+    TokenKind::Coalesce,
+    TokenKind::DefineEmpty,
+    TokenKind::Pipe,
+    TokenKind::Eq,
+    TokenKind::Ne,
+    TokenKind::Le,
+    TokenKind::Ge,
+    TokenKind::And,
+    TokenKind::Or,
+    TokenKind::ArrowR,
+    TokenKind::ArrowL,
+    // Colon family (longest first)
+    TokenKind::Has,
+    TokenKind::Copy,
+    TokenKind::Present,
+    TokenKind::Bind,
+    TokenKind::Colon,
+];
+
+/// A lexing failure.
+///
+/// `pos` is the canonical byte offset. `loc` is the one-based
+/// `(line, column)` of the same position when it is known; the lexer
+/// always fills it in, counting columns in characters.
 #[derive(Debug)]
 pub enum LexError {
-    UnexpectedChar { ch: char, pos: usize },
-    UnterminatedText { pos: usize },
+    UnexpectedChar {
+        ch: char,
+        pos: usize,
+        loc: Option<(usize, usize)>,
+    },
+    UnterminatedText {
+        pos: usize,
+        loc: Option<(usize, usize)>,
+    },
+    UnknownEscape {
+        ch: char,
+        pos: usize,
+        loc: Option<(usize, usize)>,
+    },
 }
 
-pub struct Lexer<'a> {
-    src: &'a str,
-    pos: usize, // byte offset
-}
+impl LexError {
+    /// Byte offset of the error.
+    pub fn pos(&self) -> usize {
+        match self {
+            LexError::UnexpectedChar { pos, .. }
+            | LexError::UnterminatedText { pos, .. }
+            | LexError::UnknownEscape { pos, .. } => *pos,
+        }
+    }
 
-impl<'a> Lexer<'a> {
-    pub fn new(src: &'a str) -> Self {
-        Self { src, pos: 0 }
+    /// One-based `(line, column)` of the error, if known.
+    pub fn loc(&self) -> Option<(usize, usize)> {
+        match self {
+            LexError::UnexpectedChar { loc, .. }
+            | LexError::UnterminatedText { loc, .. }
+            | LexError::UnknownEscape { loc, .. } => *loc,
+        }
     }
+}
 
-    pub fn tokenize(&mut self) -> Result<Vec<Token>, LexError> {
-        let mut tokens = Vec::new();
+/// Names the position by line and column when known, else by byte.
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexError::UnexpectedChar { ch, .. } => write!(f, "unexpected character `{ch}`")?,
+            LexError::UnterminatedText { .. } => f.write_str("unterminated text literal")?,
+            LexError::UnknownEscape { ch, .. } => write!(f, "unknown escape `\\{ch}`")?,
+        }
 
-        while !self.eof() {
-            self.skip_whitespace();
+        match self.loc() {
+            Some((line, col)) => write!(f, " at line {line}, column {col}"),
+            None => write!(f, " at byte {}", self.pos()),
+        }
+    }
+}
 
-            if self.eof() {
-                break;
-            }
+impl Error for LexError {}
 
-            let start = self.pos;
-            let ch = self.peek_char();
+/// Streaming token iterator returned by [`Lexer::iter`].
+pub struct Tokens<'l, 'a> {
+    lexer: &'l mut Lexer<'a>,
+    done: bool,
+}
 
-            // ===== Digit-starting: NumLit, DecLit, or digit-leading Ident =====
-            //
-            // Rules:
-            // - If it starts with digits and continues with letters/_ -> Ident (e.g., 1a, 9lives, 123_456)
-            // - If it's only digits -> NumLit
-            // - Decimals are strictly digits '.' digits (e.g., 3.14)
-            // - Invalid decimals error: "1.", "1..2"
-            if ch.is_ascii_digit() {
-                let start = self.pos;
-
-                // First consume the leading digit run.
-                self.read_while(|c| c.is_ascii_digit());
+impl Iterator for Tokens<'_, '_> {
+    type Item = Result<Token, LexError>;
 
-                // Decimal form: digits '.' digits
-                if !self.eof() && self.peek_char() == '.' {
-                    self.bump_char(); // consume '.'
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
 
-                    // Require at least one digit after the decimal point.
-                    if self.eof() || !self.peek_char().is_ascii_digit() {
-                        return Err(LexError::UnexpectedChar {
-                            ch: '.',
-                            pos: self.pos - 1, // position of '.'
-                        });
-                    }
+        let result = self.lexer.next_token();
+        self.done = !matches!(&result, Ok(token) if token.kind != TokenKind::Eof);
+        Some(result)
+    }
+}
 
-                    self.read_while(|c| c.is_ascii_digit());
+pub struct Lexer<'a> {
+    src: &'a str,
+    pos: usize, // byte offset
+    line: usize, // one-based
+    col: usize,  // one-based, in characters
+}
 
-                    tokens.push(Token {
-                        kind: TokenKind::DecLit,
-                        lexeme: self.src[start..self.pos].to_string(),
-                        pos: start,
-                    });
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            line: 1,
+            col: 1,
+        }
+    }
 
-                    continue;
-                }
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, LexError> {
+        // Typical Druim averages a token every few bytes; reserving for
+        // one per four avoids most regrowth on large sources.
+        let mut tokens = Vec::with_capacity((self.src.len() - self.pos) / 4 + 1);
 
-                // If the next char is identifier-continue, this is a digit-leading identifier.
-                if !self.eof() {
-                    let next = self.peek_char();
-                    if next.is_ascii_alphabetic() || next == '_' {
-                        self.read_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        for token in self.iter() {
+            tokens.push(token?);
+        }
 
-                        tokens.push(Token {
-                            kind: TokenKind::Ident,
-                            lexeme: self.src[start..self.pos].to_string(),
-                            pos: start,
-                        });
+        Ok(tokens)
+    }
 
-                        continue;
+    /// Lex the whole input, continuing past errors.
+    ///
+    /// An unexpected character is recorded and skipped, and lexing
+    /// resumes at the next character. The tokens always end with `Eof`
+    /// and hold every token that could be lexed.
+    pub fn tokenize_recovering(&mut self) -> (Vec<Token>, Vec<LexError>) {
+        let mut tokens = Vec::with_capacity((self.src.len() - self.pos) / 4 + 1);
+        let mut errors = Vec::new();
+
+        loop {
+            match self.next_token() {
+                Ok(token) => {
+                    let done = token.kind == TokenKind::Eof;
+                    tokens.push(token);
+                    if done {
+                        return (tokens, errors);
                     }
                 }
-
-                // Otherwise it is pure digits.
-                tokens.push(Token {
-                    kind: TokenKind::NumLit,
-                    lexeme: self.src[start..self.pos].to_string(),
-                    pos: start,
-                });
-
-                continue;
+                Err(err) => {
+                    // An unknown character is left in place; step over it.
+                    if err.pos() == self.pos && !self.eof() {
+                        self.bump_char();
+                    }
+                    errors.push(err);
+                }
             }
+        }
+    }
 
-            // ===== Identifier or keyword (non-digit start) =====
-            if ch.is_ascii_alphabetic() || ch == '_' {
-                let text = self.read_while(|c| c.is_ascii_alphanumeric() || c == '_');
-
-                let kind = match text.as_str() {
-                    "num" => TokenKind::KwNum,
-                    "dec" => TokenKind::KwDec,
-                    "flag" => TokenKind::KwFlag,
-                    "text" => TokenKind::KwText,
-                    "void" => TokenKind::KwVoid,
-                    "fn" => TokenKind::KwFn,
-                    "ret" => TokenKind::KwRet,
-                    "loc" => TokenKind::KwLoc,
-                    _ => TokenKind::Ident,
-                };
-
-                tokens.push(Token {
-                    kind,
-                    lexeme: text,
-                    pos: start,
-                });
+    /// Lazily lex the remaining input.
+    ///
+    /// The iterator yields one token per `next()`, ending after the
+    /// `Eof` token or the first error.
+    pub fn iter(&mut self) -> Tokens<'_, 'a> {
+        Tokens {
+            lexer: self,
+            done: false,
+        }
+    }
 
-                continue;
-            }
+    /// Lex the next token, one at a time.
+    ///
+    /// This is the streaming entry point: once the input is exhausted it
+    /// returns an `Eof` token (and keeps doing so). Errors carry the
+    /// line and column tracked so far, so callers need no `Source`.
+    pub fn next_token(&mut self) -> Result<Token, LexError> {
+        self.skip_whitespace();
 
-            // ===== Text literal =====
-            if ch == '"' {
-                let text = self.read_text(start)?;
-                tokens.push(Token {
-                    kind: TokenKind::TextLit,
-                    lexeme: text,
-                    pos: start,
-                });
-                continue;
-            }
+        if self.eof() {
+            return Ok(Token {
+                kind: TokenKind::Eof,
+                lexeme: String::new(),
+                pos: self.pos,
+                end: self.pos,
+            });
+        }
 
-            // ===== Multi-char operators (longest first) =====
+        let start = self.pos;
+        let start_loc = self.loc();
+        let ch = self.peek_char();
+
+        // ===== Digit-starting: NumLit, DecLit, or digit-leading Ident =====
+        //
+        // Rules:
+        // - If it starts with digits and continues with letters/_ -> Ident (e.g., 1a, 9lives, 123_456)
+        // - If it's only digits -> NumLit
+        // - Decimals are strictly digits '.' digits (e.g., 3.14)
+        // - Invalid decimals error: "1.", "1..2"
+        if ch.is_ascii_digit() {
+            let start = self.pos;
 
-            // ===== Block delimiters (must be before single ':') =====
-            if self.match_str(":[") {
-                tokens.push(tok(TokenKind::ArrayStart, ":[", start));
-                continue;
-            }
-            if self.match_str("]:") {
-                tokens.push(tok(TokenKind::ArrayEnd, "]:", start));
-                continue;
-            }
-            if self.match_str("][") {
-                tokens.push(tok(TokenKind::ArrayChain, "][", start));
-                continue;
-            }
+            // First consume the leading digit run.
+            self.read_while(|c| c.is_ascii_digit());
 
-            if self.match_str(":{") {
-                tokens.push(tok(TokenKind::BlockStart, ":{", start));
-                continue;
-            }
-            if self.match_str("}:") {
-                tokens.push(tok(TokenKind::BlockEnd, "}:", start));
-                continue;
-            }
-            if self.match_str("}{") {
-                tokens.push(tok(TokenKind::BlockChain, "}{", start));
-                continue;
-            }
+            // Decimal form: digits '.' digits
+            if !self.eof() && self.peek_char() == '.' {
+                let dot_loc = self.loc();
+                self.bump_char(); // consume '.'
 
-            if self.match_str(":(") {
-                tokens.push(tok(TokenKind::FuncStart, ":(", start));
-                continue;
-            }
-            if self.match_str("):") {
-                tokens.push(tok(TokenKind::FuncEnd, "):", start));
-                continue;
-            }
-            if self.match_str(")(") {
-                tokens.push(tok(TokenKind::FuncChain, ")(", start));
-                continue;
-            }
+                // Require at least one digit after the decimal point.
+                if self.eof() || !self.peek_char().is_ascii_digit() {
+                    return Err(LexError::UnexpectedChar {
+                        ch: '.',
+                        pos: self.pos - 1, // position of '.'
+                        loc: Some(dot_loc),
+                    });
+                }
 
+                self.read_while(|c| c.is_ascii_digit());
 
-            // ===== Other multi-char operators =====
-            if self.match_str("?=") {
-                tokens.push(tok(TokenKind::Guard, "?=", start));
-                continue;
-            }
-            if self.match_str("=;") {
-                tokens.push(tok(TokenKind::DefineEmpty, "=;", start));
-                continue;
-            }
-            if self.match_str("|>") {
-                tokens.push(tok(TokenKind::Pipe, "|>", start));
-                continue;
+                return Ok(Token {
+                    kind: TokenKind::DecLit,
+                    lexeme: self.src[start..self.pos].to_string(),
+                    pos: start,
+                    end: self.pos,
+                });
             }
 
-            if self.match_str("==") {
-                tokens.push(tok(TokenKind::Eq, "==", start));
-                continue;
-            }
-            if self.match_str("!=") {
-                tokens.push(tok(TokenKind::Ne, "!=", start));
-                continue;
-            }
-            if self.match_str("<=") {
-                tokens.push(tok(TokenKind::Le, "<=", start));
-                continue;
-            }
-            if self.match_str(">=") {
-                tokens.push(tok(TokenKind::Ge, ">=", start));
-                continue;
-            }
+            // If the next char is identifier-continue, this is a digit-leading identifier.
+            if !self.eof() {
+                let next = self.peek_char();
+                if next.is_ascii_alphabetic() || next == '_' {
+                    self.read_while(|c| c.is_ascii_alphanumeric() || c == '_');
 
-            if self.match_str("&&") {
-                tokens.push(tok(TokenKind::And, "&&", start));
-                continue;
-            }
-            if self.match_str("||") {
-                tokens.push(tok(TokenKind::Or, "||", start));
-                continue;
-            }
-
-            if self.match_str("->") {
-                tokens.push(tok(TokenKind::ArrowR, "->", start));
-                continue;
-            }
-            if self.match_str("<-") {
-                tokens.push(tok(TokenKind::ArrowL, "<-", start));
-                continue;
+                    return Ok(Token {
+                        kind: TokenKind::Ident,
+                        lexeme: self.src[start..self.pos].to_string(),
+                        pos: start,
+                        end: self.pos,
+                    });
+                }
             }
 
-            // ===== Colon-family operators (longest first) =====
-            if self.match_str("::") {
-                tokens.push(tok(TokenKind::Has, "::", start));
-                continue;
-            }
-            if self.match_str(":=") {
-                tokens.push(tok(TokenKind::Copy, ":=", start));
-                continue;
-            }
-            if self.match_str(":?") {
-                tokens.push(tok(TokenKind::Present, ":?", start));
-                continue;
-            }
-            if self.match_str(":>") {
-                tokens.push(tok(TokenKind::Bind, ":>", start));
-                continue;
-            }
-            if self.match_char(':') {
-                tokens.push(tok(TokenKind::Colon, ":", start));
-                continue;
-            }
+            // Otherwise it is pure digits.
+            return Ok(Token {
+                kind: TokenKind::NumLit,
+                lexeme: self.src[start..self.pos].to_string(),
+                pos: start,
+                end: self.pos,
+            });
+        }
 
-            // ===== Single-char operators / punctuation =====
-            let kind = match ch {
-                '=' => TokenKind::Define,
-                '+' => TokenKind::Add,
-                '-' => TokenKind::Sub,
-                '*' => TokenKind::Mul,
-                '/' => TokenKind::Div,
-                '%' => TokenKind::Mod,
-                '>' => TokenKind::Gt,
-                '<' => TokenKind::Lt,
-                '(' => TokenKind::LParen,
-                ')' => TokenKind::RParen,
-                ',' => TokenKind::Comma,
-                ';' => TokenKind::Semicolon,
-                '!' => TokenKind::Not,
-                _ => {
-                    return Err(LexError::UnexpectedChar {
-                        ch,
-                        pos: self.pos,
-                    })
-                }
+        // ===== Identifier or keyword (non-digit start) =====
+        if ch.is_ascii_alphabetic() || ch == '_' {
+            let text = self.read_while(|c| c.is_ascii_alphanumeric() || c == '_');
+
+            let kind = match text.as_str() {
+                "num" => TokenKind::KwNum,
+                "dec" => TokenKind::KwDec,
+                "flag" => TokenKind::KwFlag,
+                "text" => TokenKind::KwText,
+                "void" => TokenKind::KwVoid,
+                "emp" => TokenKind::KwEmp,
+                "true" => TokenKind::KwTrue,
+                "false" => TokenKind::KwFalse,
+                "fn" => TokenKind::KwFn,
+                "ret" => TokenKind::KwRet,
+                "loc" => TokenKind::KwLoc,
+                _ => TokenKind::Ident,
             };
 
-            self.bump_char();
-            tokens.push(Token {
+            return Ok(Token {
                 kind,
-                lexeme: ch.to_string(),
+                lexeme: text,
                 pos: start,
+                end: self.pos,
             });
         }
 
-        tokens.push(Token {
-            kind: TokenKind::Eof,
-            lexeme: String::new(),
-            pos: self.pos,
-        });
+        // ===== Text literal =====
+        if ch == '"' {
+            let text = self.read_text(start, start_loc)?;
+            return Ok(Token {
+                kind: TokenKind::TextLit,
+                lexeme: text,
+                pos: start,
+                end: self.pos,
+            });
+        }
 
-        Ok(tokens)
+        // ===== Multi-char operators (longest first) =====
+        if let Some((kind, lexeme)) = self.match_operator() {
+            return Ok(tok(kind, lexeme, start));
+        }
+
+        // ===== Single-char operators / punctuation =====
+        let kind = match ch {
+            '=' => TokenKind::Define,
+            '+' => TokenKind::Add,
+            '-' => TokenKind::Sub,
+            '*' => TokenKind::Mul,
+            '/' => TokenKind::Div,
+            '%' => TokenKind::Mod,
+            '>' => TokenKind::Gt,
+            '<' => TokenKind::Lt,
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            ',' => TokenKind::Comma,
+            ';' => TokenKind::Semicolon,
+            '!' => TokenKind::Not,
+            _ => {
+                return Err(LexError::UnexpectedChar {
+                    ch,
+                    pos: self.pos,
+                    loc: Some(start_loc),
+                })
+            }
+        };
+
+        self.bump_char();
+        Ok(Token {
+            kind,
+            lexeme: ch.to_string(),
+            pos: start,
+            end: self.pos,
+        })
     }
 
     // ===== helpers =====
@@ -301,45 +372,97 @@ impl<'a> Lexer<'a> {
     }
 
 
-    fn read_text(&mut self, start_pos: usize) -> Result<String, LexError> {
+    /// Read a text literal, returning its decoded value.
+    ///
+    /// The escapes are `\n`, `\t`, `\r`, `\0`, `\\` and `\"`. An unknown
+    /// escape is reported only once the closing quote is found, so the
+    /// lexer resumes after the literal rather than inside it.
+    fn read_text(&mut self, start_pos: usize, start_loc: (usize, usize)) -> Result<String, LexError> {
         // consume opening quote
         self.bump_char();
-        let start = self.pos;
+
+        let mut text = String::new();
+        let mut bad_escape = None;
 
         while !self.eof() && self.peek_char() != '"' {
+            let ch = self.peek_char();
+            if ch != '\\' {
+                text.push(ch);
+                self.bump_char();
+                continue;
+            }
+
+            let escape_pos = self.pos;
+            let escape_loc = self.loc();
+            self.bump_char(); // backslash
+            if self.eof() {
+                break;
+            }
+
+            let escaped = self.peek_char();
+            match unescape(escaped) {
+                Some(decoded) => text.push(decoded),
+                None => {
+                    bad_escape.get_or_insert(LexError::UnknownEscape {
+                        ch: escaped,
+                        pos: escape_pos,
+                        loc: Some(escape_loc),
+                    });
+                }
+            }
             self.bump_char();
         }
 
         if self.eof() {
-            return Err(LexError::UnterminatedText { pos: start_pos });
+            return Err(LexError::UnterminatedText {
+                pos: start_pos,
+                loc: Some(start_loc),
+            });
         }
 
-        let text = self.src[start..self.pos].to_string();
         self.bump_char(); // closing quote
-        Ok(text)
+
+        match bad_escape {
+            Some(err) => Err(err),
+            None => Ok(text),
+        }
     }
 
     fn match_str(&mut self, s: &str) -> bool {
         if self.src[self.pos..].starts_with(s) {
+            // operators never span lines
             self.pos += s.len();
+            self.col += s.chars().count();
             true
         } else {
             false
         }
     }
 
-    fn match_char(&mut self, c: char) -> bool {
-        if !self.eof() && self.peek_char() == c {
-            self.bump_char();
-            true
-        } else {
-            false
+    fn match_operator(&mut self) -> Option<(TokenKind, &'static str)> {
+        for &kind in MULTI_CHAR_OPERATORS {
+            let lexeme = canonical_lexeme(kind).expect("operator must have a lexeme");
+            if self.match_str(lexeme) {
+                return Some((kind, lexeme));
+            }
         }
+        None
     }
 
     fn bump_char(&mut self) {
         let c = self.peek_char();
         self.pos += c.len_utf8();
+
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+    }
+
+    fn loc(&self) -> (usize, usize) {
+        (self.line, self.col)
     }
 
     fn peek_char(&self) -> char {
@@ -351,10 +474,24 @@ impl<'a> Lexer<'a> {
     }
 }
 
+/// The character a `\\` escape in a text literal stands for.
+fn unescape(ch: char) -> Option<char> {
+    Some(match ch {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '0' => '\0',
+        '\\' => '\\',
+        '"' => '"',
+        _ => return None,
+    })
+}
+
 fn tok(kind: TokenKind, lex: &str, pos: usize) -> Token {
     Token {
         kind,
         lexeme: lex.to_string(),
         pos,
+        end: pos + lex.len(),
     }
 }
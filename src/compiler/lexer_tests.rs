@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests {
-    use crate::compiler::lexer::Lexer;
-    use crate::compiler::token::TokenKind;
+    use crate::compiler::lexer::{Lexer, MULTI_CHAR_OPERATORS};
+    use crate::compiler::token::{canonical_lexeme, TokenKind};
     use crate::compiler::token::TokenKind::*;
 
     fn kinds(src: &str) -> Vec<TokenKind> {
@@ -48,6 +48,29 @@ mod tests {
         assert_eq!(ks[0], TextLit);
     }
 
+    #[test]
+    fn text_escapes_decode_but_span_the_source() {
+        let src = r#"x = "a\tb\0\"c\\";"#;
+        let tokens = Lexer::new(src).tokenize().unwrap();
+
+        let text = &tokens[2];
+        assert_eq!(text.kind, TextLit);
+        assert_eq!(text.lexeme, "a\tb\0\"c\\");
+        assert_eq!(&src[text.pos..text.end], r#""a\tb\0\"c\\""#);
+        assert_eq!(tokens[3].pos, text.end);
+    }
+
+    #[test]
+    fn unknown_text_escape_is_reported_after_the_literal() {
+        let (tokens, errors) = Lexer::new(r#"x = "a\qb"; y"#).tokenize_recovering();
+
+        let found: Vec<_> = errors.iter().map(|e| (e.to_string(), e.pos())).collect();
+        assert_eq!(found, vec![("unknown escape `\\q` at line 1, column 7".to_string(), 6)]);
+
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![Ident, Define, Semicolon, Ident, Eof]);
+    }
+
     #[test]
     fn block_tokens() {
         let src = ":[ 1, 2, 3 ][ \"carrots\", \"eggs\", \"milk\" ]: :{ a = 16; }{ d := a; }: fn my_function :( b )( a = b; ):";
@@ -102,4 +125,197 @@ mod tests {
         assert!(ks.contains(&Guard));
     }
 
+    #[test]
+    fn token_kinds_display_readably() {
+        assert_eq!(Define.to_string(), "`=`");
+        assert_eq!(BlockStart.to_string(), "`:{`");
+        assert_eq!(Coalesce.to_string(), "`??`");
+        assert_eq!(KwRet.to_string(), "`ret`");
+        assert_eq!(Ident.to_string(), "identifier");
+        assert_eq!(NumLit.to_string(), "number literal");
+        assert_eq!(Eof.to_string(), "end of input");
+    }
+
+    #[test]
+    fn canonical_lexemes_round_trip_through_lexer() {
+        let cases = [
+            (BlockStart, ":{"),
+            (BlockEnd, "}:"),
+            (FuncChain, ")("),
+            (Copy, ":="),
+            (Guard, "?="),
+            (Coalesce, "??"),
+            (DefineEmpty, "=;"),
+            (Has, "::"),
+            (Colon, ":"),
+            (Le, "<="),
+            (Not, "!"),
+            (KwFn, "fn"),
+            (KwVoid, "void"),
+        ];
+
+        for (kind, lexeme) in cases {
+            assert_eq!(canonical_lexeme(kind), Some(lexeme));
+
+            let tokens = Lexer::new(lexeme).tokenize().unwrap();
+            assert_eq!(tokens.len(), 2, "`{lexeme}` should lex as one token");
+            assert_eq!(tokens[0].kind, kind);
+            assert_eq!(tokens[0].lexeme, lexeme);
+        }
+    }
+
+    #[test]
+    fn adjacent_delimiters_split_by_maximal_munch() {
+        assert_eq!(kinds(":(:"), vec![FuncStart, Colon, Eof]);
+        assert_eq!(kinds("):)"), vec![FuncEnd, RParen, Eof]);
+        assert_eq!(kinds("}{:"), vec![BlockChain, Colon, Eof]);
+        assert_eq!(kinds("][]:"), vec![ArrayChain, ArrayEnd, Eof]);
+        assert_eq!(kinds(")():"), vec![FuncChain, FuncEnd, Eof]);
+        assert_eq!(kinds(":::="), vec![Has, Copy, Eof]);
+        assert_eq!(kinds("=;="), vec![DefineEmpty, Define, Eof]);
+    }
+
+    #[test]
+    fn lone_bracket_after_chain_is_rejected() {
+        // `][` is taken first, leaving a `]` that starts no token
+        assert!(Lexer::new("][]").tokenize().is_err());
+    }
+
+    #[test]
+    fn no_operator_is_shadowed_by_an_earlier_prefix() {
+        for (i, &later) in MULTI_CHAR_OPERATORS.iter().enumerate() {
+            let later = canonical_lexeme(later).unwrap();
+            for &earlier in &MULTI_CHAR_OPERATORS[..i] {
+                let earlier = canonical_lexeme(earlier).unwrap();
+                assert!(
+                    !later.starts_with(earlier),
+                    "`{earlier}` is matched before the longer `{later}`"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn variable_lexeme_kinds_have_no_canonical_lexeme() {
+        assert_eq!(canonical_lexeme(Ident), None);
+        assert_eq!(canonical_lexeme(NumLit), None);
+        assert_eq!(canonical_lexeme(DecLit), None);
+        assert_eq!(canonical_lexeme(TextLit), None);
+        assert_eq!(canonical_lexeme(Eof), None);
+    }
+
+    #[test]
+    fn streaming_unexpected_char_reports_line_and_column() {
+        use crate::compiler::lexer::LexError;
+
+        let mut lx = Lexer::new("x = 1;\n  y = é @;");
+
+        let err = loop {
+            match lx.next_token() {
+                Ok(tok) => assert_ne!(tok.kind, Eof, "expected a lex error"),
+                Err(err) => break err,
+            }
+        };
+
+        match err {
+            LexError::UnexpectedChar { ch, pos, loc } => {
+                assert_eq!(ch, 'é');
+                assert_eq!(pos, 13);
+                assert_eq!(loc, Some((2, 7)));
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn unterminated_text_reports_opening_quote_location() {
+        let err = Lexer::new("a = 1;\nb = \"open").tokenize().unwrap_err();
+
+        assert_eq!(err.pos(), 11);
+        assert_eq!(err.loc(), Some((2, 5)));
+    }
+
+    #[test]
+    fn iterator_matches_tokenize() {
+        let src = "fn f :(a)( ret a ?? 1; ): x = f(\"t\");";
+
+        let expected = Lexer::new(src).tokenize().unwrap();
+        let streamed: Vec<_> = Lexer::new(src).iter().map(Result::unwrap).collect();
+
+        assert_eq!(streamed, expected);
+        assert_eq!(streamed.last().map(|t| t.kind), Some(Eof));
+    }
+
+    #[test]
+    fn iterator_stops_after_error() {
+        let mut lx = Lexer::new("x @ y");
+        let results: Vec<_> = lx.iter().collect();
+
+        assert_eq!(results.len(), 2);
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn lex_errors_display_their_position() {
+        use crate::compiler::lexer::LexError;
+
+        let err = LexError::UnexpectedChar { ch: '.', pos: 3, loc: None };
+        assert_eq!(err.to_string(), "unexpected character `.` at byte 3");
+
+        let err = LexError::UnterminatedText { pos: 4, loc: Some((1, 5)) };
+        assert_eq!(err.to_string(), "unterminated text literal at line 1, column 5");
+    }
+
+    #[test]
+    fn lex_errors_convert_to_boxed_errors() {
+        fn lex(src: &str) -> Result<usize, Box<dyn std::error::Error>> {
+            Ok(Lexer::new(src).tokenize()?.len())
+        }
+
+        let err = lex("x = @;").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected character `@` at line 1, column 5");
+    }
+
+    #[test]
+    fn tokenize_large_source_is_unchanged() {
+        let src = "value = total + 12 * 3;\n".repeat(500);
+
+        let tokens = Lexer::new(&src).tokenize().unwrap();
+
+        assert_eq!(tokens.len(), 500 * 8 + 1);
+        assert_eq!(tokens[..8], Lexer::new("value = total + 12 * 3;").tokenize().unwrap()[..8]);
+        assert_eq!(tokens.last().map(|t| t.kind), Some(Eof));
+    }
+
+    #[test]
+    fn flag_keywords() {
+        assert_eq!(kinds("true false"), vec![KwTrue, KwFalse, Eof]);
+        assert_eq!(kinds("truthy falsey true_"), vec![Ident, Ident, Ident, Eof]);
+    }
+
+    #[test]
+    fn recovering_tokenize_reports_every_bad_character() {
+        let (tokens, errors) = Lexer::new("a @ b é c").tokenize_recovering();
+
+        let lexemes: Vec<_> = tokens.iter().map(|t| (t.kind, t.lexeme.as_str())).collect();
+        assert_eq!(lexemes, vec![(Ident, "a"), (Ident, "b"), (Ident, "c"), (Eof, "")]);
+
+        let found: Vec<_> = errors.iter().map(|e| (e.to_string(), e.pos())).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("unexpected character `@` at line 1, column 3".to_string(), 2),
+                ("unexpected character `é` at line 1, column 7".to_string(), 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn recovering_tokenize_matches_tokenize_on_valid_input() {
+        let src = "x = f(1, 2.5) ?? \"t\";";
+        let (tokens, errors) = Lexer::new(src).tokenize_recovering();
+
+        assert!(errors.is_empty());
+        assert_eq!(tokens, Lexer::new(src).tokenize().unwrap());
+    }
 }
@@ -0,0 +1,529 @@
+use crate::compiler::ast::{Block, BlockExpr, Func, Literal, Node, Program};
+use crate::compiler::error::{Diagnostic, Span};
+use crate::compiler::visit::{walk_block, walk_block_expr, walk_func, walk_node, Visitor};
+
+/// A lint pass that keeps state per lexical scope.
+///
+/// Passes get their [`Visitor`] from this trait: it opens a scope for
+/// every block chain, block expression and function, hands the contents
+/// to the pass's hooks, and closes the scope again. The top-level scope
+/// is the pass's own to open and close.
+trait ScopedLint {
+    fn enter_scope(&mut self);
+
+    fn leave_scope(&mut self);
+
+    /// Check one node. The default keeps descending.
+    fn check_node(&mut self, node: &Node)
+    where
+        Self: Sized,
+    {
+        walk_node(self, node);
+    }
+
+    /// Check a function, inside its scope. The default walks the
+    /// parameter defaults and the body.
+    fn check_func(&mut self, func: &Func)
+    where
+        Self: Sized,
+    {
+        walk_func(self, func);
+    }
+}
+
+impl<L: ScopedLint> Visitor for L {
+    fn visit_node(&mut self, node: &Node) {
+        self.check_node(node);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        self.enter_scope();
+        walk_block(self, block);
+        self.leave_scope();
+    }
+
+    fn visit_block_expr(&mut self, block: &BlockExpr) {
+        self.enter_scope();
+        walk_block_expr(self, block);
+        self.leave_scope();
+    }
+
+    fn visit_func(&mut self, func: &Func) {
+        self.enter_scope();
+        self.check_func(func);
+        self.leave_scope();
+    }
+}
+
+/// Warn about bindings that are never read.
+///
+/// Definitions, empty definitions, copies and binds introduce names.
+/// A name is used if it is read anywhere its binding is visible,
+/// including from a function body, regardless of order. Scoping follows
+/// the evaluator: `loc` always introduces a new binding, while a plain
+/// binding of a name that already exists only updates it.
+///
+/// Function names and parameters are not checked. Each warning points
+/// at the statement that introduced the binding.
+pub fn check_unused(program: &Program) -> Vec<Diagnostic> {
+    let mut checker = UnusedChecker {
+        scopes: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    checker.enter_scope();
+    checker.visit_program(program);
+    checker.leave_scope();
+
+    checker.warnings
+}
+
+struct Binding {
+    name: String,
+    span: Span,
+    used: bool,
+}
+
+#[derive(Default)]
+struct LintScope {
+    /// Names bound in this scope, in binding order.
+    bindings: Vec<Binding>,
+
+    /// Reads that no enclosing binding had claimed yet when they were
+    /// seen; resolved when the scope closes.
+    pending: Vec<String>,
+}
+
+impl LintScope {
+    fn binding_mut(&mut self, name: &str) -> Option<&mut Binding> {
+        self.bindings.iter_mut().find(|b| b.name == name)
+    }
+}
+
+struct UnusedChecker {
+    scopes: Vec<LintScope>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl UnusedChecker {
+    fn bind(&mut self, name: &str, span: Span, local: bool) {
+        if !local && self.scopes.iter().any(|s| s.bindings.iter().any(|b| b.name == name)) {
+            return;
+        }
+
+        let scope = self.scopes.last_mut().expect("no lint scope");
+        if scope.binding_mut(name).is_none() {
+            scope.bindings.push(Binding {
+                name: name.to_string(),
+                span,
+                used: false,
+            });
+        }
+    }
+
+    fn read(&mut self, name: &str) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(binding) = scope.binding_mut(name) {
+                binding.used = true;
+                return;
+            }
+        }
+
+        self.scopes
+            .last_mut()
+            .expect("no lint scope")
+            .pending
+            .push(name.to_string());
+    }
+
+    fn binding(&mut self, node: &Node, local: bool) {
+        match node {
+            Node::Define(def) => {
+                self.visit_node(&def.value);
+                self.bind(&def.name, *def.span, local);
+            }
+            Node::DefineEmpty(def) => self.bind(&def.name, *def.span, local),
+            Node::Copy(copy) => {
+                self.read(&copy.target);
+                self.bind(&copy.name, *copy.span, local);
+            }
+            Node::Bind(bind) => {
+                self.read(&bind.target);
+                self.bind(&bind.name, *bind.span, local);
+            }
+            other => walk_node(self, other),
+        }
+    }
+}
+
+impl ScopedLint for UnusedChecker {
+    fn enter_scope(&mut self) {
+        self.scopes.push(LintScope::default());
+    }
+
+    /// Close the innermost scope: settle its pending reads, report its
+    /// unused bindings, and hand unresolved reads to the parent.
+    fn leave_scope(&mut self) {
+        let mut scope = self.scopes.pop().expect("lint scope underflow");
+
+        let mut unresolved = Vec::new();
+        for name in std::mem::take(&mut scope.pending) {
+            match scope.binding_mut(&name) {
+                Some(binding) => binding.used = true,
+                None => unresolved.push(name),
+            }
+        }
+
+        for binding in scope.bindings {
+            if !binding.used {
+                self.warnings.push(
+                    Diagnostic::warning(format!("unused binding `{}`", binding.name), binding.span)
+                        .with_help("Remove the binding, or read it somewhere."),
+                );
+            }
+        }
+
+        if let Some(parent) = self.scopes.last_mut() {
+            parent.pending.extend(unresolved);
+        }
+    }
+
+    fn check_node(&mut self, node: &Node) {
+        match node {
+            Node::Ident(ident) => self.read(&ident.name),
+            Node::Local(inner) => self.binding(inner, true),
+            Node::Define(_) | Node::DefineEmpty(_) | Node::Copy(_) | Node::Bind(_) => {
+                self.binding(node, false)
+            }
+            other => walk_node(self, other),
+        }
+    }
+
+    fn check_func(&mut self, func: &Func) {
+        for param in &func.params {
+            if let Some(default) = &param.default {
+                self.visit_node(default);
+            }
+        }
+
+        // Parameters shadow outer names inside the body, but are exempt
+        // from the check themselves.
+        for param in &func.params {
+            self.bind(&param.name, *func.span, true);
+            self.read(&param.name);
+        }
+
+        for node in &func.body {
+            self.visit_node(node);
+        }
+    }
+}
+
+/// Warn about guard branches that can never be reached.
+///
+/// A guard takes the first truthy branch, so every branch after a literal
+/// that is always truthy (a nonzero `num`, non-empty `text`, or `true`)
+/// is dead. One warning is reported per guard, pointing at the guard,
+/// with a secondary label for the first unreachable branch.
+pub fn check_unreachable_guards(program: &Program) -> Vec<Diagnostic> {
+    let mut checker = GuardChecker { warnings: Vec::new() };
+    checker.visit_program(program);
+    checker.warnings
+}
+
+struct GuardChecker {
+    warnings: Vec<Diagnostic>,
+}
+
+impl Visitor for GuardChecker {
+    fn visit_node(&mut self, node: &Node) {
+        if let Node::Guard(guard) = node {
+            let last = guard.branches.len().saturating_sub(1);
+            if let Some(i) = guard.branches[..last]
+                .iter()
+                .position(|branch| always_truthy(&branch.expr))
+            {
+                self.warnings.push(
+                    Diagnostic::warning(
+                        format!(
+                            "unreachable guard branch: branch {} of `{}` is always truthy",
+                            i + 1,
+                            guard.target,
+                        ),
+                        *guard.span,
+                    )
+                    .with_secondary(*guard.branches[i + 1].span, "first unreachable branch")
+                    .with_help("Remove the branches after the always-truthy one."),
+                );
+            }
+        }
+
+        walk_node(self, node);
+    }
+}
+
+fn always_truthy(node: &Node) -> bool {
+    match node {
+        Node::Lit(Literal::Num(n)) => *n != 0,
+        Node::Lit(Literal::Text(s)) => !s.is_empty(),
+        Node::Lit(Literal::Flag(b)) => *b,
+        _ => false,
+    }
+}
+
+/// Warn about a name defined twice in the same scope.
+///
+/// A second definition silently replaces the first, which is usually a
+/// mistake. Defining a name declared empty (`x =;`) is not a
+/// redefinition, and neither is a `loc` definition in a nested scope,
+/// which shadows rather than replaces. A plain definition of a name from
+/// an enclosing scope updates that binding and is not reported either,
+/// nor is a definition whose value reads the name, like
+/// `total = total + 1;`.
+///
+/// Each warning points at the second definition and carries a
+/// secondary label for the one it replaces.
+pub fn check_redefinitions(program: &Program) -> Vec<Diagnostic> {
+    let mut checker = RedefinitionChecker {
+        scopes: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    checker.enter_scope();
+    checker.visit_program(program);
+    checker.leave_scope();
+
+    checker.warnings
+}
+
+/// Definition kinds that matter for redefinition.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Defined {
+    /// `x =;`
+    Empty,
+    /// `x = 1;`
+    Value,
+    /// `x = x + 1;`, a value computed from the previous one
+    Update,
+}
+
+struct RedefinitionChecker {
+    /// Names defined in each open scope, innermost last, with the kind
+    /// and span of their latest definition.
+    scopes: Vec<Vec<(String, Defined, Span)>>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl RedefinitionChecker {
+    fn define(&mut self, name: &str, span: Span, kind: Defined, local: bool) {
+        let (inner, outer) = self.scopes.split_last_mut().expect("no lint scope");
+
+        if let Some(previous) = inner.iter_mut().find(|(n, ..)| n == name) {
+            if previous.1 != Defined::Empty && kind != Defined::Update {
+                self.warnings.push(
+                    Diagnostic::warning(format!("`{name}` is defined twice in the same scope"), span)
+                        .with_secondary(previous.2, "first defined here")
+                        .with_help("Rename one of the bindings, or remove the first definition."),
+                );
+            }
+            previous.1 = kind;
+            previous.2 = span;
+            return;
+        }
+
+        if !local && outer.iter().any(|scope| scope.iter().any(|(n, ..)| n == name)) {
+            return;
+        }
+
+        inner.push((name.to_string(), kind, span));
+    }
+}
+
+impl ScopedLint for RedefinitionChecker {
+    fn enter_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn leave_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn check_node(&mut self, node: &Node) {
+        let (def, local) = match node {
+            Node::Local(inner) => (inner.as_ref(), true),
+            other => (other, false),
+        };
+
+        match def {
+            Node::Define(d) => {
+                self.visit_node(&d.value);
+                let kind = if reads_name(&d.value, &d.name) {
+                    Defined::Update
+                } else {
+                    Defined::Value
+                };
+                self.define(&d.name, *d.span, kind, local);
+            }
+            Node::DefineEmpty(d) => self.define(&d.name, *d.span, Defined::Empty, local),
+            _ => walk_node(self, node),
+        }
+    }
+}
+
+/// Whether `node` reads `name` anywhere inside it.
+fn reads_name(node: &Node, name: &str) -> bool {
+    struct Reads<'a> {
+        name: &'a str,
+        found: bool,
+    }
+
+    impl Visitor for Reads<'_> {
+        fn visit_node(&mut self, node: &Node) {
+            match node {
+                Node::Ident(ident) if ident.name == self.name => self.found = true,
+                other => walk_node(self, other),
+            }
+        }
+    }
+
+    let mut reads = Reads { name, found: false };
+    reads.visit_node(node);
+    reads.found
+}
+
+/// Warn about statements that follow a `ret` in the same statement list.
+///
+/// Function bodies, block segments and block expressions are checked,
+/// including nested ones. Only the first unreachable statement of each
+/// list is reported, pointing at the statement, with a secondary label
+/// for the `ret`.
+pub fn check_unreachable_code(program: &Program) -> Vec<Diagnostic> {
+    let mut checker = UnreachableChecker { warnings: Vec::new() };
+    checker.visit_program(program);
+    checker.warnings
+}
+
+struct UnreachableChecker {
+    warnings: Vec<Diagnostic>,
+}
+
+impl UnreachableChecker {
+    fn check<'n>(&mut self, mut nodes: impl Iterator<Item = &'n Node>) {
+        let Some(ret) = nodes.find_map(|node| match node {
+            Node::Ret(ret) => Some(ret),
+            _ => None,
+        }) else {
+            return;
+        };
+
+        if let Some(dead) = nodes.next() {
+            // A trailing value made of literals and names records no
+            // position; it starts right after the `ret`.
+            let span = dead.span().unwrap_or(Span {
+                start: ret.span.end,
+                end: ret.span.end,
+            });
+
+            self.warnings.push(
+                Diagnostic::warning(format!("unreachable statement `{dead}`"), span)
+                    .with_secondary(*ret.span, "any code after this `ret` is unreachable")
+                    .with_help("Remove the statement, or move it before the `ret`."),
+            );
+        }
+    }
+}
+
+impl Visitor for UnreachableChecker {
+    fn visit_block(&mut self, block: &Block) {
+        for segment in &block.segments {
+            self.check(segment.nodes.iter());
+        }
+        walk_block(self, block);
+    }
+
+    fn visit_block_expr(&mut self, block: &BlockExpr) {
+        self.check(block.nodes.iter().chain(block.value.as_deref()));
+        walk_block_expr(self, block);
+    }
+
+    fn visit_func(&mut self, func: &Func) {
+        self.check(func.body.iter());
+        walk_func(self, func);
+    }
+}
+
+/// Warn about statements that obviously do little or nothing to their
+/// target.
+///
+/// Two patterns are reported:
+/// - a define that adds or subtracts a literal zero to its own target,
+///   `x = x + 0;`, `x = 0 + x;` or `x = x - 0;`, which leaves it unchanged
+/// - a guard whose only branch reads its own target, `x ?= x;`, which
+///   keeps a truthy value and only replaces a falsey one with `void`
+///
+/// Adding a `dec` zero is not flagged, since it turns a `num` into a
+/// `dec`. Each warning points at the statement.
+pub fn check_self_references(program: &Program) -> Vec<Diagnostic> {
+    let mut checker = SelfReferenceChecker { warnings: Vec::new() };
+    checker.visit_program(program);
+    checker.warnings
+}
+
+struct SelfReferenceChecker {
+    warnings: Vec<Diagnostic>,
+}
+
+impl Visitor for SelfReferenceChecker {
+    fn visit_node(&mut self, node: &Node) {
+        match node {
+            Node::Guard(guard)
+                if matches!(guard.branches.as_slice(), [only] if is_ident(&only.expr, &guard.target)) =>
+            {
+                self.warnings.push(
+                    Diagnostic::warning(
+                        format!(
+                            "self-guard `{node}` only replaces a falsey `{}` with `void`",
+                            guard.target
+                        ),
+                        *guard.span,
+                    )
+                    .with_help(format!(
+                        "Write `{} =;` to clear it, or remove the statement.",
+                        guard.target
+                    )),
+                );
+            }
+
+            Node::Define(def) if is_zero_identity(&def.value, &def.name) => {
+                self.warnings.push(
+                    Diagnostic::warning(
+                        format!("pointless statement `{node}` leaves its target unchanged"),
+                        *def.span,
+                    )
+                    .with_help("Remove the statement."),
+                );
+            }
+
+            _ => {}
+        }
+
+        walk_node(self, node);
+    }
+}
+
+/// Whether `value` is `name + 0`, `0 + name` or `name - 0`.
+fn is_zero_identity(value: &Node, name: &str) -> bool {
+    match value {
+        Node::Add(l, r) => (is_ident(l, name) && is_zero(r)) || (is_zero(l) && is_ident(r, name)),
+        Node::Sub(l, r) => is_ident(l, name) && is_zero(r),
+        _ => false,
+    }
+}
+
+fn is_ident(node: &Node, name: &str) -> bool {
+    matches!(node, Node::Ident(ident) if ident.name == name)
+}
+
+fn is_zero(node: &Node) -> bool {
+    matches!(node, Node::Lit(Literal::Num(0)))
+}
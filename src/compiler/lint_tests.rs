@@ -0,0 +1,189 @@
+use crate::compiler::ast::Program;
+use crate::compiler::error::{Diagnostic, Severity, Span};
+use crate::compiler::lexer::Lexer;
+use crate::compiler::lint::{
+    check_redefinitions, check_self_references, check_unreachable_code, check_unreachable_guards,
+    check_unused,
+};
+use crate::compiler::parser::Parser;
+
+fn parse_program(src: &str) -> Program {
+    let tokens = Lexer::new(src).tokenize().expect("lexing failed");
+    let mut parser = Parser::new(&tokens);
+    parser.parse_program().expect("failed to parse program")
+}
+
+fn unused(src: &str) -> Vec<String> {
+    check_unused(&parse_program(src))
+        .into_iter()
+        .map(|d| {
+            assert_eq!(d.severity, Severity::Warning);
+            d.message
+        })
+        .collect()
+}
+
+#[test]
+fn reports_only_unread_bindings() {
+    assert_eq!(unused("x = 1; y = 2; out(x);"), vec!["unused binding `y`"]);
+}
+
+#[test]
+fn inner_scope_is_reported_independently() {
+    assert_eq!(
+        unused("x = 1; :{ loc x = 2; z = 3; }: out(x);"),
+        vec!["unused binding `x`", "unused binding `z`"]
+    );
+}
+
+#[test]
+fn plain_rebinding_updates_the_outer_name() {
+    assert!(unused("x = 1; :{ x = 2; }: out(x);").is_empty());
+}
+
+#[test]
+fn function_bodies_may_read_later_bindings() {
+    assert!(unused("fn f :(a)( ret a + g; ): g = 1; b := c; c = 2; f(b);").is_empty());
+}
+
+#[test]
+fn warns_about_branches_after_truthy_literal() {
+    let warnings = check_unreachable_guards(&parse_program("x ?= 5 : y;"));
+
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Warning);
+    assert!(warnings[0].message.contains("unreachable guard branch"));
+    assert_eq!(warnings[0].secondary.len(), 1);
+    assert_eq!(warnings[0].secondary[0], (Span { start: 9, end: 10 }, "first unreachable branch".into()));
+}
+
+#[test]
+fn identifier_branch_is_not_statically_truthy() {
+    assert!(check_unreachable_guards(&parse_program("x ?= a : y;")).is_empty());
+}
+
+#[test]
+fn truthy_last_branch_is_fine() {
+    assert!(check_unreachable_guards(&parse_program("x ?= a : 0 : \"\" : 1;")).is_empty());
+}
+
+#[test]
+fn warns_about_redefinition_in_the_same_scope() {
+    let warnings = check_redefinitions(&parse_program("x = 1; x = 2; :{ loc y = 1; loc y = 2; }:"));
+
+    let messages: Vec<&str> = warnings.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "`x` is defined twice in the same scope",
+            "`y` is defined twice in the same scope",
+        ]
+    );
+    assert_eq!(warnings[0].severity, Severity::Warning);
+    assert_eq!(warnings[0].secondary[0], (Span { start: 0, end: 6 }, "first defined here".into()));
+}
+
+#[test]
+fn definitions_computed_from_the_name_do_not_warn() {
+    let program = parse_program("total = 0; total = total + 1; n = 1; n = f(:[ n ]:);");
+    assert!(check_redefinitions(&program).is_empty());
+
+    // the label points at the latest definition being replaced
+    let warnings = check_redefinitions(&parse_program("x = 1; x = x * 2; x = 5;"));
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].span, Span { start: 18, end: 24 });
+    assert_eq!(warnings[0].secondary[0].0, Span { start: 7, end: 17 });
+}
+
+#[test]
+fn nested_scopes_and_empty_declarations_do_not_warn() {
+    let program = parse_program(
+        "x = 1; :{ loc x = 2; }: :{ x = 3; }: fn f :()( loc x = 4; ret x; ): y =; y = 5;",
+    );
+
+    assert!(check_redefinitions(&program).is_empty());
+}
+
+#[test]
+fn warns_about_statements_after_ret() {
+    let warnings = check_unreachable_code(&parse_program("fn f :()( ret 1; x = 2; ):"));
+
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Warning);
+    assert_eq!(warnings[0].message, "unreachable statement `x = 2;`");
+    assert_eq!(
+        warnings[0].secondary[0],
+        (Span { start: 10, end: 16 }, "any code after this `ret` is unreachable".into())
+    );
+}
+
+#[test]
+fn checks_nested_block_expressions() {
+    let program = parse_program("fn f :()( y = :[ ret 1; 2 ]:; ret y; ):");
+    let messages: Vec<String> = check_unreachable_code(&program)
+        .into_iter()
+        .map(|d| d.message)
+        .collect();
+
+    assert_eq!(messages, ["unreachable statement `2`"]);
+}
+
+#[test]
+fn ret_as_last_statement_is_fine() {
+    assert!(check_unreachable_code(&parse_program("fn f :(a)( b = a + 1; ret b; ):")).is_empty());
+}
+
+#[test]
+fn warns_about_self_guards_and_zero_identities() {
+    let program = parse_program("x = 1; x ?= x; :{ x = 0 + x; }: fn f :(y)( y = y - 0; ret y; ):");
+    let messages: Vec<String> = check_self_references(&program)
+        .into_iter()
+        .map(|d| {
+            assert_eq!(d.severity, Severity::Warning);
+            d.message
+        })
+        .collect();
+
+    assert_eq!(
+        messages,
+        [
+            "self-guard `x ?= x;` only replaces a falsey `x` with `void`",
+            "pointless statement `x = 0 + x;` leaves its target unchanged",
+            "pointless statement `y = y - 0;` leaves its target unchanged",
+        ]
+    );
+}
+
+#[test]
+fn self_references_that_change_the_value_are_fine() {
+    let program = parse_program("x = 1; x ?= x : 2; x = x + 1; x = x + 0.0; y = 0 - x; x ?= y;");
+
+    assert!(check_self_references(&program).is_empty());
+}
+
+#[test]
+fn warnings_point_at_the_statement() {
+    let span = |warnings: Vec<Diagnostic>| warnings[0].span;
+
+    let program = parse_program("x = 1; loc y = 2; out(x);");
+    assert_eq!(span(check_unused(&program)), Span { start: 7, end: 17 });
+
+    let program = parse_program("x = 1; x = 2;");
+    assert_eq!(span(check_redefinitions(&program)), Span { start: 7, end: 13 });
+
+    let program = parse_program("x ?= 5 : y;");
+    assert_eq!(span(check_unreachable_guards(&program)), Span { start: 0, end: 11 });
+
+    let program = parse_program("fn f :()( ret 1; g(2); ):");
+    assert_eq!(span(check_unreachable_code(&program)), Span { start: 17, end: 21 });
+
+    // a trailing value without a recorded position starts after the `ret`
+    let program = parse_program("x = :[ ret 1; 2 ]:;");
+    assert_eq!(span(check_unreachable_code(&program)), Span { start: 13, end: 13 });
+
+    let program = parse_program("x = 1; x = x + 0;");
+    assert_eq!(span(check_self_references(&program)), Span { start: 7, end: 17 });
+
+    let program = parse_program("x = 0; x ?= x;");
+    assert_eq!(span(check_self_references(&program)), Span { start: 7, end: 14 });
+}
@@ -5,6 +5,17 @@ pub mod parser;
 pub mod error;
 pub mod diagnostic;
 pub mod semantics;
+pub mod format;
+pub mod pipeline;
+pub mod analyze;
+pub mod visit;
+pub mod fold;
+pub mod lint;
+
+#[cfg(feature = "cli")]
+pub mod repl;
+
+pub use pipeline::{compile, run};
 
 #[cfg(test)]
 mod lexer_tests;
@@ -18,3 +29,27 @@ mod diagnostic_tests;
 #[cfg(test)]
 mod diagnostic_builder_tests;
 
+#[cfg(test)]
+mod format_tests;
+
+
+#[cfg(test)]
+mod pipeline_tests;
+
+#[cfg(test)]
+mod analyze_tests;
+
+#[cfg(test)]
+mod visit_tests;
+
+#[cfg(test)]
+mod fold_tests;
+
+#[cfg(test)]
+mod lint_tests;
+
+#[cfg(all(test, feature = "serde"))]
+mod ast_tests;
+
+#[cfg(all(test, feature = "cli"))]
+mod repl_tests;
@@ -1,27 +1,72 @@
 use crate::compiler::ast::{
-    Bind, Block, BlockSegment, Call, Copy, Define, DefineEmpty, Func,
-    Guard, GuardBranch, Literal, Node, Param, Program, Ret,
+    Bind, Block, BlockExpr, BlockSegment, Call, Copy, Define, DefineEmpty, Func,
+    Guard, GuardBranch, Ident, Literal, Node, NodeSpan, Param, Program, Ret, Type,
 };
-use crate::compiler::error::{Span, Diagnostic};
-use crate::compiler::token::{Token, TokenKind};
+use std::borrow::Cow;
+
+use crate::compiler::error::{codes, Span, Diagnostic};
+use crate::compiler::lexer::Lexer;
+use crate::compiler::pipeline::lex_diagnostic;
+use crate::compiler::token::{canonical_lexeme, Token, TokenKind};
+
+/// Default limit on expression nesting, see [`Parser::with_max_depth`].
+///
+/// Chosen so that parsing a maximally nested expression fits in a 2 MiB
+/// thread stack even in unoptimized builds.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
 
 pub struct Parser<'a> {
-    tokens: &'a [Token],
+    /// Borrowed from the caller, or owned when built by
+    /// [`Parser::from_source`].
+    tokens: Cow<'a, [Token]>,
     index: usize,
     in_block: bool,
     in_func: bool, 
+    depth: usize,
+    max_depth: usize,
+    /// Whether the expression `parse_bp` just returned is an
+    /// unparenthesized comparison.
+    ended_in_comparison: bool,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(tokens: &'a [Token]) -> Self {
         Self {
-            tokens,
+            tokens: Cow::Borrowed(tokens),
             index: 0,
             in_block: false,
             in_func: false,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            ended_in_comparison: false,
         }
     }
 
+    /// Lex `src` and build a parser that owns the resulting tokens.
+    ///
+    /// A lex error is returned as a diagnostic, as [`compile`] reports it.
+    ///
+    /// [`compile`]: crate::compiler::compile
+    pub fn from_source(src: &str) -> Result<Parser<'static>, Diagnostic> {
+        let tokens = Lexer::new(src).tokenize().map_err(|err| lex_diagnostic(&err, src))?;
+
+        Ok(Parser {
+            tokens: Cow::Owned(tokens),
+            ..Parser::new(&[])
+        })
+    }
+
+    /// Limit how deeply expressions (groups, operands, block expressions)
+    /// may nest before parsing fails with a diagnostic.
+    ///
+    /// The limit keeps hostile input from overflowing the stack. A flat
+    /// chain such as `1 + 2 + 3` is parsed in a loop, so its length does
+    /// not count.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
     pub fn parse_program(&mut self) -> Result<Program, Diagnostic> {
         let mut nodes = Vec::new();
 
@@ -33,6 +78,89 @@ impl<'a> Parser<'a> {
         Ok(Program { nodes })
     }
 
+    /// Parse a whole program, continuing past statement errors.
+    ///
+    /// Each failed statement is recorded and dropped from the AST; the
+    /// parser then skips to the end of that statement (its `;`, or the
+    /// closer of the block or function it opened) and resumes there.
+    pub fn parse_program_recovering(&mut self) -> (Program, Vec<Diagnostic>) {
+        let mut nodes = Vec::new();
+        let mut diagnostics = Vec::new();
+
+        while self.peek_kind() != TokenKind::Eof {
+            let start = self.index;
+
+            match self.parse_node() {
+                Ok(node) => nodes.push(node),
+                Err(diagnostic) => {
+                    diagnostics.push(diagnostic);
+                    self.synchronize(start);
+                }
+            }
+        }
+
+        (Program { nodes }, diagnostics)
+    }
+
+    /// Skip past the statement that failed to parse.
+    ///
+    /// Rescans from `start`, the statement's first token, tracking
+    /// delimiter depth so that `;` and closers inside the statement's own
+    /// structures do not end it early:
+    /// - a function or block statement ends at the closer that returns
+    ///   to depth zero
+    /// - any other statement ends at the next `;` at depth zero
+    /// - a closer at depth zero closes nothing; as the first token it is
+    ///   the whole failed statement and is consumed, otherwise the skip
+    ///   stops in front of it
+    fn synchronize(&mut self, start: usize) {
+        // a failed structure may not have restored its context flags
+        self.in_block = false;
+        self.in_func = false;
+
+        self.index = start;
+
+        let structural = matches!(
+            self.peek_kind(),
+            TokenKind::KwFn | TokenKind::BlockStart
+        );
+        let mut depth = 0usize;
+
+        while let Some(tok) = self.peek() {
+            match tok.kind {
+                TokenKind::Eof => break,
+
+                TokenKind::BlockStart | TokenKind::FuncStart | TokenKind::ArrayStart => {
+                    depth += 1;
+                }
+
+                TokenKind::BlockEnd | TokenKind::FuncEnd | TokenKind::ArrayEnd => {
+                    if depth == 0 {
+                        if self.index == start {
+                            self.bump();
+                        }
+                        break;
+                    }
+
+                    depth -= 1;
+                    if depth == 0 && structural {
+                        self.bump();
+                        break;
+                    }
+                }
+
+                TokenKind::Semicolon if depth == 0 => {
+                    self.bump();
+                    break;
+                }
+
+                _ => {}
+            }
+
+            self.bump();
+        }
+    }
+
     pub fn parse_node(&mut self) -> Result<Node, Diagnostic> {
         match self.peek_kind() {
             // ---------- structural constructs ----------
@@ -52,6 +180,22 @@ impl<'a> Parser<'a> {
                 self.parse_func()
             }
 
+            // ---------- stray closers ----------
+            TokenKind::BlockEnd | TokenKind::FuncEnd | TokenKind::ArrayEnd => {
+                let kind = self.peek_kind();
+                Err(
+                    Diagnostic::error(
+                        format!("unmatched closing delimiter {kind}"),
+                        self.current_span(),
+                    )
+                    .with_code(codes::UNMATCHED_DELIMITER)
+                    .with_help(
+                        "This delimiter does not close anything.\n\
+                        Remove it, or add the opening delimiter it belongs to.",
+                    ),
+                )
+            }
+
             // ---------- everything else ----------
             _ => self.parse_statement_entry(),
         }
@@ -59,9 +203,21 @@ impl<'a> Parser<'a> {
 
     fn parse_statement_entry(&mut self) -> Result<Node, Diagnostic> {
         let mut i = self.index;
+        let mut depth = 0usize;
 
         while let Some(tok) = self.tokens.get(i) {
             match tok.kind {
+                // block expressions own everything up to their `]:`
+                TokenKind::ArrayStart => {
+                    depth += 1;
+                    i += 1;
+                }
+                TokenKind::ArrayEnd => {
+                    depth = depth.saturating_sub(1);
+                    i += 1;
+                }
+                _ if depth > 0 => i += 1,
+
                 // statement-defining keywords
                 TokenKind::KwRet => {
                     return self.parse_ret();
@@ -97,19 +253,130 @@ impl<'a> Parser<'a> {
         self.parse_call_statement()
     }
 
+    /// Index of the `;` terminating the statement at the current token.
+    ///
+    /// Semicolons inside nested block expressions (`:[ ... ]:`) belong to
+    /// those expressions and are skipped.
+    fn statement_end(&self) -> Option<usize> {
+        let mut depth = 0usize;
+
+        for (off, tok) in self.tokens[self.index..].iter().enumerate() {
+            match tok.kind {
+                TokenKind::ArrayStart => depth += 1,
+                TokenKind::ArrayEnd => depth = depth.saturating_sub(1),
+                TokenKind::Semicolon if depth == 0 => return Some(self.index + off),
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// The first closing delimiter (`}:`, `]:` or `):`) from index `from`
+    /// on that no opener after `from` accounts for: the one that closes
+    /// the structure open at `from`, whichever family it belongs to.
+    fn pending_closer(&self, from: usize) -> Option<&Token> {
+        let mut depth = 0usize;
+
+        for tok in &self.tokens[from..] {
+            match tok.kind {
+                TokenKind::BlockStart | TokenKind::ArrayStart | TokenKind::FuncStart => depth += 1,
+                TokenKind::BlockEnd | TokenKind::ArrayEnd | TokenKind::FuncEnd => {
+                    if depth == 0 {
+                        return Some(tok);
+                    }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// Span from the current token through the token before index `end`.
+    fn leftover_span(&self, end: usize) -> Span {
+        let first = token_span(&self.tokens[self.index]);
+        match end.checked_sub(1) {
+            Some(last) if last > self.index => first.merge(token_span(&self.tokens[last])),
+            _ => first,
+        }
+    }
+
+    /// Zero-width span where the missing `;` of the statement at the
+    /// current token belongs: just past its last token.
+    ///
+    /// The statement is taken to run until the end of input or a closing
+    /// delimiter that does not belong to it. Falls back to the current
+    /// token when the statement is empty.
+    fn missing_semicolon_span(&self) -> Span {
+        let mut depth = 0usize;
+        let mut last = None;
+
+        for tok in &self.tokens[self.index..] {
+            match tok.kind {
+                TokenKind::Eof => break,
+                TokenKind::BlockStart
+                | TokenKind::ArrayStart
+                | TokenKind::FuncStart
+                | TokenKind::LParen => depth += 1,
+                TokenKind::BlockEnd
+                | TokenKind::ArrayEnd
+                | TokenKind::FuncEnd
+                | TokenKind::RParen => {
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+            last = Some(tok);
+        }
+
+        match last {
+            Some(tok) => {
+                let end = tok.end;
+                Span { start: end, end }
+            }
+            None => self.current_span(),
+        }
+    }
+
+    /// Indices of the tokens from the current position up to `end` that
+    /// are not nested inside a block expression.
+    ///
+    /// Structural scans use this so that statements inside a block
+    /// expression are not mistaken for operators of the outer statement.
+    fn top_level_indices(&self, end: usize) -> Vec<usize> {
+        let mut depth = 0usize;
+        let mut indices = Vec::new();
+
+        for i in self.index..end {
+            match self.tokens[i].kind {
+                TokenKind::ArrayStart => depth += 1,
+                TokenKind::ArrayEnd => depth = depth.saturating_sub(1),
+                _ if depth == 0 => indices.push(i),
+                _ => {}
+            }
+        }
+
+        indices
+    }
+
     fn parse_ret(&mut self) -> Result<Node, Diagnostic> {
+        let start = self.current_span().start;
+
         // We are committing to parsing a return statement
         self.bump(); // consume `ret`
 
         // 🔒 REQUIRED: verify semicolon exists BEFORE parsing anything else
-        let stmt_end = match self.tokens[self.index..]
-            .iter()
-            .position(|t| t.kind == TokenKind::Semicolon)
-        {
-            Some(off) => self.index + off,
+        let stmt_end = match self.statement_end() {
+            Some(end) => end,
             None => {
                 return Err(
-                    Diagnostic::error("unterminated return statement", self.current_span())
+                    Diagnostic::error("unterminated return statement", self.missing_semicolon_span())
+                        .with_code(codes::UNTERMINATED_STATEMENT)
                         .with_help(
                             "Druim expected a semicolon `;` to terminate this return statement.\n\
                             Examples:\n\
@@ -123,12 +390,14 @@ impl<'a> Parser<'a> {
         // `ret;` — valid, no value
         if self.peek_kind() == TokenKind::Semicolon {
             self.bump(); // consume `;`
-            return Ok(Node::Ret(Ret { value: None }));
+            return Ok(Node::Ret(Ret {
+                value: None,
+                span: self.span_from(start),
+            }));
         }
 
         // Disallow statement operators inside return value
-        let mut i = self.index;
-        while i < stmt_end {
+        for i in self.top_level_indices(stmt_end) {
             match self.tokens[i].kind {
                 TokenKind::Define
                 | TokenKind::DefineEmpty
@@ -141,9 +410,10 @@ impl<'a> Parser<'a> {
                             "invalid return statement",
                             Span {
                                 start: self.tokens[i].pos,
-                                end: self.tokens[i].pos + self.tokens[i].lexeme.len(),
+                                end: self.tokens[i].end,
                             },
                         )
+                        .with_code(codes::INVALID_RETURN)
                         .with_help(
                             "Return values must be a value expression or function call.\n\
                             Statements are not allowed inside `ret`.\n\
@@ -153,7 +423,6 @@ impl<'a> Parser<'a> {
                 }
                 _ => {}
             }
-            i += 1;
         }
 
         // ✅ Structure validated — now parse the return value
@@ -162,9 +431,9 @@ impl<'a> Parser<'a> {
                 && self.peek_kind() == TokenKind::Ident
             {
                 let ident = self.bump().expect("identifier token must exist");
-                Node::Ident(ident.lexeme.clone())
+                ident_node(ident)
             } else {
-                self.parse_rhs()?
+                self.parse_rhs(codes::INVALID_RETURN)?
             };
 
         // Consume terminating semicolon
@@ -172,10 +441,12 @@ impl<'a> Parser<'a> {
 
         Ok(Node::Ret(Ret {
             value: Some(Box::new(value)),
+            span: self.span_from(start),
         }))
     }
 
     fn parse_define_empty(&mut self) -> Result<Node, Diagnostic> {
+        let start = self.current_span().start;
 
         // Optional `loc` (syntactic only — no semantics here)
         let is_local = if self.peek_kind() == TokenKind::KwLoc {
@@ -185,12 +456,15 @@ impl<'a> Parser<'a> {
             false
         };
 
+        let ty = self.parse_type_annotation();
+
         // Identifier (single assertion)
         let ident_tok = match self.bump() {
             Some(tok) => tok,
             None => {
                 return Err(
                     Diagnostic::error("invalid empty definition", self.current_span())
+                        .with_code(codes::INVALID_DEFINE)
                         .with_help(
                             "Druim empty definitions must begin with an identifier.\n\
                             Example: `x =;`",
@@ -199,15 +473,20 @@ impl<'a> Parser<'a> {
             }
         };
 
+        if let Some(err) = invalid_identifier(ident_tok, codes::INVALID_DEFINE) {
+            return Err(err);
+        }
+
         if ident_tok.kind != TokenKind::Ident {
             return Err(
                 Diagnostic::error(
                     "invalid empty definition",
                     Span {
                         start: ident_tok.pos,
-                        end: ident_tok.pos + ident_tok.lexeme.len(),
+                        end: ident_tok.end,
                     },
                 )
+                .with_code(codes::INVALID_DEFINE)
                 .with_help(
                     "Druim empty definitions must begin with an identifier.\n\
                     Example: `x =;`",
@@ -229,6 +508,7 @@ impl<'a> Parser<'a> {
             | TokenKind::Guard => {
                 return Err(
                     Diagnostic::error("invalid empty definition", self.current_span())
+                        .with_code(codes::INVALID_DEFINE)
                         .with_help(
                             "Statement operators cannot be chained.\n\
                             Split this into multiple statements.\n\
@@ -239,7 +519,11 @@ impl<'a> Parser<'a> {
             _ => {}
         }
 
-        let node = Node::DefineEmpty(DefineEmpty { name });
+        let node = Node::DefineEmpty(DefineEmpty {
+            name,
+            ty,
+            span: self.span_from(start),
+        });
 
         if is_local {
             Ok(Node::Local(Box::new(node)))
@@ -248,16 +532,56 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Consume an optional leading type keyword.
+    fn parse_type_annotation(&mut self) -> Option<Type> {
+        let ty = match self.peek_kind() {
+            TokenKind::KwNum => Type::Num,
+            TokenKind::KwDec => Type::Dec,
+            TokenKind::KwFlag => Type::Flag,
+            TokenKind::KwText => Type::Text,
+            _ => return None,
+        };
+
+        self.bump();
+        Some(ty)
+    }
+
+    /// Reject a type annotation in front of a non-define target, as in
+    /// `num x := y;`, where it would otherwise read as a bad target name.
+    fn reject_annotation(&self, code: &'static str) -> Result<(), Diagnostic> {
+        let is_type = matches!(
+            self.peek_kind(),
+            TokenKind::KwNum | TokenKind::KwDec | TokenKind::KwFlag | TokenKind::KwText
+        );
+
+        if is_type && self.tokens.get(self.index + 1).is_some_and(|t| t.kind == TokenKind::Ident) {
+            return Err(
+                Diagnostic::error(
+                    "type annotations are only allowed on `=` definitions",
+                    self.current_span(),
+                )
+                .with_code(code)
+                .with_help(
+                    "Only `=` and `=;` definitions take a declared type.\n\
+                    Remove the type, or define the value with `=`.\n\
+                    Example: `num x = 42;`",
+                ),
+            );
+        }
+
+        Ok(())
+    }
+
     fn parse_define(&mut self) -> Result<Node, Diagnostic> {
+        let start = self.current_span().start;
+
         // Statement MUST terminate
-        let stmt_end = match self.tokens[self.index..]
-            .iter()
-            .position(|t| t.kind == TokenKind::Semicolon)
-        {
-            Some(off) => self.index + off,
+        let stmt_end = match self.statement_end() {
+            Some(end) => end,
             None => {
                 return Err(
-                    Diagnostic::error("unterminated define statement", self.current_span())
+                    Diagnostic::error("unterminated define statement", self.missing_semicolon_span())
+                        .with_code(codes::UNTERMINATED_STATEMENT)
                         .with_help(
                             "Druim expected a semicolon `;` to terminate this define statement.\n\
                             Example: `x = 42;`",
@@ -274,12 +598,15 @@ impl<'a> Parser<'a> {
             false
         };
 
+        let ty = self.parse_type_annotation();
+
         // Identifier (single assertion)
         let ident_tok = match self.bump() {
             Some(tok) => tok,
             None => {
                 return Err(
                     Diagnostic::error("invalid define statement", self.current_span())
+                        .with_code(codes::INVALID_DEFINE)
                         .with_help(
                             "Druim define statements must begin with an identifier.\n\
                             Example: `x = 42;`",
@@ -288,15 +615,20 @@ impl<'a> Parser<'a> {
             }
         };
 
+        if let Some(err) = invalid_identifier(ident_tok, codes::INVALID_DEFINE) {
+            return Err(err);
+        }
+
         if ident_tok.kind != TokenKind::Ident {
             return Err(
                 Diagnostic::error(
                     "invalid define statement",
                     Span {
                         start: ident_tok.pos,
-                        end: ident_tok.pos + ident_tok.lexeme.len(),
+                        end: ident_tok.end,
                     },
                 )
+                .with_code(codes::INVALID_DEFINE)
                 .with_help(
                     "Druim define statements must begin with an identifier.\n\
                     Example: `x = 42;`",
@@ -313,6 +645,7 @@ impl<'a> Parser<'a> {
         if self.peek_kind() == TokenKind::Semicolon {
             return Err(
                 Diagnostic::error("invalid define statement", self.current_span())
+                    .with_code(codes::INVALID_DEFINE)
                     .with_help(
                         "A define statement requires a value after `=`.\n\
                         Did you mean to use the empty define operator?\n\
@@ -322,8 +655,7 @@ impl<'a> Parser<'a> {
         }
 
         // Structural scan: no statement operators allowed inside RHS
-        let mut i = self.index;
-        while i < stmt_end {
+        for i in self.top_level_indices(stmt_end) {
             match self.tokens[i].kind {
                 TokenKind::Define => {
                     return Err(
@@ -331,9 +663,10 @@ impl<'a> Parser<'a> {
                             "invalid define statement",
                             Span {
                                 start: self.tokens[i].pos,
-                                end: self.tokens[i].pos + self.tokens[i].lexeme.len(),
+                                end: self.tokens[i].end,
                             },
                         )
+                        .with_code(codes::INVALID_DEFINE)
                         .with_help(
                             "Define statements cannot be chained.\n\
                             Split this into multiple statements.\n\
@@ -351,9 +684,10 @@ impl<'a> Parser<'a> {
                             "invalid define statement",
                             Span {
                                 start: self.tokens[i].pos,
-                                end: self.tokens[i].pos + self.tokens[i].lexeme.len(),
+                                end: self.tokens[i].end,
                             },
                         )
+                        .with_code(codes::INVALID_DEFINE)
                         .with_help(
                             "Define statements cannot contain other statement operators.\n\
                             Split this into separate statements.",
@@ -363,30 +697,10 @@ impl<'a> Parser<'a> {
 
                 _ => {}
             }
-
-            i += 1;
-        }
-
-        // RHS must not be a single identifier
-        if self.index + 1 == stmt_end && self.tokens[self.index].kind == TokenKind::Ident {
-            return Err(
-                Diagnostic::error(
-                    "invalid define statement",
-                    Span {
-                        start: self.tokens[self.index].pos,
-                        end: self.tokens[self.index].pos + self.tokens[self.index].lexeme.len(),
-                    },
-                )
-                .with_help(
-                    "Define statements cannot define directly from another identifier.\n\
-                    Use `:=` to copy a value or `:>` to create a live binding.\n\
-                    Examples: `a := b;` or `a :> b;`",
-                ),
-            );
         }
 
         // Parse RHS LAST
-        let value = self.parse_rhs()?;
+        let value = self.parse_rhs(codes::INVALID_DEFINE)?;
 
         // The parsed expression must consume the entire RHS.
         // Only the terminating semicolon may remain.
@@ -394,7 +708,8 @@ impl<'a> Parser<'a> {
             Some(tok) => tok,
             None => {
                 return Err(
-                    Diagnostic::error("unterminated define statement", self.current_span())
+                    Diagnostic::error("unterminated define statement", self.missing_semicolon_span())
+                        .with_code(codes::UNTERMINATED_STATEMENT)
                         .with_help(
                             "Druim expected a semicolon `;` after the defined value.\n\
                             Example: `x = 42;`",
@@ -405,13 +720,8 @@ impl<'a> Parser<'a> {
 
         if next_tok.kind != TokenKind::Semicolon {
             return Err(
-                Diagnostic::error(
-                    "invalid define statement",
-                    Span {
-                        start: next_tok.pos,
-                        end: next_tok.pos + next_tok.lexeme.len(),
-                    },
-                )
+                Diagnostic::error("invalid define statement", self.leftover_span(stmt_end))
+                .with_code(codes::INVALID_DEFINE)
                 .with_help(
                     "A Druim define statement must contain exactly one complete expression.\n\
                     Unexpected tokens remain after the defined value.\n\
@@ -425,7 +735,9 @@ impl<'a> Parser<'a> {
 
         let node = Node::Define(Define {
             name,
+            ty,
             value: Box::new(value),
+            span: self.span_from(start),
         });
 
         if is_local {
@@ -436,15 +748,14 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_copy(&mut self) -> Result<Node, Diagnostic> {
+        let start = self.current_span().start;
 
-        match self.tokens[self.index..]
-            .iter()
-            .position(|t| t.kind == TokenKind::Semicolon)
-        {
-            Some(off) => self.index + off,
+        match self.statement_end() {
+            Some(end) => end,
             None => {
                 return Err(
-                    Diagnostic::error("unterminated copy statement", self.current_span())
+                    Diagnostic::error("unterminated copy statement", self.missing_semicolon_span())
+                        .with_code(codes::UNTERMINATED_STATEMENT)
                         .with_help(
                             "Druim expected a semicolon `;` to terminate this copy statement.\n\
                             Example: `a := b;`",
@@ -461,12 +772,15 @@ impl<'a> Parser<'a> {
             false
         };
 
+        self.reject_annotation(codes::INVALID_COPY)?;
+
         // 3️⃣ Left-hand identifier (single assertion)
         let lhs_tok = match self.bump() {
             Some(tok) => tok,
             None => {
                 return Err(
                     Diagnostic::error("invalid copy statement", self.current_span())
+                        .with_code(codes::INVALID_COPY)
                         .with_help(
                             "Copy statements must begin with an identifier.\n\
                             Example: `a := b;`",
@@ -475,15 +789,20 @@ impl<'a> Parser<'a> {
             }
         };
 
+        if let Some(err) = invalid_identifier(lhs_tok, codes::INVALID_COPY) {
+            return Err(err);
+        }
+
         if lhs_tok.kind != TokenKind::Ident {
             return Err(
                 Diagnostic::error(
                     "invalid copy statement",
                     Span {
                         start: lhs_tok.pos,
-                        end: lhs_tok.pos + lhs_tok.lexeme.len(),
+                        end: lhs_tok.end,
                     },
                 )
+                .with_code(codes::INVALID_COPY)
                 .with_help(
                     "Copy statements must begin with an identifier.\n\
                     Example: `a := b;`",
@@ -502,6 +821,7 @@ impl<'a> Parser<'a> {
             None => {
                 return Err(
                     Diagnostic::error("invalid copy statement", self.current_span())
+                        .with_code(codes::INVALID_COPY)
                         .with_help(
                             "Copy statements require an identifier after `:=`.\n\
                             Example: `a := b;`",
@@ -516,9 +836,10 @@ impl<'a> Parser<'a> {
                     "invalid copy statement",
                     Span {
                         start: rhs_tok.pos,
-                        end: rhs_tok.pos + rhs_tok.lexeme.len(),
+                        end: rhs_tok.end,
                     },
                 )
+                .with_code(codes::INVALID_COPY)
                 .with_help(
                     "Copy statements require an identifier after `:=`.\n\
                     Example: `a := b;`",
@@ -532,7 +853,8 @@ impl<'a> Parser<'a> {
             Some(tok) => tok,
             None => {
                 return Err(
-                    Diagnostic::error("unterminated copy statement", self.current_span())
+                    Diagnostic::error("unterminated copy statement", self.missing_semicolon_span())
+                        .with_code(codes::UNTERMINATED_STATEMENT)
                         .with_help(
                             "Druim expected a semicolon `;` after the copy target.\n\
                             Example: `a := b;`",
@@ -555,9 +877,10 @@ impl<'a> Parser<'a> {
                 "invalid copy statement",
                 Span {
                     start: next_tok.pos,
-                    end: next_tok.pos + next_tok.lexeme.len(),
+                    end: next_tok.end,
                 },
-            );
+            )
+            .with_code(codes::INVALID_COPY);
 
             return if is_chained {
                 Err(diagnostic.with_help(
@@ -577,7 +900,11 @@ impl<'a> Parser<'a> {
         // 7️⃣ Consume `;`
         self.bump();
 
-        let node = Node::Copy(Copy { name, target });
+        let node = Node::Copy(Copy {
+            name,
+            target,
+            span: self.span_from(start),
+        });
 
         if is_local {
             Ok(Node::Local(Box::new(node)))
@@ -587,14 +914,14 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_bind(&mut self) -> Result<Node, Diagnostic> {
-        match self.tokens[self.index..]
-            .iter()
-            .position(|t| t.kind == TokenKind::Semicolon)
-        {
+        let start = self.current_span().start;
+
+        match self.statement_end() {
             Some(_) => {}
             None => {
                 return Err(
-                    Diagnostic::error("unterminated bind statement", self.current_span())
+                    Diagnostic::error("unterminated bind statement", self.missing_semicolon_span())
+                        .with_code(codes::UNTERMINATED_STATEMENT)
                         .with_help(
                             "Druim expected a semicolon `;` to terminate this bind statement.\n\
                             Example: `a :> b;`",
@@ -611,12 +938,15 @@ impl<'a> Parser<'a> {
             false
         };
 
+        self.reject_annotation(codes::INVALID_BIND)?;
+
         // Left-hand identifier
         let lhs_tok = match self.bump() {
             Some(tok) => tok,
             None => {
                 return Err(
                     Diagnostic::error("invalid bind statement", self.current_span())
+                        .with_code(codes::INVALID_BIND)
                         .with_help(
                             "Bind statements must begin with an identifier.\n\
                             Example: `a :> b;`",
@@ -625,15 +955,20 @@ impl<'a> Parser<'a> {
             }
         };
 
+        if let Some(err) = invalid_identifier(lhs_tok, codes::INVALID_BIND) {
+            return Err(err);
+        }
+
         if lhs_tok.kind != TokenKind::Ident {
             return Err(
                 Diagnostic::error(
                     "invalid bind statement",
                     Span {
                         start: lhs_tok.pos,
-                        end: lhs_tok.pos + lhs_tok.lexeme.len(),
+                        end: lhs_tok.end,
                     },
                 )
+                .with_code(codes::INVALID_BIND)
                 .with_help(
                     "Bind statements must begin with an identifier.\n\
                     Example: `a :> b;`",
@@ -652,6 +987,7 @@ impl<'a> Parser<'a> {
             None => {
                 return Err(
                     Diagnostic::error("invalid bind statement", self.current_span())
+                        .with_code(codes::INVALID_BIND)
                         .with_help(
                             "Bind statements require an identifier after `:>`.\n\
                             Example: `a :> b;`",
@@ -666,9 +1002,10 @@ impl<'a> Parser<'a> {
                     "invalid bind statement",
                     Span {
                         start: rhs_tok.pos,
-                        end: rhs_tok.pos + rhs_tok.lexeme.len(),
+                        end: rhs_tok.end,
                     },
                 )
+                .with_code(codes::INVALID_BIND)
                 .with_help(
                     "Bind statements require an identifier after `:>`.\n\
                     Example: `a :> b;`",
@@ -683,7 +1020,8 @@ impl<'a> Parser<'a> {
             Some(tok) => tok,
             None => {
                 return Err(
-                    Diagnostic::error("unterminated bind statement", self.current_span())
+                    Diagnostic::error("unterminated bind statement", self.missing_semicolon_span())
+                        .with_code(codes::UNTERMINATED_STATEMENT)
                         .with_help(
                             "Druim expected a semicolon `;` after the bind target.\n\
                             Example: `a :> b;`",
@@ -706,9 +1044,10 @@ impl<'a> Parser<'a> {
                 "invalid bind statement",
                 Span {
                     start: next_tok.pos,
-                    end: next_tok.pos + next_tok.lexeme.len(),
+                    end: next_tok.end,
                 },
-            );
+            )
+            .with_code(codes::INVALID_BIND);
 
             return if is_chained {
                 Err(diagnostic.with_help(
@@ -728,7 +1067,11 @@ impl<'a> Parser<'a> {
         // Consume `;`
         self.bump();
 
-        let node = Node::Bind(Bind { name, target });
+        let node = Node::Bind(Bind {
+            name,
+            target,
+            span: self.span_from(start),
+        });
 
         if is_local {
             Ok(Node::Local(Box::new(node)))
@@ -738,15 +1081,15 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_guard(&mut self) -> Result<Node, Diagnostic> {
+        let start = self.current_span().start;
+
         // Find statement terminator FIRST
-        let stmt_end = match self.tokens[self.index..]
-            .iter()
-            .position(|t| t.kind == TokenKind::Semicolon)
-        {
-            Some(off) => self.index + off,
+        let stmt_end = match self.statement_end() {
+            Some(end) => end,
             None => {
                 return Err(
-                    Diagnostic::error("unterminated guard statement", self.current_span())
+                    Diagnostic::error("unterminated guard statement", self.missing_semicolon_span())
+                        .with_code(codes::UNTERMINATED_STATEMENT)
                         .with_help(
                             "Druim expected a semicolon `;` to terminate this guard statement.\n\
                             Example: `x ?= y;`",
@@ -763,12 +1106,15 @@ impl<'a> Parser<'a> {
             false
         };
 
+        self.reject_annotation(codes::INVALID_GUARD)?;
+
         // Identifier (single assertion)
         let ident_tok = match self.bump() {
             Some(tok) => tok,
             None => {
                 return Err(
                     Diagnostic::error("invalid guard statement", self.current_span())
+                        .with_code(codes::INVALID_GUARD)
                         .with_help(
                             "Druim guard statements must begin with an identifier.\n\
                             Example: `x ?= y;`",
@@ -777,15 +1123,20 @@ impl<'a> Parser<'a> {
             }
         };
 
+        if let Some(err) = invalid_identifier(ident_tok, codes::INVALID_GUARD) {
+            return Err(err);
+        }
+
         if ident_tok.kind != TokenKind::Ident {
             return Err(
                 Diagnostic::error(
                     "invalid guard statement",
                     Span {
                         start: ident_tok.pos,
-                        end: ident_tok.pos + ident_tok.lexeme.len(),
+                        end: ident_tok.end,
                     },
                 )
+                .with_code(codes::INVALID_GUARD)
                 .with_help(
                     "Druim guard statements must begin with an identifier.\n\
                     Example: `x ?= y;`",
@@ -803,6 +1154,7 @@ impl<'a> Parser<'a> {
             TokenKind::Semicolon | TokenKind::Colon => {
                 return Err(
                     Diagnostic::error("invalid guard statement", self.current_span())
+                        .with_code(codes::INVALID_GUARD)
                         .with_help(
                             "A Druim guard statement requires a value after `?=`.\n\
                             Did you mean to use the DefineEmpty operator?\n\
@@ -814,8 +1166,7 @@ impl<'a> Parser<'a> {
         }
 
         // Structural scan: no statement operators inside guard
-        let mut i = self.index;
-        while i < stmt_end {
+        for i in self.top_level_indices(stmt_end) {
             match self.tokens[i].kind {
                 TokenKind::Define
                 | TokenKind::DefineEmpty
@@ -827,9 +1178,10 @@ impl<'a> Parser<'a> {
                             "invalid guard statement",
                             Span {
                                 start: self.tokens[i].pos,
-                                end: self.tokens[i].pos + self.tokens[i].lexeme.len(),
+                                end: self.tokens[i].end,
                             },
                         )
+                        .with_code(codes::INVALID_GUARD)
                         .with_help(
                             "Druim guard branches must be values, not statements.\n\
                             Split this into separate statements.",
@@ -838,15 +1190,13 @@ impl<'a> Parser<'a> {
                 }
                 _ => {}
             }
-            i += 1;
         }
 
-        // Parse branches LAST
+        // Parse branches LAST. Branches use `parse_expr`, not `parse_rhs`:
+        // a bare identifier is a valid branch and reads that name.
         let mut branches = Vec::new();
 
-        branches.push(GuardBranch {
-            expr: self.parse_expr()?,
-        });
+        branches.push(self.parse_guard_branch()?);
 
         while self.peek_kind() == TokenKind::Colon {
             self.bump(); // consume `:`
@@ -854,6 +1204,7 @@ impl<'a> Parser<'a> {
             if self.peek_kind() == TokenKind::Semicolon {
                 return Err(
                     Diagnostic::error("invalid guard statement", self.current_span())
+                        .with_code(codes::INVALID_GUARD)
                         .with_help(
                             "Druim expected a value after `:` in guard statement.\n\
                             Example: `x ?= y : z;`",
@@ -861,9 +1212,7 @@ impl<'a> Parser<'a> {
                 );
             }
 
-            branches.push(GuardBranch {
-                expr: self.parse_expr()?,
-            });
+            branches.push(self.parse_guard_branch()?);
         }
 
         // The final branch must consume the complete guard RHS.
@@ -872,7 +1221,8 @@ impl<'a> Parser<'a> {
             Some(tok) => tok,
             None => {
                 return Err(
-                    Diagnostic::error("unterminated guard statement", self.current_span())
+                    Diagnostic::error("unterminated guard statement", self.missing_semicolon_span())
+                        .with_code(codes::UNTERMINATED_STATEMENT)
                         .with_help(
                             "Druim expected a semicolon `;` after the final guard branch.\n\
                             Example: `x ?= y : z;`",
@@ -883,13 +1233,8 @@ impl<'a> Parser<'a> {
 
         if next_tok.kind != TokenKind::Semicolon {
             return Err(
-                Diagnostic::error(
-                    "invalid guard statement",
-                    Span {
-                        start: next_tok.pos,
-                        end: next_tok.pos + next_tok.lexeme.len(),
-                    },
-                )
+                Diagnostic::error("invalid guard statement", self.leftover_span(stmt_end))
+                .with_code(codes::INVALID_GUARD)
                 .with_help(
                     "Each Druim guard branch must contain exactly one complete expression.\n\
                     Unexpected tokens remain after the final branch.\n\
@@ -904,6 +1249,7 @@ impl<'a> Parser<'a> {
         let node = Node::Guard(Guard {
             target: name,
             branches,
+            span: self.span_from(start),
         });
 
         if is_local {
@@ -913,10 +1259,23 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn parse_guard_branch(&mut self) -> Result<GuardBranch, Diagnostic> {
+        let start = self.current_span().start;
+        let expr = self.parse_expr()?;
+
+        Ok(GuardBranch {
+            expr,
+            span: self.span_from(start),
+        })
+    }
+
     fn parse_block(&mut self) -> Result<Node, Diagnostic> {
+        let start = self.current_span().start;
+
         if self.in_func {
             return Err(
                 Diagnostic::error("block not allowed in function body", self.current_span())
+                    .with_code(codes::NESTING_NOT_ALLOWED)
                     .with_help(
                         "Blocks cannot appear inside function bodies.\n\
                         Use chained blocks at the top level instead.",
@@ -927,6 +1286,7 @@ impl<'a> Parser<'a> {
         if self.in_block {
             return Err(
                 Diagnostic::error("nested block not allowed", self.current_span())
+                    .with_code(codes::NESTING_NOT_ALLOWED)
                     .with_help(
                         "Druim blocks may be chained but not nested.\n\
                         Use `}{` to create a new block at the same level.",
@@ -935,26 +1295,26 @@ impl<'a> Parser<'a> {
         }
 
         // Consume block start
+        let open_span = self.current_span();
         self.bump(); // `:{`
 
+        // Verify block closes with its own delimiter before parsing contents
+        match self.pending_closer(self.index) {
+            Some(tok) if tok.kind == TokenKind::BlockEnd => {}
+            Some(tok) => return Err(mismatched_closer(tok, TokenKind::BlockEnd, open_span)),
+            None => {
+                return Err(
+                    Diagnostic::error("unterminated block structure", self.current_span())
+                        .with_code(codes::UNTERMINATED_DELIMITER)
+                        .with_help("Druim expected a closing block delimiter `}:`."),
+                );
+            }
+        }
+
         // Enter block context
         let prev = self.in_block;
         self.in_block = true;
 
-        // Verify block can close before parsing contents
-        let has_end = self.tokens[self.index..]
-            .iter()
-            .any(|t| t.kind == TokenKind::BlockEnd);
-
-        if !has_end {
-            self.in_block = prev;
-
-            return Err(
-                Diagnostic::error("unterminated block structure", self.current_span())
-                    .with_help("Druim expected a closing block delimiter `}:`."),
-            );
-        }
-
         // Parse block-chain segments
         let mut segments = Vec::new();
         let mut nodes = Vec::new();
@@ -969,7 +1329,7 @@ impl<'a> Parser<'a> {
                 continue;
             }
 
-            nodes.push(self.parse_statement_entry()?);
+            nodes.push(self.parse_node()?);
         }
 
         // Store the final segment
@@ -981,13 +1341,19 @@ impl<'a> Parser<'a> {
         // Exit block context
         self.in_block = prev;
 
-        Ok(Node::Block(Block { segments }))
+        Ok(Node::Block(Block {
+            segments,
+            span: self.span_from(start),
+        }))
     }
 
     fn parse_func(&mut self) -> Result<Node, Diagnostic> {
+        let start = self.current_span().start;
+
         if self.in_func {
             return Err(
                 Diagnostic::error("nested function not allowed", self.current_span())
+                    .with_code(codes::NESTING_NOT_ALLOWED)
                     .with_help(
                         "Functions cannot be defined inside other functions.\n\
                         Define functions at the top level and call them instead.",
@@ -1007,8 +1373,19 @@ impl<'a> Parser<'a> {
                 .iter()
                 .any(|t| t.kind == TokenKind::FuncEnd)
             {
+                // Closed by another family's delimiter instead?
+                if let Some(open) = self.tokens[self.index..]
+                    .iter()
+                    .position(|t| t.kind == TokenKind::FuncStart)
+                    .map(|off| self.index + off)
+                    && let Some(tok) = self.pending_closer(open + 1)
+                {
+                    return Err(mismatched_closer(tok, TokenKind::FuncEnd, token_span(&self.tokens[open])));
+                }
+
                 return Err(
                     Diagnostic::error("unterminated function structure", self.current_span())
+                        .with_code(codes::UNTERMINATED_DELIMITER)
                         .with_help("Druim expected a closing function delimiter `):`."),
                 );
             }
@@ -1019,20 +1396,26 @@ impl<'a> Parser<'a> {
                 None => {
                     return Err(
                         Diagnostic::error("invalid function structure", self.current_span())
+                            .with_code(codes::INVALID_FUNCTION)
                             .with_help("Druim expected a function name after the `fn` keyword."),
                     );
                 }
             };
 
+            if let Some(err) = invalid_identifier(name_tok, codes::INVALID_FUNCTION) {
+                return Err(err);
+            }
+
             if name_tok.kind != TokenKind::Ident {
                 return Err(
                     Diagnostic::error(
                         "invalid function structure",
                         Span {
                             start: name_tok.pos,
-                            end: name_tok.pos + name_tok.lexeme.len(),
+                            end: name_tok.end,
                         },
                     )
+                    .with_code(codes::INVALID_FUNCTION)
                     .with_help("Druim expected a function name after the `fn` keyword."),
                 );
             }
@@ -1045,11 +1428,12 @@ impl<'a> Parser<'a> {
                         "invalid function name",
                         Span {
                             start: name_tok.pos,
-                            end: name_tok.pos + name_tok.lexeme.len(),
+                            end: name_tok.end,
                         },
                     )
+                    .with_code(codes::INVALID_FUNCTION)
                     .with_help(
-                        "Function names in Druim must use snake_case (lowercase letters and underscores).",
+                        "Function names in Druim must use snake_case (lowercase letters, digits and underscores, starting with a letter).",
                     ),
                 );
             }
@@ -1058,14 +1442,22 @@ impl<'a> Parser<'a> {
             if self.peek_kind() != TokenKind::FuncStart {
                 return Err(
                     Diagnostic::error("invalid function structure", self.current_span())
+                        .with_code(codes::INVALID_FUNCTION)
                         .with_help(
                             "Druim expected a parameter block starting with `:(` after the function name.",
                         ),
                 );
             }
 
+            let open_span = self.current_span();
             self.bump(); // consume `:(`
 
+            if let Some(tok) = self.pending_closer(self.index)
+                && tok.kind != TokenKind::FuncEnd
+            {
+                return Err(mismatched_closer(tok, TokenKind::FuncEnd, open_span));
+            }
+
             // Verify at least one body delimiter exists
             let mut i = self.index;
             let mut saw_body = false;
@@ -1085,6 +1477,7 @@ impl<'a> Parser<'a> {
             if !saw_body {
                 return Err(
                     Diagnostic::error("incomplete function definition", self.current_span())
+                        .with_code(codes::INVALID_FUNCTION)
                         .with_help(
                             "Druim functions must consist of a parameter list and at least one body.\n\
                             An empty list and empty body is allowed, but a body delimiter `)(` is required.\n\
@@ -1093,97 +1486,17 @@ impl<'a> Parser<'a> {
                 );
             }
 
-            // Parse parameters
-            let mut params = Vec::new();
-
-            if self.peek_kind() != TokenKind::FuncChain {
-                loop {
-                    if self.peek_kind() == TokenKind::KwLoc {
-                        return Err(
-                            Diagnostic::error("invalid function parameter", self.current_span())
-                                .with_help("`loc` is not allowed in Druim function parameter declarations."),
-                        );
-                    }
-
-                    let ident_tok = match self.bump() {
-                        Some(tok) => tok,
-                        None => {
-                            return Err(
-                                Diagnostic::error("invalid function parameter", self.current_span())
-                                    .with_help("Druim expected a parameter name."),
-                            );
-                        }
-                    };
-
-                    if ident_tok.kind != TokenKind::Ident {
-                        return Err(
-                            Diagnostic::error(
-                                "invalid function parameter",
-                                Span {
-                                    start: ident_tok.pos,
-                                    end: ident_tok.pos + ident_tok.lexeme.len(),
-                                },
-                            )
-                            .with_help(
-                                "Druim function parameters must begin with an identifier.\n\
-                                Examples: `x`, `x = 10`",
-                            ),
-                        );
-                    }
-
-                    let param_name = ident_tok.lexeme.clone();
-
-                    if self.peek_kind() == TokenKind::Define {
-                        self.bump();
-
-                        if self.peek_kind() == TokenKind::Comma
-                            || self.peek_kind() == TokenKind::FuncChain
-                        {
-                            return Err(
-                                Diagnostic::error("invalid default parameter", self.current_span())
-                                    .with_help(
-                                        "Druim default parameters require a value.\n\
-                                        Example: `x = 10`",
-                                    ),
-                            );
-                        }
-
-                        let value = self.parse_rhs()?;
-
-                        params.push(Param {
-                            name: param_name,
-                            default: Some(value),
-                        });
-                    } else {
-                        params.push(Param {
-                            name: param_name,
-                            default: None,
-                        });
-                    }
-
-                    match self.peek_kind() {
-                        TokenKind::Comma => {
-                            self.bump();
-                        }
-                        TokenKind::FuncChain => break,
-                        _ => {
-                            return Err(
-                                Diagnostic::error("invalid function parameter list", self.current_span())
-                                    .with_help(
-                                        "Druim parameters must be separated by commas and terminated with `)(`.",
-                                    ),
-                            );
-                        }
-                    }
-                }
-            }
-
-            self.bump(); // consume `)(`
+            // Parse parameters (consumes `)(`)
+            let params =
+                self.parse_separated(TokenKind::Comma, TokenKind::FuncChain, "fn f :(a, b = 2)( ret a; ):", |p| {
+                    p.parse_param()
+                })?;
 
             // Reject function chaining
             if self.peek_kind() == TokenKind::FuncChain {
                 return Err(
                     Diagnostic::error("function chaining not allowed", self.current_span())
+                        .with_code(codes::CHAINING_NOT_ALLOWED)
                         .with_help(
                             "Functions may only define a single body.\n\
                             Function chaining is not supported.",
@@ -1204,6 +1517,7 @@ impl<'a> Parser<'a> {
                 name,
                 params,
                 body: nodes,
+                span: self.span_from(start),
             }))
         })();
 
@@ -1211,38 +1525,216 @@ impl<'a> Parser<'a> {
         result
     }
 
-    fn parse_rhs(&mut self) -> Result<Node, Diagnostic> {
-        let start_span = self.current_span();
+    /// Parse one function parameter: `name` or `name = default`.
+    fn parse_param(&mut self) -> Result<Param, Diagnostic> {
+        if self.peek_kind() == TokenKind::KwLoc {
+            return Err(
+                Diagnostic::error("invalid function parameter", self.current_span())
+                    .with_code(codes::INVALID_FUNCTION)
+                    .with_help("`loc` is not allowed in Druim function parameter declarations."),
+            );
+        }
 
-        let value = self.parse_expr()?;
+        // A leading or doubled comma leaves an empty slot in the list.
+        if self.peek_kind() == TokenKind::Comma {
+            return Err(
+                Diagnostic::error("missing parameter before `,`", self.current_span())
+                    .with_code(codes::INVALID_FUNCTION)
+                    .with_help(
+                        "Druim expected a parameter name here.\n\
+                        Remove the extra comma.",
+                    ),
+            );
+        }
 
-        // Bare identifiers are not values
-        if matches!(value, Node::Ident(_)) {
+        let ident_tok = match self.bump() {
+            Some(tok) => tok,
+            None => {
+                return Err(
+                    Diagnostic::error("invalid function parameter", self.current_span())
+                        .with_code(codes::INVALID_FUNCTION)
+                        .with_help("Druim expected a parameter name."),
+                );
+            }
+        };
+
+        if let Some(err) = invalid_identifier(ident_tok, codes::INVALID_FUNCTION) {
+            return Err(err);
+        }
+
+        if ident_tok.kind != TokenKind::Ident {
+            return Err(
+                Diagnostic::error(
+                    "invalid function parameter",
+                    Span {
+                        start: ident_tok.pos,
+                        end: ident_tok.end,
+                    },
+                )
+                .with_code(codes::INVALID_FUNCTION)
+                .with_help(
+                    "Druim function parameters must begin with an identifier.\n\
+                    Examples: `x`, `x = 10`",
+                ),
+            );
+        }
+
+        let name = ident_tok.lexeme.clone();
+
+        if self.peek_kind() != TokenKind::Define {
+            return Ok(Param {
+                name,
+                default: None,
+            });
+        }
+
+        self.bump(); // consume `=`
+
+        if self.peek_kind() == TokenKind::Comma || self.peek_kind() == TokenKind::FuncChain {
             return Err(
-                Diagnostic::error("invalid value expression", start_span)
+                Diagnostic::error("invalid default parameter", self.current_span())
+                    .with_code(codes::INVALID_FUNCTION)
                     .with_help(
-                        "A bare identifier is not a value.\n\
-                        Use a function call, copy (`:=`), or bind (`:>`) instead.",
+                        "Druim default parameters require a value.\n\
+                        Example: `x = 10`",
                     ),
             );
         }
 
+        let value = self.parse_rhs(codes::INVALID_FUNCTION)?;
+
+        Ok(Param {
+            name,
+            default: Some(value),
+        })
+    }
+
+    /// Parse a block expression `:[ ... ]:`.
+    ///
+    /// The body is a sequence of statements followed by an optional
+    /// trailing expression, which becomes the value of the block.
+    /// Block expressions may nest.
+    /// The opening `:[` has already been consumed; `open_span` is its span.
+    fn parse_block_expr(&mut self, open_span: Span) -> Result<Node, Diagnostic> {
+        let mut nodes = Vec::new();
+        let mut value = None;
+
+        loop {
+            match self.peek_kind() {
+                TokenKind::ArrayEnd => break,
+                TokenKind::BlockEnd | TokenKind::FuncEnd => {
+                    return Err(mismatched_closer(
+                        &self.tokens[self.index],
+                        TokenKind::ArrayEnd,
+                        open_span,
+                    ));
+                }
+                TokenKind::Eof => {
+                    return Err(
+                        Diagnostic::error("unterminated block expression", open_span)
+                            .with_code(codes::UNTERMINATED_DELIMITER)
+                            .with_help("Druim expected a closing block expression delimiter `]:`."),
+                    );
+                }
+                _ => {}
+            }
+
+            if self.block_expr_statement_ahead() {
+                nodes.push(self.parse_statement_entry()?);
+                continue;
+            }
+
+            value = Some(Box::new(self.parse_expr()?));
+
+            if self.peek_kind() != TokenKind::ArrayEnd {
+                return Err(
+                    Diagnostic::error("invalid block expression", self.current_span())
+                        .with_code(codes::INVALID_EXPRESSION)
+                        .with_help(
+                            "The value of a block expression must be its final expression.\n\
+                            Druim expected `]:` after it.\n\
+                            Example: `:[ y = 1; y + 1 ]:`",
+                        ),
+                );
+            }
+        }
+
+        self.bump(); // `]:`
+
+        Ok(Node::BlockExpr(BlockExpr {
+            nodes,
+            value,
+            span: self.span_from(open_span.start),
+        }))
+    }
+
+    /// Inside a block expression, decide whether the upcoming tokens form
+    /// a statement (terminated by `;`) or the trailing value expression
+    /// (terminated by the closing `]:`).
+    fn block_expr_statement_ahead(&self) -> bool {
+        if self.peek_kind() == TokenKind::KwRet {
+            return true;
+        }
+
+        let mut depth = 0usize;
+
+        for tok in &self.tokens[self.index..] {
+            match tok.kind {
+                TokenKind::ArrayStart => depth += 1,
+                TokenKind::ArrayEnd if depth == 0 => return false,
+                TokenKind::ArrayEnd => depth -= 1,
+                TokenKind::Semicolon if depth == 0 => return true,
+                _ => {}
+            }
+        }
+
+        false
+    }
+
+    /// Parse the value after a define's `=` or a parameter's default `=`
+    /// (or a `ret` value, which accepts a lone identifier before getting
+    /// here).
+    ///
+    /// This is the one place the bare-identifier rule lives: in these
+    /// positions a lone identifier is rejected, because `:=` or `:>` says
+    /// what was meant. The error carries `code`, the caller's statement
+    /// code. Guard branches and call arguments are not parsed through
+    /// here: a name is exactly what they test or pass along.
+    fn parse_rhs(&mut self, code: &'static str) -> Result<Node, Diagnostic> {
+        let start = self.index;
+        let value = self.parse_expr()?;
+
+        if matches!(value, Node::Ident(_)) {
+            let mut err = Diagnostic::error("bare identifier is not a value", token_span(&self.tokens[start]))
+                .with_code(code)
+                .with_help(
+                    "A bare identifier cannot be defined from directly.\n\
+                    Use `:=` to copy a value or `:>` to create a live binding.\n\
+                    Examples: `a := b;` or `a :> b;`",
+                );
+
+            // In a define, the fix is to swap the `=` itself.
+            if code == codes::INVALID_DEFINE {
+                err = err.with_suggestion(token_span(&self.tokens[start - 1]), ":=");
+            }
+
+            return Err(err);
+        }
+
         Ok(value)
     }
 
     fn parse_call_statement(&mut self) -> Result<Node, Diagnostic> {
         // Verify statement terminates
-        let stmt_end = match self.tokens[self.index..]
-            .iter()
-            .position(|t| t.kind == TokenKind::Semicolon)
-        {
-            Some(off) => self.index + off,
+        let stmt_end = match self.statement_end() {
+            Some(end) => end,
             None => {
                 return Err(
                     Diagnostic::error(
                         "unterminated function call statement",
-                        self.current_span(),
+                        self.missing_semicolon_span(),
                     )
+                    .with_code(codes::UNTERMINATED_STATEMENT)
                     .with_help(
                         "Druim expected a semicolon `;` to terminate this function call.\n\
                         Example: `do_work();`",
@@ -1252,9 +1744,7 @@ impl<'a> Parser<'a> {
         };
 
         // Scan for illegal statement operators before parsing
-        let mut i = self.index;
-
-        while i < stmt_end {
+        for i in self.top_level_indices(stmt_end) {
             match self.tokens[i].kind {
                 TokenKind::Define
                 | TokenKind::DefineEmpty
@@ -1266,9 +1756,10 @@ impl<'a> Parser<'a> {
                             "invalid function call statement",
                             Span {
                                 start: self.tokens[i].pos,
-                                end: self.tokens[i].pos + self.tokens[i].lexeme.len(),
+                                end: self.tokens[i].end,
                             },
                         )
+                        .with_code(codes::INVALID_CALL)
                         .with_help(
                             "Druim function call statements cannot be chained with other statement operators.\n\
                             Split this into multiple statements.",
@@ -1278,8 +1769,6 @@ impl<'a> Parser<'a> {
 
                 _ => {}
             }
-
-            i += 1;
         }
 
         // Parse the complete call expression
@@ -1292,6 +1781,7 @@ impl<'a> Parser<'a> {
                     "invalid function call statement",
                     self.current_span(),
                 )
+                .with_code(codes::INVALID_CALL)
                 .with_help(
                     "Only function calls may appear as standalone expressions.\n\
                     Example: `do_work();`",
@@ -1306,6 +1796,7 @@ impl<'a> Parser<'a> {
                     "invalid function call statement",
                     self.current_span(),
                 )
+                .with_code(codes::INVALID_CALL)
                 .with_help(
                     "A standalone function call cannot be followed by another expression.\n\
                     Split this into separate statements.",
@@ -1322,68 +1813,69 @@ impl<'a> Parser<'a> {
         self.parse_bp(0)
     }
 
-    // ===== Pratt parser =====
-
-    fn parse_bp(&mut self, min_bp: u8) -> Result<Node, Diagnostic> {
-        let mut lhs = self.parse_prefix()?;
-
-        loop {
-            // Postfix function call: lhs(...)
-            if self.peek_kind() == TokenKind::LParen {
-                let call_bp = 95;
+    /// Parse the remaining input as exactly one expression.
+    ///
+    /// Unlike [`Parser::parse_expr`], anything left after the expression
+    /// is an error rather than being ignored.
+    pub fn parse_expression(&mut self) -> Result<Node, Diagnostic> {
+        let expr = self.parse_expr()?;
 
-                if call_bp < min_bp {
-                    break;
-                }
-
-                self.bump(); // consume `(`
-
-                let mut args = Vec::new();
+        if self.peek_kind() != TokenKind::Eof {
+            let end = self.tokens[self.index..]
+                .iter()
+                .position(|tok| tok.kind == TokenKind::Eof)
+                .map_or(self.tokens.len(), |off| self.index + off);
 
-                if self.peek_kind() != TokenKind::RParen {
-                    loop {
-                        args.push(self.parse_expr()?);
+            return Err(
+                Diagnostic::error("unexpected trailing tokens", self.leftover_span(end))
+                    .with_code(codes::INVALID_EXPRESSION)
+                    .with_help("Druim expected the input to end after this expression."),
+            );
+        }
 
-                        match self.peek_kind() {
-                            TokenKind::Comma => {
-                                self.bump();
-                            }
+        Ok(expr)
+    }
 
-                            TokenKind::RParen => break,
+    // ===== Pratt parser =====
 
-                            _ => {
-                                return Err(
-                                    Diagnostic::error(
-                                        "invalid function call",
-                                        self.current_span(),
-                                    )
-                                    .with_help(
-                                        "Druim function arguments must be separated by commas and closed with `)`.",
-                                    ),
-                                );
-                            }
-                        }
-                    }
-                }
+    fn parse_bp(&mut self, min_bp: u8) -> Result<Node, Diagnostic> {
+        self.deepen()?;
+        let depth = self.depth;
+        let result = self.parse_bp_nested(min_bp);
+        self.depth = depth - 1;
+        result
+    }
 
-                self.bump(); // consume `)`
+    /// Count one more level of recursion against the depth limit.
+    /// [`Parser::parse_bp`] restores the depth when its expression is
+    /// complete.
+    fn deepen(&mut self) -> Result<(), Diagnostic> {
+        if self.depth >= self.max_depth {
+            return Err(
+                Diagnostic::error("expression nested too deeply", self.current_span())
+                    .with_code(codes::INVALID_EXPRESSION)
+                    .with_help("Split the expression into smaller named parts."),
+            );
+        }
 
-                lhs = Node::Call(Call {
-                    callee: Box::new(lhs),
-                    args,
-                });
+        self.depth += 1;
+        Ok(())
+    }
 
-                continue;
-            }
+    fn parse_bp_nested(&mut self, min_bp: u8) -> Result<Node, Diagnostic> {
+        let start = self.current_span().start;
+        let mut lhs = self.parse_prefix()?;
+        let mut lhs_is_comparison = false;
 
+        loop {
+            // Postfix function call: lhs(...)
             if self.peek_kind() == TokenKind::LParen {
-                const CALL_BP: u8 = 95;
-
                 if CALL_BP < min_bp {
                     break;
                 }
 
-                lhs = self.parse_call_suffix(lhs)?;
+                lhs = self.parse_call_suffix(lhs, start)?;
+                lhs_is_comparison = false;
                 continue;
             }
 
@@ -1397,57 +1889,123 @@ impl<'a> Parser<'a> {
                 break;
             }
 
+            let op_span = self.current_span();
             self.bump();
 
             let rhs = self.parse_bp(r_bp)?;
+
+            let is_comparison = infix_kind.is_comparison();
+            if is_comparison && (lhs_is_comparison || self.ended_in_comparison) {
+                return Err(chained_comparison(op_span));
+            }
+
             lhs = build_infix(infix_kind, lhs, rhs);
+            lhs_is_comparison = is_comparison;
         }
 
+        self.ended_in_comparison = lhs_is_comparison;
         Ok(lhs)
     }
 
+    /// Parse a prefix operator, group or block expression, or an atom.
+    ///
+    /// Only the forms that recurse are handled here. Everything else is
+    /// in [`Parser::parse_atom`], whose frame holds the diagnostics, so
+    /// each level of nesting costs as little stack as possible.
     fn parse_prefix(&mut self) -> Result<Node, Diagnostic> {
+        let open_span = self.current_span();
+
+        match self.peek_kind() {
+            TokenKind::Not => {
+                self.bump();
+                let rhs = self.parse_bp(PREFIX_BP)?;
+                Ok(Node::Not(Box::new(rhs)))
+            }
+
+            // A minus sign directly before a numeric literal is part of
+            // the literal, which `parse_atom` reads.
+            TokenKind::Sub if !self.negative_literal_ahead() => {
+                self.bump();
+                let rhs = self.parse_bp(PREFIX_BP)?;
+                Ok(Node::Neg(Box::new(rhs)))
+            }
+
+            TokenKind::ArrayStart => {
+                self.bump();
+                self.parse_block_expr(open_span)
+            }
+
+            TokenKind::LParen => {
+                self.bump();
+                let expr = self.parse_bp(0)?;
+                self.expect(TokenKind::RParen, "`)`")?;
+                Ok(expr)
+            }
+
+            _ => self.parse_atom(),
+        }
+    }
+
+    /// Whether the current `-` is directly followed by a numeric literal.
+    fn negative_literal_ahead(&self) -> bool {
+        self.tokens
+            .get(self.index + 1)
+            .is_some_and(|next| matches!(next.kind, TokenKind::NumLit | TokenKind::DecLit))
+    }
+
+    /// Parse a literal or name, or report a token that cannot start a
+    /// value.
+    fn parse_atom(&mut self) -> Result<Node, Diagnostic> {
         let span_start = self.current_span().start;
+        let called = self.tokens.get(self.index + 1).is_some_and(|next| next.kind == TokenKind::LParen);
 
         let tok = self.bump().ok_or_else(|| {
             Diagnostic::error(
                 "unexpected end of input",
                 Span { start: span_start, end: span_start },
             )
+            .with_code(codes::INVALID_EXPRESSION)
             .with_help("Druim expected a value expression here.")
         })?;
 
         match tok.kind {
             // ─── Atoms ──────────────────────────────
-            TokenKind::Ident => Ok(Node::Ident(tok.lexeme.clone())),
+            TokenKind::Ident => Ok(ident_node(tok)),
 
-            TokenKind::NumLit => {
-                let n = tok.lexeme.parse::<i64>().unwrap_or(0);
-                Ok(Node::Lit(Literal::Num(n)))
-            }
+            TokenKind::NumLit => match tok.lexeme.parse::<i64>() {
+                Ok(n) => Ok(Node::Lit(Literal::Num(n))),
+                Err(_) => Err(num_out_of_range(tok)),
+            },
 
             TokenKind::DecLit => Ok(Node::Lit(Literal::Dec(tok.lexeme.clone()))),
 
             TokenKind::TextLit => Ok(Node::Lit(Literal::Text(tok.lexeme.clone()))),
 
-            TokenKind::KwVoid => Ok(Node::Lit(Literal::Void)),
-
-            // ─── Unary operators ────────────────────
-            TokenKind::Not => {
-                let rhs = self.parse_bp(PREFIX_BP)?;
-                Ok(Node::Not(Box::new(rhs)))
+            // A type keyword directly before `(` names its conversion.
+            TokenKind::KwNum | TokenKind::KwDec | TokenKind::KwFlag | TokenKind::KwText
+                if called =>
+            {
+                Ok(ident_node(tok))
             }
 
-            TokenKind::Sub => {
-                let rhs = self.parse_bp(PREFIX_BP)?;
-                Ok(Node::Neg(Box::new(rhs)))
-            }
+            TokenKind::KwVoid => Ok(Node::Lit(Literal::Void)),
+            TokenKind::KwEmp => Ok(Node::Lit(Literal::Emp)),
+            TokenKind::KwTrue => Ok(Node::Lit(Literal::Flag(true))),
+            TokenKind::KwFalse => Ok(Node::Lit(Literal::Flag(false))),
 
-            // ─── Grouping ───────────────────────────
-            TokenKind::LParen => {
-                let expr = self.parse_bp(0)?;
-                self.expect(TokenKind::RParen, "`)`")?;
-                Ok(expr)
+            // ─── Negative literals ──────────────────
+            // Reading the sign with the digits keeps `i64::MIN`
+            // representable: its magnitude has no positive counterpart.
+            TokenKind::Sub => {
+                let next = &self.tokens[self.index];
+                self.index += 1;
+                match next.kind {
+                    TokenKind::NumLit => match format!("-{}", next.lexeme).parse::<i64>() {
+                        Ok(n) => Ok(Node::Lit(Literal::Num(n))),
+                        Err(_) => Err(num_out_of_range(next)),
+                    },
+                    _ => Ok(Node::Lit(Literal::Dec(format!("-{}", next.lexeme)))),
+                }
             }
 
             // ─── Explicitly illegal value starters ──
@@ -1461,9 +2019,10 @@ impl<'a> Parser<'a> {
                         "invalid value expression",
                         Span {
                             start: tok.pos,
-                            end: tok.pos + tok.lexeme.len(),
+                            end: tok.end,
                         },
                     )
+                    .with_code(codes::INVALID_EXPRESSION)
                     .with_help(
                         "Statement operators are not valid values.\n\
                         Use them as complete statements ending with `;`.",
@@ -1480,9 +2039,10 @@ impl<'a> Parser<'a> {
                         "invalid value expression",
                         Span {
                             start: tok.pos,
-                            end: tok.pos + tok.lexeme.len(),
+                            end: tok.end,
                         },
                     )
+                    .with_code(codes::INVALID_EXPRESSION)
                     .with_help(
                         "This construct cannot be used as a value.\n\
                         It must appear in its own statement context.",
@@ -1493,54 +2053,80 @@ impl<'a> Parser<'a> {
             // ─── Everything else ────────────────────
             _ => Err(
                 Diagnostic::error(
-                    "unexpected token in value expression",
+                    format!("unexpected {} in value expression", tok.kind),
                     Span {
                         start: tok.pos,
-                        end: tok.pos + tok.lexeme.len(),
+                        end: tok.end,
                     },
                 )
+                .with_code(codes::INVALID_EXPRESSION)
                 .with_help("Druim expected a value here."),
             ),
         }
     }
 
-    fn parse_call_suffix(&mut self, callee: Node) -> Result<Node, Diagnostic> {
+    /// Parse the argument list of a call whose callee began at byte
+    /// `start`.
+    fn parse_call_suffix(&mut self, callee: Node, start: usize) -> Result<Node, Diagnostic> {
         self.bump(); // consume `(`
 
-        let mut args = Vec::new();
+        let args =
+            self.parse_separated(TokenKind::Comma, TokenKind::RParen, "f(1, 2)", |p| p.parse_expr())?;
+
+        Ok(Node::Call(Call {
+            callee: Box::new(callee),
+            args,
+            span: self.span_from(start),
+        }))
+    }
 
-        if self.peek_kind() != TokenKind::RParen {
-            loop {
-                args.push(self.parse_expr()?);
+    /// Parse a `sep`-separated list of items up to and including `close`.
+    ///
+    /// The opening delimiter must already be consumed. Empty lists and a
+    /// single trailing separator are accepted. `example` is a well-formed
+    /// list of the caller's kind, shown in the help for a missing
+    /// separator.
+    pub(crate) fn parse_separated<T>(
+        &mut self,
+        sep: TokenKind,
+        close: TokenKind,
+        example: &str,
+        mut parse_item: impl FnMut(&mut Self) -> Result<T, Diagnostic>,
+    ) -> Result<Vec<T>, Diagnostic> {
+        let mut items = Vec::new();
 
-                match self.peek_kind() {
-                    TokenKind::Comma => {
-                        self.bump();
-                    }
+        loop {
+            if self.peek_kind() == close {
+                self.bump();
+                return Ok(items);
+            }
 
-                    TokenKind::RParen => break,
+            items.push(parse_item(self)?);
 
-                    _ => {
-                        return Err(
-                            Diagnostic::error(
-                                "invalid function call",
-                                self.current_span(),
-                            )
-                            .with_help(
-                                "Druim function arguments must be separated by commas and closed with `)`.",
+            match self.peek_kind() {
+                kind if kind == sep => {
+                    self.bump();
+                }
+                kind if kind == close => {}
+                _ => {
+                    return Err(
+                        Diagnostic::error(
+                            format!(
+                                "expected `{}` or `{}`",
+                                canonical_lexeme(sep).unwrap_or_default(),
+                                canonical_lexeme(close).unwrap_or_default(),
                             ),
-                        );
-                    }
+                            self.current_span(),
+                        )
+                        .with_code(codes::INVALID_LIST)
+                        .with_help(format!(
+                            "Druim list items must be separated by commas.\n\
+                            Example: `{example}`"
+                        )),
+                    );
                 }
             }
         }
-
-        self.bump(); // consume `)`
-
-        Ok(Node::Call(Call {
-            callee: Box::new(callee),
-            args,
-        }))
     }
 
     fn expect(&mut self, kind: TokenKind, expected: &'static str) -> Result<(), Diagnostic> {
@@ -1550,18 +2136,20 @@ impl<'a> Parser<'a> {
                 "unexpected end of input",
                 Span { start: span_start, end: span_start },
             )
+            .with_code(codes::INVALID_EXPRESSION)
             .with_help(expected)
         })?;
 
         if tok.kind != kind {
             return Err(
                 Diagnostic::error(
-                    "unexpected token",
+                    format!("expected {kind}, found {}", tok.kind),
                     Span {
                         start: tok.pos,
-                        end: tok.pos + tok.lexeme.len(),
+                        end: tok.end,
                     },
                 )
+                .with_code(codes::INVALID_EXPRESSION)
                 .with_help(expected)
             );
         }
@@ -1589,18 +2177,111 @@ impl<'a> Parser<'a> {
         if let Some(tok) = self.peek() {
             Span {
                 start: tok.pos,
-                end: tok.pos + tok.lexeme.len(),
+                end: tok.end,
             }
         } else if let Some(prev) = self.tokens.last() {
-            let end = prev.pos + prev.lexeme.len();
+            let end = prev.end;
             Span { start: end, end }
         } else {
             Span { start: 0, end: 0 }
         }
     }
+
+    /// Span of a node that began at byte `start` and ends with the last
+    /// consumed token.
+    fn span_from(&self, start: usize) -> NodeSpan {
+        let end = self.index.checked_sub(1).map_or(start, |i| self.tokens[i].end);
+        NodeSpan(Span { start, end })
+    }
 }
 
+/// A structure opened at `open_span` is closed by a delimiter of
+/// another family, e.g. `:{ a = 1; ]:`.
+fn mismatched_closer(found: &Token, expected: TokenKind, open_span: Span) -> Diagnostic {
+    Diagnostic::error(
+        format!("mismatched closing delimiter: expected {expected}, found {}", found.kind),
+        token_span(found),
+    )
+    .with_code(codes::UNMATCHED_DELIMITER)
+    .with_secondary(open_span, "opened here")
+    .with_help(format!(
+        "Each structure must close with its own delimiter.\n\
+        Replace {} with {expected}.",
+        found.kind
+    ))
+}
+
+fn token_span(tok: &Token) -> Span {
+    Span {
+        start: tok.pos,
+        end: tok.end,
+    }
+}
+
+fn ident_node(tok: &Token) -> Node {
+    Node::Ident(Ident {
+        name: tok.lexeme.clone(),
+        span: NodeSpan(token_span(tok)),
+    })
+}
+
+fn num_out_of_range(tok: &Token) -> Diagnostic {
+    Diagnostic::error(
+        "integer literal out of range",
+        Span {
+            start: tok.pos,
+            end: tok.end,
+        },
+    )
+    .with_code(codes::INVALID_EXPRESSION)
+    .with_help(
+        "Druim `num` values are 64-bit signed integers.\n\
+        The valid range is -9223372036854775808 to 9223372036854775807.",
+    )
+}
+
+/// Check a binding target (or function name) token against the
+/// identifier rule.
+///
+/// Names follow the lexical identifier rule: ASCII letters, digits and
+/// underscores, with at least one non-digit. The lexer only produces
+/// `Ident` tokens that satisfy it, so what is rejected here is a
+/// name-like token that is not one: a reserved keyword, or a sequence of
+/// digits. An identifier in [`RESERVED_NAMES`] is rejected too. Other
+/// tokens return `None` and are reported by the caller as a malformed
+/// statement.
+fn invalid_identifier(tok: &Token, code: &'static str) -> Option<Diagnostic> {
+    let help = match tok.kind {
+        TokenKind::Ident if RESERVED_NAMES.contains(&tok.lexeme.as_str()) => {
+            format!("`{}` is reserved as a send-to destination and cannot be bound.", tok.lexeme)
+        }
+        TokenKind::Ident => return None,
+        TokenKind::NumLit => "A name must contain at least one non-digit character.".to_string(),
+        kind if canonical_lexeme(kind).is_some_and(|l| l.chars().all(|c| c.is_ascii_alphabetic())) => {
+            format!("`{}` is a reserved keyword and cannot be used as a name.", tok.lexeme)
+        }
+        _ => return None,
+    };
+
+    Some(
+        Diagnostic::error(format!("invalid identifier name `{}`", tok.lexeme), token_span(tok))
+            .with_code(code)
+            .with_help(help),
+    )
+}
+
+/// Names reserved as send-to destinations (`-> out;`).
+///
+/// They lex as identifiers, so they can still be read, but no statement
+/// or function may bind them: `out = 1;` would shadow the destination.
+pub const RESERVED_NAMES: &[&str] = &["out"];
+
+/// Function names must additionally be snake_case.
 fn is_snake_case(name: &str) -> bool {
+    if !name.starts_with(|c: char| c.is_ascii_lowercase()) {
+        return false;
+    }
+
     let mut prev_underscore = false;
 
     for c in name.chars() {
@@ -1616,13 +2297,17 @@ fn is_snake_case(name: &str) -> bool {
         }
     }
 
-    !name.starts_with('_') && !name.ends_with('_')
+    !name.ends_with('_')
 }
 
-const PREFIX_BP: u8 = 90;
+/// Binding power of the prefix operators `!` and `-`.
+pub(crate) const PREFIX_BP: u8 = 90;
+
+/// Binding power of a postfix call `f(...)`.
+pub(crate) const CALL_BP: u8 = 95;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Infix {
+pub(crate) enum Infix {
 
     // Arithmetic
     Add,
@@ -1649,9 +2334,18 @@ enum Infix {
 
     // Flow
     Pipe,
+
+    // Absence
+    Coalesce,
 }
 
-fn infix_binding_power(op: TokenKind) -> Option<(u8, u8, Infix)> {
+impl Infix {
+    fn is_comparison(self) -> bool {
+        matches!(self, Infix::Eq | Infix::Ne | Infix::Lt | Infix::Le | Infix::Gt | Infix::Ge)
+    }
+}
+
+pub(crate) fn infix_binding_power(op: TokenKind) -> Option<(u8, u8, Infix)> {
     use Infix::*;
 
     Some(match op {
@@ -1684,10 +2378,23 @@ fn infix_binding_power(op: TokenKind) -> Option<(u8, u8, Infix)> {
         // pipe
         TokenKind::Pipe => (20, 21, Pipe),
 
+        // coalescing
+        TokenKind::Coalesce => (15, 16, Coalesce),
+
         _ => return None,
     })
 }
 
+/// Built outside `parse_bp_nested` to keep its stack frame small.
+fn chained_comparison(op_span: Span) -> Diagnostic {
+    Diagnostic::error("comparison operators cannot be chained", op_span)
+        .with_code(codes::INVALID_EXPRESSION)
+        .with_help(
+            "Compare two values at a time and combine the results.\n\
+            Example: `(a < b) && (b < c)`",
+        )
+}
+
 fn build_infix(kind: Infix, lhs: Node, rhs: Node) -> Node {
     use Infix::*;
 
@@ -1712,5 +2419,6 @@ fn build_infix(kind: Infix, lhs: Node, rhs: Node) -> Node {
         Present => Node::Present(Box::new(lhs), Box::new(rhs)),
 
         Pipe => Node::Pipe(Box::new(lhs), Box::new(rhs)),
+        Coalesce => Node::Coalesce(Box::new(lhs), Box::new(rhs)),
     }
 }
@@ -1,8 +1,9 @@
 use crate::compiler::lexer::Lexer;
 use crate::compiler::parser::Parser;
-use crate::compiler::ast::{Node, Block, Define, DefineEmpty, Copy, Bind, Guard, Ret, Program, Func, Literal};
+use crate::compiler::ast::{Node, NodeSpan, Block, BlockExpr, Define, DefineEmpty, Copy, Bind, Guard, Ret, Program, Func, Literal, Param, Type};
 use crate::compiler::diagnostic::render;
-use crate::compiler::error::{Diagnostic, Source};
+use crate::compiler::error::{codes, Diagnostic, Source, Span};
+use crate::compiler::token::TokenKind;
 
 fn parse_node(src: &str) -> Node {
     let mut lexer = Lexer::new(src);
@@ -49,7 +50,9 @@ fn parses_define_empty_node() {
     assert_eq!(
         node,
         Node::DefineEmpty(DefineEmpty {
-            name: "a".into()
+            name: "a".into(),
+            ty: None,
+            span: NodeSpan::default(),
         })
     );
 }
@@ -98,7 +101,7 @@ fn parses_local_define_empty_node() {
 
     match node {
         Node::Local(inner) => match inner.as_ref() {
-            Node::DefineEmpty(DefineEmpty { name }) => {
+            Node::DefineEmpty(DefineEmpty { name, .. }) => {
                 assert_eq!(name, "a");
             }
             other => panic!("expected empty definition inside local node, got {:?}", other),
@@ -117,14 +120,14 @@ fn define_empty_allows_following_statement() {
     let second = parser.parse_node().unwrap();
 
     match first {
-        Node::DefineEmpty(DefineEmpty { name }) => {
+        Node::DefineEmpty(DefineEmpty { name, .. }) => {
             assert_eq!(name, "a");
         }
         other => panic!("expected first empty definition node, got {:?}", other),
     }
 
     match second {
-        Node::DefineEmpty(DefineEmpty { name }) => {
+        Node::DefineEmpty(DefineEmpty { name, .. }) => {
             assert_eq!(name, "b");
         }
         other => panic!("expected second empty definition node, got {:?}", other),
@@ -150,7 +153,7 @@ fn parses_define_node() {
     let node = parser.parse_node().expect("failed to parse define node");
 
     match node {
-        Node::Define(Define { name, value }) => {
+        Node::Define(Define { name, value, .. }) => {
             assert_eq!(name, "x");
 
             match *value {
@@ -184,6 +187,32 @@ fn define_requires_identifier_lhs() {
     );
 }
 
+#[test]
+fn reserved_destinations_cannot_be_bound() {
+    let cases = [
+        ("out = 1;", codes::INVALID_DEFINE),
+        ("out =;", codes::INVALID_DEFINE),
+        ("out := a;", codes::INVALID_COPY),
+        ("out :> b;", codes::INVALID_BIND),
+        ("out ?= a;", codes::INVALID_GUARD),
+        ("fn out :()( ret; ):", codes::INVALID_FUNCTION),
+        ("fn f :( out )( ret out + 1; ):", codes::INVALID_FUNCTION),
+    ];
+
+    for (src, code) in cases {
+        let err = parse_node_err(src);
+        assert_eq!(err.message, "invalid identifier name `out`", "{src}");
+        assert_eq!(err.code, Some(code), "{src}");
+        let start = src.find("out").unwrap();
+        assert_eq!(err.span, Span { start, end: start + 3 }, "{src}");
+    }
+
+    // only the exact name is reserved, and it can still be read
+    for src in ["outer = 1;", "output := a;", "x = out + 1;"] {
+        parse_node(src);
+    }
+}
+
 #[test]
 fn define_requires_rhs_value() {
     let src = "a = ;";
@@ -221,6 +250,33 @@ fn define_requires_semicolon() {
     let _ = err;
 }
 
+#[test]
+fn missing_semicolon_points_past_last_token() {
+    let err = parse_node_err("x = 1");
+    assert_eq!(err.code, Some(codes::UNTERMINATED_STATEMENT));
+    assert_eq!(err.span, Span { start: 5, end: 5 });
+
+    let err = parse_node_err("x = 1\n\n");
+    assert_eq!(err.span, Span { start: 5, end: 5 });
+
+    let err = parse_node_err("out(x)  ");
+    assert_eq!(err.span, Span { start: 6, end: 6 });
+
+    let rendered = render(&parse_node_err("x = 1"), &Source::new("x = 1".to_string()));
+    assert!(rendered.contains("x = 1\n  |      ^"), "{rendered}");
+}
+
+#[test]
+fn missing_semicolon_in_block_stops_at_block_end() {
+    let src = ":{ x = 1 }:";
+    let tokens = Lexer::new(src).tokenize().unwrap();
+    let mut parser = Parser::new(&tokens);
+    let err = parser.parse_program().expect_err("expected missing semicolon error");
+
+    assert_eq!(err.code, Some(codes::UNTERMINATED_STATEMENT));
+    assert_eq!(err.span, Span { start: 8, end: 8 });
+}
+
 #[test]
 fn define_cannot_be_chained() {
     let src = "a = b = c;";
@@ -272,7 +328,7 @@ fn parses_local_define_node() {
 
     match node {
         Node::Local(inner) => match inner.as_ref() {
-            Node::Define(Define { name, value }) => {
+            Node::Define(Define { name, value, .. }) => {
                 assert_eq!(name, "a");
 
                 match value.as_ref() {
@@ -306,7 +362,7 @@ fn define_accepts_compound_expression_rhs() {
     let node = parser.parse_node().unwrap();
 
     match node {
-        Node::Define(Define { name, value }) => {
+        Node::Define(Define { name, value, .. }) => {
             assert_eq!(name, "a");
 
             match value.as_ref() {
@@ -333,7 +389,7 @@ fn parses_node_block() {
     assert_eq!(program.nodes.len(), 1);
 
     match &program.nodes[0] {
-        Node::Block(Block { segments }) => {
+        Node::Block(Block { segments, .. }) => {
             assert_eq!(segments.len(), 1);
             assert_eq!(segments[0].nodes.len(), 2);
 
@@ -351,6 +407,19 @@ fn parses_node_block() {
     }
 }
 
+#[test]
+fn block_may_define_functions_but_not_nest_blocks() {
+    match parse_node(":{ fn f :()( ret 1; ): }:") {
+        Node::Block(Block { segments, .. }) => {
+            assert!(matches!(segments[0].nodes[0], Node::Func(_)));
+        }
+        other => panic!("expected block node, got {:?}", other),
+    }
+
+    let err = parse_node_err(":{ :{ x = 1; }: }:");
+    assert_eq!(err.message, "nested block not allowed");
+}
+
 #[test]
 fn block_requires_closing_delimiter() {
     let src = ":{ a := b;";
@@ -375,7 +444,7 @@ fn parses_copy_node() {
     let node = parser.parse_node().unwrap();
 
     match node {
-        Node::Copy(Copy { name, target }) => {
+        Node::Copy(Copy { name, target, .. }) => {
             assert_eq!(name, "a");
             assert_eq!(target, "b");
         }
@@ -451,7 +520,7 @@ fn parses_local_copy_node() {
 
     match node {
         Node::Local(inner) => match inner.as_ref() {
-            Node::Copy(Copy { name, target }) => {
+            Node::Copy(Copy { name, target, .. }) => {
                 assert_eq!(name, "a");
                 assert_eq!(target, "b");
             }
@@ -552,7 +621,7 @@ fn parses_local_bind_node() {
 
     match node {
         Node::Local(inner) => match inner.as_ref() {
-            Node::Bind(Bind { name, target }) => {
+            Node::Bind(Bind { name, target, .. }) => {
                 assert_eq!(name, "a");
                 assert_eq!(target, "b");
             }
@@ -582,9 +651,10 @@ fn guard_basic_node() {
     assert_eq!(program.nodes.len(), 1);
 
     match &program.nodes[0] {
-        Node::Guard(Guard { target, branches })  => {
+        Node::Guard(Guard { target, branches, .. })  => {
             assert_eq!(target, "x");
             assert_eq!(branches.len(), 1);
+            assert_eq!(branches[0].expr, Node::Ident("y".into()));
         }
         _ => panic!("expected guard"),
     }
@@ -600,7 +670,7 @@ fn guard_single_fallback_node() {
     assert_eq!(program.nodes.len(), 1);
 
     match &program.nodes[0] {
-        Node::Guard(Guard { target, branches })  => {
+        Node::Guard(Guard { target, branches, .. })  => {
             assert_eq!(target, "x");
             assert_eq!(branches.len(), 2);
 
@@ -628,7 +698,7 @@ fn guard_chained_node() {
     assert_eq!(program.nodes.len(), 1);
 
     match &program.nodes[0] {
-        Node::Guard(Guard { target, branches })  => {
+        Node::Guard(Guard { target, branches, .. })  => {
             assert_eq!(target, "x");
             assert_eq!(branches.len(), 4);
         }
@@ -667,7 +737,7 @@ fn guard_allows_void_condition() {
     let node = parser.parse_node().expect("expected guard node to parse");
 
     match node {
-        Node::Guard(Guard { target, branches }) => {
+        Node::Guard(Guard { target, branches, .. }) => {
             assert_eq!(target, "x");
             assert_eq!(branches.len(), 1);
 
@@ -718,7 +788,7 @@ fn parses_return_node_with_value() {
     let node = parser.parse_node().expect("failed to parse ret");
 
     match node {
-        Node::Ret(Ret { value: Some(value) }) => {
+        Node::Ret(Ret { value: Some(value), .. }) => {
             match *value {
                 Node::Lit(Literal::Num(n)) => {
                     assert_eq!(n, 42);
@@ -740,7 +810,7 @@ fn parses_local_guard_node() {
 
     match node {
         Node::Local(inner) => match inner.as_ref() {
-            Node::Guard(Guard { target, branches }) => {
+            Node::Guard(Guard { target, branches, .. }) => {
                 assert_eq!(target, "x");
                 assert_eq!(branches.len(), 2);
                 assert_eq!(branches[0].expr, Node::Lit(Literal::Num(12)));
@@ -808,7 +878,7 @@ fn parses_function_with_single_param_and_body() {
     let expr = parser.parse_node().expect("failed to parse function");
 
     match expr {
-        Node::Func(Func { name, params, body }) => {
+        Node::Func(Func { name, params, body, .. }) => {
             assert_eq!(name, "f");
 
             assert_eq!(params.len(), 1);
@@ -819,7 +889,7 @@ fn parses_function_with_single_param_and_body() {
 
             match &body[0] {
                 Node::Ret(Ret {
-                    value: Some(value),
+                    value: Some(value), ..
                 }) => {
                     assert!(matches!(
                         value.as_ref(),
@@ -853,6 +923,708 @@ fn function_missing_body_block_is_error() {
     );
 }
 
+// Block Expression Tests
+#[test]
+fn parse_nested_expression_block() {
+    let node = parse_node("x = :[ :[ 1 ]: ]:;");
+
+    let Node::Define(Define { value, .. }) = node else {
+        panic!("expected define node, got {:?}", node);
+    };
+
+    assert_eq!(
+        *value,
+        Node::BlockExpr(BlockExpr {
+            nodes: vec![],
+            value: Some(Box::new(Node::BlockExpr(BlockExpr {
+                nodes: vec![],
+                value: Some(Box::new(Node::Lit(Literal::Num(1)))),
+                span: NodeSpan::default(),
+            }))),
+            span: NodeSpan::default(),
+        })
+    );
+}
+
+#[test]
+fn block_expression_statements_do_not_leak_into_outer_statement() {
+    let node = parse_node("x = :[ y = 1; z = 2; y + z ]:;");
+
+    let Node::Define(Define { name, value, .. }) = node else {
+        panic!("expected define node, got {:?}", node);
+    };
+
+    assert_eq!(name, "x");
+
+    match *value {
+        Node::BlockExpr(BlockExpr { nodes, value: Some(value), .. }) => {
+            assert_eq!(nodes.len(), 2);
+            assert!(matches!(*value, Node::Add(_, _)));
+        }
+        other => panic!("expected block expression, got {:?}", other),
+    }
+}
+
+#[test]
+fn block_expression_requires_closing_delimiter() {
+    let src = ":[ y = 1; y";
+    let tokens = Lexer::new(src).tokenize().unwrap();
+    let mut parser = Parser::new(&tokens);
+
+    let err = parser.parse_expr().expect_err("expected unterminated block expression");
+    let source = Source::new(src.to_string());
+    let msg = render(&err, &source);
+
+    assert!(
+        msg.contains("block expression"),
+        "expected block expression error, got:\n{msg}"
+    );
+}
+
+#[test]
+fn parser_errors_carry_family_codes() {
+    assert_eq!(parse_node_err("x = 1").code, Some(codes::UNTERMINATED_STATEMENT));
+    assert_eq!(parse_node_err("x = y;").code, Some(codes::INVALID_DEFINE));
+    assert_eq!(parse_node_err("a := b := c;").code, Some(codes::INVALID_COPY));
+    assert_eq!(
+        parse_node_err("fn f :()( )( ret; ):").code,
+        Some(codes::CHAINING_NOT_ALLOWED)
+    );
+}
+
+#[test]
+fn invalid_define_span_covers_all_leftover_tokens() {
+    let err = parse_node_err("x = 1 2 3;");
+
+    assert_eq!(err.message, "invalid define statement");
+    assert_eq!(err.span, Span { start: 6, end: 9 });
+}
+
+#[test]
+fn binding_targets_share_the_identifier_rule() {
+    let cases = [
+        ("void = 1;", "void", codes::INVALID_DEFINE),
+        ("12 =;", "12", codes::INVALID_DEFINE),
+        ("true := a;", "true", codes::INVALID_COPY),
+        ("12 :> b;", "12", codes::INVALID_BIND),
+        ("emp ?= a : 1;", "emp", codes::INVALID_GUARD),
+        ("fn void :()( ret; ):", "void", codes::INVALID_FUNCTION),
+    ];
+
+    for (src, name, code) in cases {
+        let err = parse_node_err(src);
+        assert_eq!(err.message, format!("invalid identifier name `{name}`"), "{src}");
+        assert_eq!(err.code, Some(code), "{src}");
+    }
+}
+
+#[test]
+fn only_calls_stand_alone_as_statements() {
+    let err = parse_node_err("1 + 1;");
+
+    assert_eq!(err.message, "invalid function call statement");
+    assert_eq!(err.code, Some(codes::INVALID_CALL));
+    assert!(matches!(parse_node("f(1 + 1);"), Node::Call(_)));
+}
+
+#[test]
+fn lexical_identifiers_are_valid_targets() {
+    for src in ["_foo = 1;", "9lives := a;", "Total :> b;", "a1 ?= b;"] {
+        parse_node(src);
+    }
+}
+
+#[test]
+fn recovering_parser_reports_every_bad_statement() {
+    let tokens = Lexer::new("x = ; y = 2; z = ;").tokenize().expect("lexing failed");
+    let mut parser = Parser::new(&tokens);
+
+    let (program, diagnostics) = parser.parse_program_recovering();
+
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics.iter().all(|d| d.message == "invalid define statement"));
+    assert_eq!(
+        program.nodes,
+        vec![Node::Define(Define {
+            name: "y".into(),
+            ty: None,
+            value: Box::new(Node::Lit(Literal::Num(2))),
+            span: NodeSpan::default(),
+        })]
+    );
+}
+
+#[test]
+fn recovering_parser_resumes_after_broken_block() {
+    let tokens = Lexer::new(":{ a = ; }: b = 1;").tokenize().expect("lexing failed");
+    let mut parser = Parser::new(&tokens);
+
+    let (program, diagnostics) = parser.parse_program_recovering();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        program.nodes.last(),
+        Some(&Node::Define(Define {
+            name: "b".into(),
+            ty: None,
+            value: Box::new(Node::Lit(Literal::Num(1))),
+            span: NodeSpan::default(),
+        }))
+    );
+}
+
+fn parse_num_list(src: &str) -> Result<Vec<i64>, Diagnostic> {
+    let tokens = Lexer::new(src).tokenize().expect("lexing failed");
+    let mut parser = Parser::new(&tokens);
+    parser.parse_separated(TokenKind::Comma, TokenKind::RParen, "(1, 2)", |p| match p.parse_expr()? {
+        Node::Lit(Literal::Num(n)) => Ok(n),
+        other => panic!("expected number, got {other:?}"),
+    })
+}
+
+#[test]
+fn separated_list_may_be_empty() {
+    assert_eq!(parse_num_list(")").unwrap(), Vec::<i64>::new());
+}
+
+#[test]
+fn separated_list_with_single_item() {
+    assert_eq!(parse_num_list("1)").unwrap(), vec![1]);
+}
+
+#[test]
+fn separated_list_with_many_items() {
+    assert_eq!(parse_num_list("1, 2, 3)").unwrap(), vec![1, 2, 3]);
+}
+
+#[test]
+fn separated_list_accepts_trailing_separator() {
+    assert_eq!(parse_num_list("1, 2,)").unwrap(), vec![1, 2]);
+}
+
+#[test]
+fn separated_list_requires_separator_between_items() {
+    let err = parse_num_list("1 2)").unwrap_err();
+
+    assert_eq!(err.message, "expected `,` or `)`");
+    assert!(err.help.as_deref().unwrap().ends_with("Example: `(1, 2)`"));
+}
+
+#[test]
+fn missing_separator_help_matches_the_list_kind() {
+    let err = parse_node_err("fn f :(a b)( ret a; ):");
+    assert_eq!(err.message, "expected `,` or `)(`");
+    assert!(err.help.as_deref().unwrap().ends_with("Example: `fn f :(a, b = 2)( ret a; ):`"));
+
+    let err = parse_node_err("x = f(1 2);");
+    assert_eq!(err.message, "expected `,` or `)`");
+    assert!(err.help.as_deref().unwrap().ends_with("Example: `f(1, 2)`"));
+}
+
+#[test]
+fn define_from_identifier_suggests_copy_operator() {
+    let src = "x = y;\n";
+    let err = parse_node_err(src);
+
+    assert_eq!(err.suggestion, Some((Span { start: 2, end: 3 }, ":=".to_string())));
+
+    let rendered = render(&err, &Source::new(src.to_string()));
+    assert!(
+        rendered.ends_with(
+            "\
+help: replace `=` with `:=`
+  |
+1 | x := y;
+  |   ~~
+"
+        ),
+        "got:\n{rendered}"
+    );
+}
+
+#[test]
+fn bare_identifier_values_share_one_error_shape() {
+    let define = parse_node_err("x = y;");
+    let default = parse_node_err("fn f :(a = y)( ret a; ):");
+
+    for (err, start, code) in [(&define, 4, codes::INVALID_DEFINE), (&default, 11, codes::INVALID_FUNCTION)] {
+        assert_eq!(err.message, "bare identifier is not a value");
+        assert_eq!(err.code, Some(code));
+        assert_eq!(err.span, Span { start, end: start + 1 });
+    }
 
+    // only a define can swap its `=` for `:=`
+    assert!(define.suggestion.is_some());
+    assert_eq!(default.suggestion, None);
+}
+
+#[test]
+fn guard_branches_and_call_arguments_accept_identifiers() {
+    let Node::Guard(guard) = parse_node("x ?= y : z;") else {
+        panic!("expected guard");
+    };
+    assert_eq!(guard.branches[0].expr, Node::Ident("y".into()));
+
+    let Node::Call(call) = parse_node("f(y);") else {
+        panic!("expected call");
+    };
+    assert_eq!(call.args, vec![Node::Ident("y".into())]);
+}
+
+#[test]
+fn negative_i64_min_literal_is_in_range() {
+    let node = parse_node("x = -9223372036854775808;");
+
+    assert_eq!(
+        node,
+        Node::Define(Define {
+            name: "x".into(),
+            ty: None,
+            value: Box::new(Node::Lit(Literal::Num(i64::MIN))),
+            span: NodeSpan::default(),
+        })
+    );
+}
+
+#[test]
+fn negative_numeric_literals_parse_as_literals() {
+    let tokens = Lexer::new("-5").tokenize().unwrap();
+    assert_eq!(Parser::new(&tokens).parse_expr().unwrap(), Node::Lit(Literal::Num(-5)));
+
+    let tokens = Lexer::new("-1.5").tokenize().unwrap();
+    assert_eq!(
+        Parser::new(&tokens).parse_expr().unwrap(),
+        Node::Lit(Literal::Dec("-1.5".into()))
+    );
+}
+
+#[test]
+fn negated_non_literals_stay_neg() {
+    let tokens = Lexer::new("-x").tokenize().unwrap();
+    assert_eq!(
+        Parser::new(&tokens).parse_expr().unwrap(),
+        Node::Neg(Box::new(Node::Ident("x".into())))
+    );
+
+    let tokens = Lexer::new("-(5)").tokenize().unwrap();
+    assert_eq!(
+        Parser::new(&tokens).parse_expr().unwrap(),
+        Node::Neg(Box::new(Node::Lit(Literal::Num(5))))
+    );
+}
+
+#[test]
+fn negative_literal_below_i64_min_is_out_of_range() {
+    let err = parse_node_err("x = -9223372036854775809;");
 
+    assert_eq!(err.message, "integer literal out of range");
+}
+
+#[test]
+fn positive_i64_min_magnitude_is_out_of_range() {
+    let err = parse_node_err("x = 9223372036854775808;");
+
+    assert_eq!(err.message, "integer literal out of range");
+    assert_eq!(err.span, Span { start: 4, end: 23 });
+}
+
+fn parse_params(src: &str) -> Vec<Param> {
+    match parse_node(src) {
+        Node::Func(Func { params, .. }) => params,
+        other => panic!("expected function, got {other:?}"),
+    }
+}
+
+#[test]
+fn parses_negative_default_parameter() {
+    let params = parse_params("fn f :(x = -1)( ret x; ):");
+
+    assert_eq!(
+        params,
+        vec![Param {
+            name: "x".into(),
+            default: Some(Node::Lit(Literal::Num(-1))),
+        }]
+    );
+}
+
+#[test]
+fn parses_expression_default_parameter() {
+    let params = parse_params("fn f :(x = -1, y = 2 + 3)( ret y; ):");
+
+    assert_eq!(params.len(), 2);
+    assert_eq!(
+        params[1].default,
+        Some(Node::Add(
+            Box::new(Node::Lit(Literal::Num(2))),
+            Box::new(Node::Lit(Literal::Num(3))),
+        ))
+    );
+}
+
+#[test]
+fn default_parameter_without_value_is_error() {
+    let err = parse_node_err("fn f :(x = )( ret x; ):");
+    assert_eq!(err.message, "invalid default parameter");
+
+    let err = parse_node_err("fn f :(x = , y)( ret y; ):");
+    assert_eq!(err.message, "invalid default parameter");
+}
+
+#[test]
+fn stray_comma_in_parameter_list_is_error() {
+    let src = "fn f :( , x )( ret x; ):";
+    let err = parse_node_err(src);
+    assert_eq!(err.message, "missing parameter before `,`");
+    assert_eq!(err.code, Some(codes::INVALID_FUNCTION));
+    assert_eq!(err.span.start, src.find(',').unwrap());
+
+    let src = "fn f :( x,, y )( ret y; ):";
+    let err = parse_node_err(src);
+    assert_eq!(err.message, "missing parameter before `,`");
+    assert_eq!(err.span.start, src.find(",,").unwrap() + 1);
+}
+
+#[test]
+fn coalesce_binds_looser_than_logical_or() {
+    let node = parse_node("x = a || b ?? c;");
+
+    let Node::Define(Define { value, .. }) = node else {
+        panic!("expected define node, got {:?}", node);
+    };
+
+    assert_eq!(
+        *value,
+        Node::Coalesce(
+            Box::new(Node::Or(
+                Box::new(Node::Ident("a".into())),
+                Box::new(Node::Ident("b".into())),
+            )),
+            Box::new(Node::Ident("c".into())),
+        )
+    );
+}
+
+#[test]
+fn unclosed_group_names_the_token_found() {
+    let err = parse_node_err("x = (1 + 2;");
+    assert_eq!(err.message, "expected `)`, found `;`");
+}
+
+#[test]
+fn unexpected_value_token_is_named() {
+    let err = parse_node_err("x = ,;");
+    assert_eq!(err.message, "unexpected `,` in value expression");
+}
+
+#[test]
+fn function_name_must_start_with_a_letter() {
+    let err = parse_node_err("fn 1bad :( )( ret; ):");
+    assert_eq!(err.message, "invalid function name");
+
+    assert!(matches!(parse_node("fn f1 :( )( ret; ):"), Node::Func(_)));
+}
+
+#[test]
+fn deep_nesting_is_a_clean_error() {
+    let src = format!("x = {}1{};", "(".repeat(1000), ")".repeat(1000));
+    let err = parse_node_err(&src);
+    assert_eq!(err.message, "expression nested too deeply");
+
+    let src = format!("x = {}1;", "-".repeat(1000));
+    let err = parse_node_err(&src);
+    assert_eq!(err.message, "expression nested too deeply");
+
+    let src = format!("x = {}1{};", ":[ ".repeat(1000), " ]:".repeat(1000));
+    let err = parse_node_err(&src);
+    assert_eq!(err.message, "expression nested too deeply");
+}
+
+#[test]
+fn flat_chains_do_not_count_as_nesting() {
+    let src = format!("x = {}1;", "1 + ".repeat(999));
+    assert!(matches!(parse_node(&src), Node::Define(_)));
+
+    let src = format!("x = f{};", "(1) ".repeat(1000));
+    assert!(matches!(parse_node(&src), Node::Define(_)));
+
+    let tokens = Lexer::new("x = 1 + 2 + 3 + 4;").tokenize().unwrap();
+    assert!(Parser::new(&tokens).with_max_depth(2).parse_node().is_ok());
+}
+
+#[test]
+fn nesting_limit_is_configurable() {
+    let tokens = Lexer::new("x = ((1));").tokenize().unwrap();
+
+    assert!(Parser::new(&tokens).with_max_depth(3).parse_node().is_ok());
+    assert!(Parser::new(&tokens).with_max_depth(2).parse_node().is_err());
+}
+
+
+#[test]
+fn parses_flag_literals() {
+    let Node::Define(Define { value, .. }) = parse_node("x = true;") else {
+        panic!("expected define node");
+    };
+    assert_eq!(*value, Node::Lit(Literal::Flag(true)));
+
+    let Node::Define(Define { value, .. }) = parse_node("x = false || truthy;") else {
+        panic!("expected define node");
+    };
+    assert_eq!(
+        *value,
+        Node::Or(
+            Box::new(Node::Lit(Literal::Flag(false))),
+            Box::new(Node::Ident("truthy".into())),
+        )
+    );
+}
+
+#[test]
+fn parses_type_annotations() {
+    let Node::Define(Define { ty, .. }) = parse_node("text s = \"hi\";") else {
+        panic!("expected define node");
+    };
+    assert_eq!(ty, Some(Type::Text));
+
+    let Node::Local(inner) = parse_node("loc num n =;") else {
+        panic!("expected local node");
+    };
+    assert_eq!(
+        *inner,
+        Node::DefineEmpty(DefineEmpty {
+            name: "n".into(),
+            ty: Some(Type::Num),
+            span: NodeSpan::default(),
+        })
+    );
+}
+
+#[test]
+fn type_annotations_are_rejected_on_other_bindings() {
+    let cases = [
+        ("num x := y;", "num", codes::INVALID_COPY),
+        ("loc text s :> t;", "text", codes::INVALID_BIND),
+        ("flag f ?= a : b;", "flag", codes::INVALID_GUARD),
+    ];
+
+    for (src, keyword, code) in cases {
+        let err = parse_node_err(src);
+        assert_eq!(err.message, "type annotations are only allowed on `=` definitions", "{src}");
+        assert_eq!(err.code, Some(code), "{src}");
+        assert_eq!(&src[err.span.start..err.span.end], keyword, "{src}");
+    }
+}
+
+#[test]
+fn guard_branches_may_be_calls() {
+    let Node::Guard(guard) = parse_node("x ?= compute() : other(1, 2);") else {
+        panic!("expected guard node");
+    };
+
+    assert_eq!(guard.branches.len(), 2);
+    assert!(guard.branches.iter().all(|b| matches!(b.expr, Node::Call(_))));
+}
+
+#[test]
+fn double_colon_is_membership_not_a_path() {
+    let Node::Define(Define { value, .. }) = parse_node("a = user::profile::email;") else {
+        panic!("expected define node");
+    };
+
+    assert_eq!(
+        *value,
+        Node::Has(
+            Box::new(Node::Has(
+                Box::new(Node::Ident("user".into())),
+                Box::new(Node::Ident("profile".into())),
+            )),
+            Box::new(Node::Ident("email".into())),
+        )
+    );
+}
+
+#[test]
+fn comparison_chains_are_rejected() {
+    let err = parse_node_err("x = 1 < 2 < 3;");
+    assert_eq!(err.message, "comparison operators cannot be chained");
+    assert_eq!(err.span, Span { start: 10, end: 11 });
+
+    let err = parse_node_err("x = a == b < c;");
+    assert_eq!(err.message, "comparison operators cannot be chained");
+}
+
+#[test]
+fn grouped_comparisons_are_accepted() {
+    assert!(matches!(
+        parse_node("x = (1 < 2) && (2 < 3);"),
+        Node::Define(_)
+    ));
+    assert!(matches!(parse_node("x = (a < b) == c;"), Node::Define(_)));
+}
+
+#[test]
+fn calls_accept_any_callee_expression() {
+    let Node::Define(Define { value, .. }) = parse_node("x = :[ f ]:(1);") else {
+        panic!("expected define node");
+    };
+
+    let Node::Call(call) = *value else {
+        panic!("expected call, got {:?}", value);
+    };
+    assert!(matches!(*call.callee, Node::BlockExpr(_)));
+    assert_eq!(call.args, vec![Node::Lit(Literal::Num(1))]);
+
+    // `)(` lexes as a function chain token, so a called call needs a space
+    let Node::Call(call) = parse_node("make(2) (3);") else {
+        panic!("expected call statement");
+    };
+    assert!(matches!(*call.callee, Node::Call(_)));
+}
+
+#[test]
+fn empty_program_parses() {
+    // Druim has no comment syntax yet, so whitespace is the only
+    // content a program can have without statements.
+    for src in ["", "  \n\t\r\n "] {
+        let tokens = Lexer::new(src).tokenize().unwrap();
+        assert_eq!(tokens.len(), 1);
+
+        let program = Parser::new(&tokens).parse_program().unwrap();
+        assert_eq!(program, Program { nodes: vec![] });
+    }
+}
+
+#[test]
+fn leading_block_end_is_unmatched() {
+    let err = parse_node_err("}: x = 1;");
+
+    assert_eq!(err.message, "unmatched closing delimiter `}:`");
+    assert_eq!(err.code, Some(codes::UNMATCHED_DELIMITER));
+    assert_eq!(err.span, Span { start: 0, end: 2 });
+}
+
+#[test]
+fn leading_function_end_is_unmatched() {
+    let err = parse_node_err("): x = 1;");
+
+    assert_eq!(err.message, "unmatched closing delimiter `):`");
+    assert_eq!(err.span, Span { start: 0, end: 2 });
+}
+
+#[test]
+fn block_closed_by_another_family_is_mismatched() {
+    let err = parse_node_err(":{ a = 1; ]:");
+
+    assert_eq!(err.message, "mismatched closing delimiter: expected `}:`, found `]:`");
+    assert_eq!(err.code, Some(codes::UNMATCHED_DELIMITER));
+    assert_eq!(err.span, Span { start: 10, end: 12 });
+    assert_eq!(err.secondary, vec![(Span { start: 0, end: 2 }, "opened here".to_string())]);
+}
+
+#[test]
+fn function_and_block_expression_closers_are_checked_too() {
+    let err = parse_node_err("fn f :()( ret 1; }:");
+    assert_eq!(err.message, "mismatched closing delimiter: expected `):`, found `}:`");
+
+    let err = parse_node_err("x = :[ y = 1; ): ]:;");
+    assert_eq!(err.message, "mismatched closing delimiter: expected `]:`, found `):`");
+    assert_eq!(err.secondary[0].0, Span { start: 4, end: 6 });
+}
+
+#[test]
+fn nested_structures_do_not_count_as_mismatches() {
+    assert!(matches!(parse_node(":{ x = :[ 1 ]:; }:"), Node::Block(_)));
+    assert!(matches!(parse_node("fn f :()( x = :[ :[ 1 ]: ]:; ):"), Node::Func(_)));
+}
+
+#[test]
+fn recovery_skips_stray_closer() {
+    let tokens = Lexer::new("x = 1; ]: y = 2;").tokenize().unwrap();
+    let (program, diagnostics) = Parser::new(&tokens).parse_program_recovering();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "unmatched closing delimiter `]:`");
+    assert_eq!(program.nodes.len(), 2);
+    assert!(matches!(&program.nodes[1], Node::Define(d) if d.name == "y"));
+}
+
+#[test]
+fn recovery_skips_the_whole_broken_structure() {
+    let cases = [
+        ("fn Bad :( )( ret; ): x = 1;", "invalid function name"),
+        ("fn f :()( fn g :()( ret 1; ): ret g(); ): x = 1;", "invalid return statement"),
+        (":{ a = ; }{ b = 2; }: x = 1;", "invalid define statement"),
+        ("y = :[ a = ; a ]:; x = 1;", "invalid define statement"),
+    ];
+
+    for (src, message) in cases {
+        let tokens = Lexer::new(src).tokenize().unwrap();
+        let (program, diagnostics) = Parser::new(&tokens).parse_program_recovering();
+
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, [message], "{src}");
+        assert_eq!(program.nodes.len(), 1, "{src}");
+    }
+}
+
+#[test]
+fn parse_expression_accepts_a_complete_expression() {
+    let tokens = Lexer::new("1 + 2 * x").tokenize().unwrap();
+    let expr = Parser::new(&tokens).parse_expression().expect("expected expression");
+
+    assert_eq!(
+        expr,
+        Node::Add(
+            Box::new(Node::Lit(Literal::Num(1))),
+            Box::new(Node::Mul(
+                Box::new(Node::Lit(Literal::Num(2))),
+                Box::new(Node::Ident("x".into())),
+            )),
+        )
+    );
+}
+
+#[test]
+fn parse_expression_rejects_trailing_tokens() {
+    let tokens = Lexer::new("1 + 2 garbage here").tokenize().unwrap();
+    let err = Parser::new(&tokens).parse_expression().expect_err("expected trailing tokens error");
+
+    assert_eq!(err.message, "unexpected trailing tokens");
+    assert_eq!(err.code, Some(codes::INVALID_EXPRESSION));
+    assert_eq!(err.span, Span { start: 6, end: 18 });
+}
+
+#[test]
+fn parser_can_own_its_tokens() {
+    let program = Parser::from_source("x = 1 + 2; y =;")
+        .expect("lexing failed")
+        .parse_program()
+        .expect("parsing failed");
+
+    assert_eq!(program.nodes.len(), 2);
+
+    let err = Parser::from_source("x = @;").err().expect("expected a lex error");
+    assert_eq!(err.message, "unexpected character `@`");
+    assert_eq!(err.span, Span { start: 4, end: 5 });
+}
+
+#[test]
+fn statements_record_their_spans() {
+    let src = "x = 1;  loc y =; f(x);";
+    let program = parse_program(src);
+    let shown: Vec<&str> = program
+        .nodes
+        .iter()
+        .map(|node| {
+            let span = node.span().expect("statement span");
+            &src[span.start..span.end]
+        })
+        .collect();
+
+    assert_eq!(shown, ["x = 1;", "loc y =;", "f(x)"]);
+
+    // spans are positions, not structure
+    assert_eq!(program, parse_program("x=1; loc y =; f( x );"));
+}
 
@@ -0,0 +1,112 @@
+use crate::compiler::ast::Program;
+use crate::compiler::error::{Diagnostic, Span};
+use crate::compiler::lexer::{LexError, Lexer};
+use crate::compiler::parser::Parser;
+use crate::compiler::semantics::eval::Evaluator;
+use crate::compiler::token::{Token, TokenKind};
+
+/// Everything produced by a single lex + parse pass.
+#[derive(Debug, Clone)]
+pub struct Compilation {
+    /// Tokens of the source, ending with `Eof`. If lexing failed, this
+    /// holds the tokens lexed before the error.
+    pub tokens: Vec<Token>,
+
+    /// Statements that parsed successfully.
+    pub program: Program,
+
+    /// Lex and parse errors, in the order they were found.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Lex and parse `src`, keeping the tokens alongside the AST.
+///
+/// Parsing recovers after statement errors, so `program` holds every
+/// statement that could be parsed and `diagnostics` every error. A lex
+/// error stops the pass before parsing.
+pub fn compile_full(src: &str) -> Compilation {
+    let mut lexer = Lexer::new(src);
+    let mut tokens = Vec::new();
+
+    loop {
+        match lexer.next_token() {
+            Ok(token) => {
+                let done = token.kind == TokenKind::Eof;
+                tokens.push(token);
+                if done {
+                    break;
+                }
+            }
+            Err(err) => {
+                return Compilation {
+                    tokens,
+                    program: Program { nodes: Vec::new() },
+                    diagnostics: vec![lex_diagnostic(&err, src)],
+                };
+            }
+        }
+    }
+
+    let (program, diagnostics) = Parser::new(&tokens).parse_program_recovering();
+
+    Compilation {
+        tokens,
+        program,
+        diagnostics,
+    }
+}
+
+/// Lex and parse `src` into a program.
+///
+/// Returns every lex and parse diagnostic if there were any.
+pub fn compile(src: &str) -> Result<Program, Vec<Diagnostic>> {
+    let compiled = compile_full(src);
+
+    if compiled.diagnostics.is_empty() {
+        Ok(compiled.program)
+    } else {
+        Err(compiled.diagnostics)
+    }
+}
+
+/// Compile `src` and evaluate it, returning the evaluator so callers can
+/// inspect the resulting bindings.
+pub fn run(src: &str) -> Result<Evaluator, Vec<Diagnostic>> {
+    let program = compile(src)?;
+
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval_program(&program)
+        .map_err(|diagnostic| vec![diagnostic])?;
+
+    Ok(evaluator)
+}
+
+/// Diagnostic for a lex error in `src`.
+pub(crate) fn lex_diagnostic(err: &LexError, src: &str) -> Diagnostic {
+    match err {
+        LexError::UnexpectedChar { ch, pos, .. } => Diagnostic::error(
+            format!("unexpected character `{ch}`"),
+            Span {
+                start: *pos,
+                end: pos + ch.len_utf8(),
+            },
+        ),
+        LexError::UnterminatedText { pos, .. } => Diagnostic::error(
+            "unterminated text literal",
+            Span {
+                start: *pos,
+                end: src.len(),
+            },
+        )
+        .with_help("Druim expected a closing quote `\"`."),
+        LexError::UnknownEscape { ch, pos, .. } => Diagnostic::error(
+            format!("unknown escape `\\{ch}` in text literal"),
+            Span {
+                start: *pos,
+                end: pos + 1 + ch.len_utf8(),
+            },
+        )
+        .with_help("Druim recognizes the escapes `\\n`, `\\t`, `\\r`, `\\0`, `\\\\` and `\\\"`."),
+    }
+}
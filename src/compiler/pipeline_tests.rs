@@ -0,0 +1,117 @@
+use crate::compiler::ast::{Define, Literal, Node, NodeSpan, Type};
+use crate::compiler::lexer::Lexer;
+use crate::compiler::parser::Parser;
+use crate::compiler::pipeline::compile_full;
+use crate::compiler::semantics::value::Value;
+use crate::compiler::{compile, run};
+
+#[test]
+fn compile_full_matches_standalone_lex_and_parse() {
+    let src = "x = 1 + 2; fn f :(a)( ret a; ): y = f(x);";
+
+    let compiled = compile_full(src);
+
+    let tokens = Lexer::new(src).tokenize().expect("lexing failed");
+    let program = Parser::new(&tokens).parse_program().expect("parsing failed");
+
+    assert_eq!(compiled.tokens, tokens);
+    assert_eq!(compiled.program, program);
+    assert!(compiled.diagnostics.is_empty());
+}
+
+#[test]
+fn compile_full_keeps_good_statements_next_to_errors() {
+    let compiled = compile_full("x = ; y = 2;");
+
+    assert_eq!(compiled.diagnostics.len(), 1);
+    assert_eq!(compiled.program.nodes.len(), 1);
+    assert_eq!(compiled.tokens.last().map(|t| t.lexeme.as_str()), Some(""));
+}
+
+#[test]
+fn diagnostics_on_escaped_text_cover_its_source() {
+    let src = r#"x = 1 "a\0b";"#;
+    let compiled = compile_full(src);
+
+    assert_eq!(compiled.diagnostics.len(), 1);
+    let span = compiled.diagnostics[0].span;
+    assert_eq!(&src[span.start..span.end], r#""a\0b""#);
+}
+
+#[test]
+fn compile_full_reports_lex_errors_as_diagnostics() {
+    let compiled = compile_full("x = 1; y = @;");
+
+    assert_eq!(compiled.diagnostics.len(), 1);
+    assert_eq!(compiled.diagnostics[0].message, "unexpected character `@`");
+    assert!(compiled.program.nodes.is_empty());
+    assert_eq!(compiled.tokens.len(), 6);
+}
+
+#[test]
+fn compile_returns_program_for_good_source() {
+    let program = compile("x = 1; fn f :(a)( ret a; ): y = f(x);").expect("compile failed");
+
+    assert_eq!(program.nodes.len(), 3);
+}
+
+#[test]
+fn compile_reports_lex_error() {
+    let diagnostics = compile("x = 1 @ 2;").unwrap_err();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "unexpected character `@`");
+}
+
+#[test]
+fn compile_reports_every_parse_error() {
+    let diagnostics = compile("x = ; y = 2; z = ;").unwrap_err();
+
+    assert_eq!(diagnostics.len(), 2);
+}
+
+#[test]
+fn run_evaluates_compiled_program() {
+    let evaluator = run("x = 2; y = x * 3;").expect("run failed");
+
+    assert_eq!(evaluator.get("y"), Some(Value::Num(6)));
+}
+
+#[test]
+fn run_reports_runtime_error() {
+    let Err(diagnostics) = run("x = 1 / 0;") else {
+        panic!("expected a runtime error");
+    };
+
+    assert_eq!(diagnostics[0].message, "division by zero");
+}
+
+#[test]
+fn compile_accepts_empty_source() {
+    let program = compile("").expect("compile failed");
+
+    assert!(program.nodes.is_empty());
+}
+
+#[test]
+fn flag_annotated_define_parses_checks_and_evaluates() {
+    let program = compile("flag b = true;").expect("compile failed");
+    assert_eq!(
+        program.nodes,
+        vec![Node::Define(Define {
+            name: "b".into(),
+            ty: Some(Type::Flag),
+            value: Box::new(Node::Lit(Literal::Flag(true))),
+            span: NodeSpan::default(),
+        })]
+    );
+
+    let evaluator = run("flag b = true; flag c = false;").expect("run failed");
+    assert_eq!(evaluator.get("b"), Some(Value::Flag(true)));
+    assert_eq!(evaluator.get("c"), Some(Value::Flag(false)));
+
+    let Err(diagnostics) = run("flag b = 1;") else {
+        panic!("expected a type error");
+    };
+    assert_eq!(diagnostics[0].message, "definition of `b`: expected flag, found num");
+}
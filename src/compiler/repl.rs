@@ -0,0 +1,76 @@
+use std::io::{self, BufRead, Write};
+
+use crate::compiler::ast::Node;
+use crate::compiler::compile;
+use crate::compiler::diagnostic::render_all;
+use crate::compiler::error::Source;
+use crate::compiler::lexer::Lexer;
+use crate::compiler::parser::Parser;
+use crate::compiler::semantics::eval::Evaluator;
+use crate::compiler::token::TokenKind;
+
+/// Prompt written before each line is read.
+const PROMPT: &str = "> ";
+
+/// Read-eval-print loop over `input`, writing to `output`.
+///
+/// Each line is evaluated against one persistent [`Evaluator`], so
+/// bindings carry over between lines. A line holding a single expression
+/// (with or without `;`) prints its value; any other line is compiled
+/// as a program. Errors are rendered as diagnostics against the line.
+/// The loop ends at end of input.
+pub fn run_repl<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    let mut evaluator = Evaluator::new();
+    let mut lines = input.lines();
+
+    loop {
+        write!(output, "{PROMPT}")?;
+        output.flush()?;
+
+        let Some(line) = lines.next() else {
+            writeln!(output)?;
+            return Ok(());
+        };
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let source = Source::new(line.clone());
+
+        if let Some(expr) = lone_expr(&line) {
+            match evaluator.eval_statement(&expr) {
+                Ok(Some(value)) => writeln!(output, "{value}")?,
+                Ok(None) => {}
+                Err(diagnostic) => write!(output, "{}", render_all(&[diagnostic], &source))?,
+            }
+            continue;
+        }
+
+        let program = match compile(&line) {
+            Ok(program) => program,
+            Err(diagnostics) => {
+                write!(output, "{}", render_all(&diagnostics, &source))?;
+                continue;
+            }
+        };
+
+        if let Err(diagnostic) = evaluator.eval_program(&program) {
+            write!(output, "{}", render_all(&[diagnostic], &source))?;
+        }
+    }
+}
+
+fn lone_expr(line: &str) -> Option<Node> {
+    let mut tokens = Lexer::new(line).tokenize().ok()?;
+
+    // A trailing `;` is allowed after the expression.
+    if let [.., semi, _eof] = tokens.as_slice()
+        && semi.kind == TokenKind::Semicolon
+    {
+        tokens.remove(tokens.len() - 2);
+    }
+
+    Parser::new(&tokens).parse_expression().ok()
+}
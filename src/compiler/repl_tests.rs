@@ -0,0 +1,22 @@
+use crate::compiler::repl::run_repl;
+
+fn session(input: &str) -> String {
+    let mut output = Vec::new();
+    run_repl(input.as_bytes(), &mut output).expect("repl failed");
+    String::from_utf8(output).expect("output is not utf-8")
+}
+
+#[test]
+fn bindings_persist_across_lines() {
+    let out = session("x = 2;\nx + 3;\n");
+
+    assert_eq!(out, "> > 5\n> \n");
+}
+
+#[test]
+fn errors_are_rendered_and_the_loop_continues() {
+    let out = session("x = ;\n\"ok\";\n");
+
+    assert!(out.contains("error"), "{out}");
+    assert!(out.ends_with("> ok\n> \n"), "{out}");
+}
@@ -1,7 +1,8 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
-use std::rc::Rc;
+use std::fmt;
+use std::rc::{Rc, Weak};
 
+use super::symbols::{Symbol, SymbolMap, Symbols};
 use super::value::Value;
 
 #[derive(Debug, Clone)]
@@ -11,69 +12,258 @@ pub struct Slot {
 
 pub type SlotRef = Rc<RefCell<Slot>>;
 
+/// A scope shared between the environment and the closures defined in it.
+pub type ScopeRef = Rc<RefCell<Scope>>;
+
 #[derive(Debug, Default)]
 pub struct Scope {
-    names: HashMap<String, SlotRef>,
+    names: SymbolMap<SlotRef>,
+    /// Scope of a single block segment, holding only `loc` bindings.
+    segment: bool,
+    /// Some function has captured this scope; see [`Env::capture`].
+    captured: bool,
+}
+
+/// Failure of an [`Env`] operation on an existing binding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvError {
+    /// The name is not bound in any visible scope.
+    Undefined(String),
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::Undefined(name) => write!(f, "undefined name `{name}`"),
+        }
+    }
 }
 
+impl std::error::Error for EnvError {}
+
+/// The scope chain a function was defined in.
+///
+/// Scopes are shared, not copied: names defined in them after the
+/// function (including the function itself) are visible to it.
+///
+/// The scopes are held strongly, so a function that escapes a block
+/// still sees the block's bindings. A scope usually holds the function
+/// that captured it as well; the [`Env`] breaks those cycles when it is
+/// dropped.
+///
+/// Compares by identity, and does not print its scopes.
+#[derive(Clone, Default)]
+pub struct Captured(Vec<ScopeRef>);
+
+impl fmt::Debug for Captured {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Captured({} scopes)", self.0.len())
+    }
+}
+
+impl PartialEq for Captured {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self.0.iter().zip(&other.0).all(|(a, b)| Rc::ptr_eq(a, b))
+    }
+}
+
+/// Scoped name bindings.
+///
+/// Names are interned into one [`Symbols`] table shared by every scope,
+/// so scopes are keyed by cheap [`Symbol`]s rather than strings.
 #[derive(Debug, Default)]
 pub struct Env {
-    scopes: Vec<Scope>,
+    scopes: Vec<ScopeRef>,
+    symbols: Symbols,
+    /// Every scope a function has captured, cleared on drop.
+    captured: Vec<Weak<RefCell<Scope>>>,
 }
 
 impl Env {
     pub fn new() -> Self {
         Self {
-            scopes: vec![Scope::default()],
+            scopes: vec![ScopeRef::default()],
+            symbols: Symbols::new(),
+            captured: Vec::new(),
         }
     }
 
+    /// The table resolving this environment's symbols back to names.
+    pub fn symbols(&self) -> &Symbols {
+        &self.symbols
+    }
+
     pub fn push_scope(&mut self) {
-        self.scopes.push(Scope::default());
+        self.scopes.push(ScopeRef::default());
+    }
+
+    /// Push the scope of one block segment; see [`Env::scope_of`].
+    pub fn push_segment_scope(&mut self) {
+        self.scopes.push(Rc::new(RefCell::new(Scope {
+            segment: true,
+            ..Scope::default()
+        })));
+    }
+
+    /// The current scope chain, for a function defined here.
+    ///
+    /// The scopes are remembered so that dropping the environment can
+    /// release them.
+    pub fn capture(&mut self) -> Captured {
+        for scope in &self.scopes {
+            let mut s = scope.borrow_mut();
+            if !s.captured {
+                s.captured = true;
+                self.captured.push(Rc::downgrade(scope));
+            }
+        }
+
+        Captured(self.scopes.clone())
+    }
+
+    /// Switch to a function's captured chain with a fresh scope on top.
+    ///
+    /// Returns the chain that was active, to be handed back to
+    /// [`Env::leave_call`] when the call ends.
+    pub fn enter_call(&mut self, captured: &Captured) -> Vec<ScopeRef> {
+        let mut scopes = captured.0.clone();
+        scopes.push(ScopeRef::default());
+        std::mem::replace(&mut self.scopes, scopes)
+    }
+
+    /// Return to the chain that was active before [`Env::enter_call`].
+    pub fn leave_call(&mut self, caller: Vec<ScopeRef>) {
+        self.scopes = caller;
     }
 
     pub fn pop_scope(&mut self) {
         self.scopes.pop().expect("scope underflow");
     }
 
+    /// Number of scopes currently pushed, including the global scope.
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Index of the innermost scope.
+    pub fn innermost(&self) -> usize {
+        self.scopes.len() - 1
+    }
+
+    /// Index of the innermost scope binding `name`, or, if no scope
+    /// does, of the innermost scope that is not a segment scope.
+    pub fn scope_of(&self, name: &str) -> usize {
+        self.symbols
+            .get(name)
+            .and_then(|symbol| {
+                self.scopes
+                    .iter()
+                    .rposition(|s| s.borrow().names.contains_key(&symbol))
+            })
+            .or_else(|| self.scopes.iter().rposition(|s| !s.borrow().segment))
+            .unwrap_or_else(|| self.innermost())
+    }
+
     /// Define a new name in the current scope (creates a fresh slot).
-    pub fn define(&mut self, name: String, value: Value) {
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.define_in(self.innermost(), name, value);
+    }
+
+    /// Define a name in the scope at index `scope` (creates a fresh slot).
+    pub fn define_in(&mut self, scope: usize, name: &str, value: Value) {
+        let symbol = self.symbols.intern(name);
         let slot = Rc::new(RefCell::new(Slot { value }));
-        self.scopes
-            .last_mut()
-            .expect("no scope")
-            .names
-            .insert(name, slot);
+        self.scopes[scope].borrow_mut().names.insert(symbol, slot);
     }
 
     /// Lookup a name, searching from innermost to outermost scope.
     pub fn lookup(&self, name: &str) -> Option<SlotRef> {
+        self.lookup_symbol(self.symbols.get(name)?)
+    }
+
+    /// Lookup an interned name, searching from innermost to outermost scope.
+    pub fn lookup_symbol(&self, symbol: Symbol) -> Option<SlotRef> {
         self.scopes
             .iter()
             .rev()
-            .find_map(|s| s.names.get(name).cloned())
+            .find_map(|s| s.borrow().names.get(&symbol).cloned())
     }
 
     /// Copy a new name in the current scope to an existing slot (aliasing).
-    pub fn copy(&mut self, name: String, target: &str) -> Result<(), ()> {
-        let slot = self.lookup(target).ok_or(())?;
-        self.scopes
-            .last_mut()
-            .expect("no scope")
-            .names
-            .insert(name, slot);
+    ///
+    /// Both names then share one slot: a write through either, such as
+    /// [`Env::assign`], is seen by the other, and chains of copies all
+    /// share the original slot. Copying a name onto itself (`a := a`)
+    /// leaves it bound to the same slot, so it changes nothing. Defining
+    /// either name again gives it a fresh slot and ends the sharing.
+    pub fn copy(&mut self, name: &str, target: &str) -> Result<(), EnvError> {
+        self.copy_in(self.innermost(), name, target)
+    }
+
+    /// [`Env::copy`] into the scope at index `scope`.
+    pub fn copy_in(&mut self, scope: usize, name: &str, target: &str) -> Result<(), EnvError> {
+        let slot = self.slot(target)?;
+        let symbol = self.symbols.intern(name);
+        self.scopes[scope].borrow_mut().names.insert(symbol, slot);
         Ok(())
     }
 
     /// Assign into an existing slot (mutation).
-    pub fn assign(&mut self, name: &str, value: Value) -> Result<(), ()> {
-        let slot = self.lookup(name).ok_or(())?;
-        slot.borrow_mut().value = value;
+    pub fn assign(&mut self, name: &str, value: Value) -> Result<(), EnvError> {
+        self.slot(name)?.borrow_mut().value = value;
         Ok(())
     }
 
+    fn slot(&self, name: &str) -> Result<SlotRef, EnvError> {
+        self.lookup(name)
+            .ok_or_else(|| EnvError::Undefined(name.to_string()))
+    }
+
+    /// Names visible from the current scope, innermost first.
+    ///
+    /// A shadowed name is listed once, for its innermost binding.
+    pub fn visible_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+
+        for scope in self.scopes.iter().rev() {
+            let mut local: Vec<&str> = scope
+                .borrow()
+                .names
+                .keys()
+                .map(|&symbol| self.symbols.resolve(symbol))
+                .collect();
+            local.sort_unstable();
+
+            for name in local {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+
+        names
+    }
+
     /// Convenience for tests: get the current value (if defined).
     pub fn get_value(&self, name: &str) -> Option<Value> {
         self.lookup(name).map(|s| s.borrow().value.clone())
     }
 }
+
+impl Drop for Env {
+    /// Release the captured scopes.
+    ///
+    /// A function stored in a scope it captured keeps that scope alive,
+    /// so emptying the scopes is what lets such cycles be freed. The
+    /// bindings are dropped after the scope's borrow ends, since they may
+    /// hold the last reference to other scopes.
+    fn drop(&mut self) {
+        for scope in self.captured.drain(..) {
+            if let Some(scope) = scope.upgrade() {
+                let names = std::mem::take(&mut scope.borrow_mut().names);
+                drop(names);
+            }
+        }
+    }
+}
@@ -1,30 +1,155 @@
-use crate::compiler::ast::{Node, Program};
-use crate::compiler::semantics::env::Env;
+use std::cmp::Ordering;
+
+use crate::compiler::ast::{self, BlockExpr, Call, Node, Program, Type};
+use crate::compiler::error::{Diagnostic, Note, Span};
+use crate::compiler::semantics::env::{Env, EnvError};
+use crate::compiler::semantics::numeric::Numeric;
+use crate::compiler::semantics::suggest::closest_name;
 use crate::compiler::semantics::truth::{truth_of, Truth};
-use crate::compiler::semantics::value::Value;
+use crate::compiler::semantics::value::{Func, Value};
 
 pub struct Evaluator {
     env: Env,
+    strict_names: bool,
+    strict_guard_target: bool,
 }
 
+/// Non-local exit raised while evaluating a node.
+///
+/// `ret` unwinds through any enclosing block expressions until the
+/// nearest function call catches it. Runtime errors unwind all the way
+/// out to the caller of the evaluator.
 #[derive(Debug, Clone, PartialEq)]
-enum Control {
-    Continue,
+enum Unwind {
     Return(Value),
+
+    /// A runtime error that has not yet reached a node with a recorded
+    /// span; the first one it unwinds through places it there.
+    Unplaced(Diagnostic),
+
+    Error(Diagnostic),
+}
+
+impl Unwind {
+    /// Anchor an unplaced error at `node`, if the node records a span.
+    fn placed_at(self, node: &Node) -> Unwind {
+        match (self, node.span()) {
+            (Unwind::Unplaced(diag), Some(span)) => Unwind::Error(Diagnostic { span, ..diag }),
+            (unwind, _) => unwind,
+        }
+    }
+
+    fn with_note(self, note: Note) -> Unwind {
+        match self {
+            Unwind::Unplaced(diag) => Unwind::Unplaced(diag.with_note(note)),
+            Unwind::Error(diag) => Unwind::Error(diag.with_note(note)),
+            ret @ Unwind::Return(_) => ret,
+        }
+    }
+}
+
+/// Build a runtime diagnostic.
+///
+/// The error is placed as it unwinds, at the innermost node around it
+/// that records a span: the name that was read, the call, or else the
+/// statement being run.
+fn runtime_error(message: impl Into<String>) -> Unwind {
+    Unwind::Unplaced(Diagnostic::error(message, Span { start: 0, end: 0 }))
+}
+
+/// Convert an unwind that escaped to the top level into a diagnostic.
+fn top_level(unwind: Unwind) -> Diagnostic {
+    match unwind {
+        Unwind::Return(_) => Diagnostic::error(
+            "return executed outside of a function",
+            Span { start: 0, end: 0 },
+        ),
+        Unwind::Unplaced(diag) | Unwind::Error(diag) => diag,
+    }
+}
+
+/// Build a runtime type error: `<context>: expected <expected>, found <type>`.
+fn type_error(context: &str, expected: &str, found: &Value) -> Unwind {
+    runtime_error(format!(
+        "{context}: expected {expected}, found {}",
+        found.type_name()
+    ))
+}
+
+/// Build a runtime error for an operation on a missing binding, e.g.
+/// `cannot copy from undefined `x``.
+/// Operands of an infix operator [`Evaluator::eval_chain`] evaluates.
+fn evaluated_operands(node: &Node) -> Option<(&Node, &Node)> {
+    match node {
+        Node::Add(l, r)
+        | Node::Sub(l, r)
+        | Node::Mul(l, r)
+        | Node::Div(l, r)
+        | Node::Mod(l, r)
+        | Node::Eq(l, r)
+        | Node::Ne(l, r)
+        | Node::Lt(l, r)
+        | Node::Le(l, r)
+        | Node::Gt(l, r)
+        | Node::Ge(l, r)
+        | Node::And(l, r)
+        | Node::Or(l, r)
+        | Node::Has(l, r)
+        | Node::Coalesce(l, r) => Some((l, r)),
+        _ => None,
+    }
+}
+
+fn env_error(action: &str, err: EnvError) -> Unwind {
+    match err {
+        EnvError::Undefined(name) => runtime_error(format!("cannot {action} undefined `{name}`")),
+    }
 }
 
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Evaluator {
     pub fn new() -> Self {
         Self {
             env: Env::new(),
+            strict_names: false,
+            strict_guard_target: false,
         }
     }
 
-    pub fn eval_program(&mut self, program: &Program) {
+    /// Make reading an undefined name a runtime error.
+    ///
+    /// By default an undefined read evaluates to `void`.
+    pub fn with_strict_names(mut self, strict: bool) -> Self {
+        self.strict_names = strict;
+        self
+    }
+
+    /// Make guards assign only to names that are already declared.
+    ///
+    /// By default a guard defines its target if it does not exist.
+    pub fn with_strict_guard_target(mut self, strict: bool) -> Self {
+        self.strict_guard_target = strict;
+        self
+    }
+
+    /// Evaluate every top-level node in order.
+    ///
+    /// The program's result is the value of its last node when that node
+    /// is an expression statement, and `void` otherwise (including for
+    /// an empty program). The parser only accepts a function call as an
+    /// expression statement, so a parsed program produces a value by
+    /// ending in a call: `fn two :()( ret 1 + 1; ): two();` results in 2.
+    pub fn eval_program(&mut self, program: &Program) -> Result<Value, Diagnostic> {
+        let mut result = Value::Void;
         for node in &program.nodes {
-            self.eval_node(node);
+            result = self.eval_statement(node)?.unwrap_or(Value::Void);
         }
+        Ok(result)
     }
 
     /// For tests only (read current value).
@@ -32,142 +157,683 @@ impl Evaluator {
         self.env.get_value(name)
     }
 
-    fn eval_value(&mut self, node: &Node) -> Value {
+    fn eval_value(&mut self, node: &Node) -> Result<Value, Unwind> {
+        self.eval_value_unplaced(node).map_err(|unwind| unwind.placed_at(node))
+    }
+
+    fn eval_value_unplaced(&mut self, node: &Node) -> Result<Value, Unwind> {
         match node {
-            Node::Lit(lit) => Value::from_literal(lit),
+            Node::Lit(lit) => Ok(Value::from_literal(lit)),
+
+            Node::Ident(ident) => match self.env.lookup(&ident.name) {
+                Some(slot) => Ok(slot.borrow().value.clone()),
+                None if self.strict_names => Err(self.unknown_binding(&ident.name)),
+                None => Ok(Value::Void),
+            },
 
-            Node::Ident(name) => {
-                self.env.get_value(name).unwrap_or(Value::Void)
+            Node::Neg(inner) => {
+                let v = self.eval_value(inner)?;
+                arith(ArithOp::Sub, Value::Num(0), v)
             }
 
-            Node::Func(func) => {
-                let value = Value::Func(crate::compiler::semantics::value::Func {
-                    name: func.name.clone(),
-                    params: func.params.clone(),
-                    body: func.body.clone(),
-                });
+            Node::Not(inner) => {
+                let v = self.eval_value(inner)?;
+                Ok(Value::Flag(!flag_of("`!`", &v)?))
+            }
 
-                self.env.define(func.name.clone(), value.clone());
-                value
+            Node::Add(..)
+            | Node::Sub(..)
+            | Node::Mul(..)
+            | Node::Div(..)
+            | Node::Mod(..)
+            | Node::Eq(..)
+            | Node::Ne(..)
+            | Node::Lt(..)
+            | Node::Le(..)
+            | Node::Gt(..)
+            | Node::Ge(..)
+            | Node::And(..)
+            | Node::Or(..)
+            | Node::Has(..)
+            | Node::Coalesce(..) => self.eval_chain(node),
+
+            Node::Func(func) => Ok(self.define_func(func)),
+
+            Node::Call(call) => self.eval_call(call),
+
+            Node::BlockExpr(block) => self.eval_block_expr(block),
+
+            // Blocks only establish scope and never yield a value, so a
+            // block reached here runs as a statement.
+            Node::Block(_) => {
+                self.eval_node_ctrl(node)?;
+                Ok(Value::Void)
             }
 
-            Node::Block(block) => {
-                self.env.push_scope();
+            _ => Ok(Value::Void),
+        }
+    }
 
-                let mut last = Value::Void;
+    /// Error for reading an unbound name, with a "did you mean" hint
+    /// when a visible name is close enough.
+    fn unknown_binding(&self, name: &str) -> Unwind {
+        let mut diagnostic = Diagnostic::error(
+            format!("unknown binding `{name}`"),
+            Span { start: 0, end: 0 },
+        );
 
-                for segment in &block.segments {
-                    for n in &segment.nodes {
-                        last = self.eval_value(n);
-                    }
-                }
+        if let Some(best) = closest_name(name, self.env.visible_names()) {
+            diagnostic = diagnostic.with_note(Note::help(format!("did you mean `{best}`?"), None));
+        }
 
-                self.env.pop_scope();
-                last
+        Unwind::Unplaced(diagnostic)
+    }
+
+    /// Evaluate an infix operator and the chain of infix operators down
+    /// its left side.
+    ///
+    /// A flat chain such as `1 + 2 + 3` nests one level per operator on
+    /// the left, with no limit from the parser, so the chain is walked
+    /// in a loop rather than by recursion. Each operator's error is
+    /// placed at that operator's own expression.
+    fn eval_chain(&mut self, node: &Node) -> Result<Value, Unwind> {
+        let mut chain = vec![node];
+        let mut leaf = node;
+        while let Some((l, _)) = evaluated_operands(leaf) {
+            chain.push(l);
+            leaf = l;
+        }
+        chain.pop();
+
+        let mut value = self.eval_value(leaf)?;
+        for &op in chain.iter().rev() {
+            value = self.eval_infix(op, value).map_err(|unwind| unwind.placed_at(op))?;
+        }
+        Ok(value)
+    }
+
+    /// Apply an infix operator to its already evaluated left operand.
+    ///
+    /// `&&`, `||` and `??` short-circuit: the right side is only
+    /// evaluated when the left does not decide the result.
+    fn eval_infix(&mut self, node: &Node, lhs: Value) -> Result<Value, Unwind> {
+        match node {
+            Node::Add(_, r) => arith(ArithOp::Add, lhs, self.eval_value(r)?),
+            Node::Sub(_, r) => arith(ArithOp::Sub, lhs, self.eval_value(r)?),
+            Node::Mul(_, r) => arith(ArithOp::Mul, lhs, self.eval_value(r)?),
+            Node::Div(_, r) => arith(ArithOp::Div, lhs, self.eval_value(r)?),
+            Node::Mod(_, r) => arith(ArithOp::Mod, lhs, self.eval_value(r)?),
+
+            Node::Eq(_, r) => Ok(Value::Flag(lhs.equals(&self.eval_value(r)?))),
+            Node::Ne(_, r) => Ok(Value::Flag(!lhs.equals(&self.eval_value(r)?))),
+
+            Node::Lt(_, r) => compare(lhs, self.eval_value(r)?, Ordering::is_lt),
+            Node::Le(_, r) => compare(lhs, self.eval_value(r)?, Ordering::is_le),
+            Node::Gt(_, r) => compare(lhs, self.eval_value(r)?, Ordering::is_gt),
+            Node::Ge(_, r) => compare(lhs, self.eval_value(r)?, Ordering::is_ge),
+
+            Node::And(_, r) => {
+                if !flag_of("`&&`", &lhs)? {
+                    return Ok(Value::Flag(false));
+                }
+                let rhs = self.eval_value(r)?;
+                Ok(Value::Flag(flag_of("`&&`", &rhs)?))
             }
+            Node::Or(_, r) => {
+                if flag_of("`||`", &lhs)? {
+                    return Ok(Value::Flag(true));
+                }
+                let rhs = self.eval_value(r)?;
+                Ok(Value::Flag(flag_of("`||`", &rhs)?))
+            }
+
+            Node::Has(_, r) => has(lhs, self.eval_value(r)?),
 
-            _ => Value::Void,
+            Node::Coalesce(_, r) => match lhs {
+                Value::Void | Value::Emp => self.eval_value(r),
+                present => Ok(present),
+            },
+
+            _ => unreachable!("not an evaluated infix operator"),
         }
     }
 
+    /// Evaluate a block expression in its own scope.
+    ///
+    /// Each nesting level pushes and pops exactly one scope, including
+    /// when a `ret` unwinds through it.
+    fn eval_block_expr(&mut self, block: &BlockExpr) -> Result<Value, Unwind> {
+        self.env.push_scope();
+
+        let result = (|| {
+            for n in &block.nodes {
+                self.eval_node_ctrl(n)?;
+            }
+
+            match &block.value {
+                Some(value) => self.eval_value(value),
+                None => Ok(Value::Void),
+            }
+        })();
+
+        self.env.pop_scope();
+        result
+    }
+
+    /// Define a function in the current scope, closing over the scope
+    /// chain it is defined in.
+    fn define_func(&mut self, func: &ast::Func) -> Value {
+        let value = Value::Func(Func {
+            name: func.name.clone(),
+            params: func.params.clone(),
+            body: func.body.clone(),
+            captured: self.env.capture(),
+        });
+
+        self.env.define(&func.name, value.clone());
+        value
+    }
 
-    pub fn eval_node(&mut self, node: &Node) {
-        match self.eval_node_ctrl(node) {
-            Control::Continue => {}
-            Control::Return(_) => {
-                panic!("return executed outside of a function");
+    /// Invoke a function value.
+    ///
+    /// Arguments are evaluated in the caller's scope. The call then runs
+    /// in a fresh scope on top of the function's captured scopes, so the
+    /// body sees its definition site rather than the caller's locals.
+    /// Parameters are bound (missing arguments take their default, or
+    /// `void`), and the body runs until a `ret` unwinds out of it. A
+    /// runtime error leaving the call is noted with the function's name
+    /// and the call site.
+    fn eval_call(&mut self, call: &Call) -> Result<Value, Unwind> {
+        if let Node::Ident(ident) = call.callee.as_ref()
+            && self.env.lookup(&ident.name).is_none()
+            && let Some(builtin) = Builtin::from_name(&ident.name)
+        {
+            let mut args = Vec::with_capacity(call.args.len());
+            for arg in &call.args {
+                args.push(self.eval_value(arg)?);
             }
+            return builtin.call(args);
+        }
+
+        let callee = self.eval_value(&call.callee)?;
+
+        let func = match callee {
+            Value::Func(func) => func,
+            other => return Err(type_error("call target", "func", &other)),
+        };
+
+        let mut args = Vec::with_capacity(call.args.len());
+        for arg in &call.args {
+            args.push(self.eval_value(arg)?);
+        }
+
+        let caller = self.env.enter_call(&func.captured);
+
+        let result = (|| {
+            let mut args = args.into_iter();
+
+            for param in &func.params {
+                let value = match (args.next(), &param.default) {
+                    (Some(v), _) => v,
+                    (None, Some(default)) => self.eval_value(default)?,
+                    (None, None) => Value::Void,
+                };
+                self.env.define(&param.name, value);
+            }
+
+            for n in &func.body {
+                self.eval_node_ctrl(n)?;
+            }
+
+            Ok(Value::Void)
+        })();
+
+        self.env.leave_call(caller);
+
+        // Each call an error unwinds through adds a frame, so the notes
+        // read like a backtrace, innermost call first.
+        match result {
+            Ok(v) | Err(Unwind::Return(v)) => Ok(v),
+            Err(err) => Err(err.with_note(Note::note(
+                format!("in call to `{}`", func.name),
+                Some(*call.span),
+            ))),
         }
     }
 
 
-    fn eval_node_ctrl(&mut self, node: &Node) -> Control {
+    pub fn eval_node(&mut self, node: &Node) -> Result<(), Diagnostic> {
+        self.eval_node_ctrl(node).map_err(top_level)
+    }
+
+    /// Evaluate one top-level node, returning its value if it is an
+    /// expression statement and `None` for every other statement.
+    pub fn eval_statement(&mut self, node: &Node) -> Result<Option<Value>, Diagnostic> {
+        match node {
+            Node::Define(_)
+            | Node::DefineEmpty(_)
+            | Node::Copy(_)
+            | Node::Bind(_)
+            | Node::Guard(_)
+            | Node::Ret(_)
+            | Node::Local(_)
+            | Node::Block(_)
+            | Node::Func(_) => self.eval_node(node).map(|()| None),
+
+            expr => self.eval_value(expr).map(Some).map_err(top_level),
+        }
+    }
+
+    /// Evaluate a binding statement (define, empty define, copy, bind or
+    /// guard).
+    ///
+    /// A `loc` binding always lands in the innermost scope (inside a
+    /// block, the current segment), shadowing any outer binding of the
+    /// name. Without `loc`, the nearest existing binding of the name is
+    /// replaced in the scope that holds it, and a new name is defined in
+    /// the innermost non-segment scope, so it stays visible across the
+    /// rest of the block chain.
+    fn eval_binding(&mut self, node: &Node, local: bool) -> Result<(), Unwind> {
         match node {
             Node::Define(def) => {
-                let v = self.eval_value(&def.value);
-                self.env.define(def.name.clone(), v);
-                Control::Continue
+                let v = self.eval_value(&def.value)?;
+
+                if let Some(ty) = def.ty
+                    && v.type_name() != ty.name()
+                {
+                    return Err(type_error(
+                        &format!("definition of `{}`", def.name),
+                        ty.name(),
+                        &v,
+                    ));
+                }
+
+                let scope = self.binding_scope(&def.name, local);
+                self.env.define_in(scope, &def.name, v);
+                Ok(())
             }
 
             Node::DefineEmpty(def) => {
-                self.env.define(def.name.clone(), Value::Void);
-                Control::Continue
+                let scope = self.binding_scope(&def.name, local);
+                self.env.define_in(scope, &def.name, Value::Void);
+                Ok(())
             }
 
             Node::Copy(copy) => {
+                let scope = self.binding_scope(&copy.name, local);
                 self.env
-                    .copy(copy.name.clone(), &copy.target)
-                    .expect("copy target must exist");
-                Control::Continue
+                    .copy_in(scope, &copy.name, &copy.target)
+                    .map_err(|err| env_error("copy from", err))
             }
 
             Node::Bind(bind) => {
                 let v = self
                     .env
                     .get_value(&bind.target)
-                    .expect("bind target must exist");
-                self.env.define(bind.name.clone(), v);
-                Control::Continue
+                    .ok_or_else(|| env_error("bind to", EnvError::Undefined(bind.target.clone())))?;
+                let scope = self.binding_scope(&bind.name, local);
+                self.env.define_in(scope, &bind.name, v);
+                Ok(())
             }
 
             Node::Guard(guard) => {
+                if self.strict_guard_target && self.env.lookup(&guard.target).is_none() {
+                    return Err(runtime_error(format!(
+                        "guard target `{}` is not declared",
+                        guard.target
+                    )));
+                }
+
                 let mut result = Value::Void;
 
+                // Branches run in order and stop at the first truthy one,
+                // so calls in later branches are never made. Each branch
+                // before it is evaluated in full, side effects included,
+                // since its value is needed to test its truth.
                 for branch in &guard.branches {
-                    let v = self.eval_value(&branch.expr);
-                    if truth_of(&v) == Truth::True {
+                    let v = self.eval_value(&branch.expr)?;
+                    if flag_of("guard branch", &v)? {
                         result = v;
                         break;
                     }
                 }
 
-                self.env.define(guard.target.clone(), result);
-                Control::Continue
+                if self.strict_guard_target {
+                    self.env
+                        .assign(&guard.target, result)
+                        .map_err(|err| env_error("assign to", err))?;
+                } else {
+                    let scope = self.binding_scope(&guard.target, local);
+                    self.env.define_in(scope, &guard.target, result);
+                }
+                Ok(())
             }
 
+            other => self.eval_node_ctrl(other),
+        }
+    }
+
+    fn binding_scope(&self, name: &str, local: bool) -> usize {
+        if local {
+            self.env.innermost()
+        } else {
+            self.env.scope_of(name)
+        }
+    }
+
+    fn eval_node_ctrl(&mut self, node: &Node) -> Result<(), Unwind> {
+        self.eval_node_unplaced(node).map_err(|unwind| unwind.placed_at(node))
+    }
+
+    fn eval_node_unplaced(&mut self, node: &Node) -> Result<(), Unwind> {
+        match node {
+            Node::Define(_)
+            | Node::DefineEmpty(_)
+            | Node::Copy(_)
+            | Node::Bind(_)
+            | Node::Guard(_) => self.eval_binding(node, false),
+
+            Node::Local(inner) => self.eval_binding(inner, true),
+
             Node::Ret(ret) => {
                 let v = match &ret.value {
-                    Some(node) => self.eval_value(node),
+                    Some(node) => self.eval_value(node)?,
                     None => Value::Void,
                 };
-                Control::Return(v)
+                Err(Unwind::Return(v))
             }
 
             Node::Block(block) => {
                 self.env.push_scope();
 
+                let mut result = Ok(());
+
+                // Each segment gets its own scope for `loc` bindings.
                 for segment in &block.segments {
+                    self.env.push_segment_scope();
                     for n in &segment.nodes {
-                        let ctl = self.eval_node_ctrl(n);
-                        if let Control::Return(v) = ctl {
-                            self.env.pop_scope();
-                            return Control::Return(v);
+                        result = self.eval_node_ctrl(n);
+                        if result.is_err() {
+                            break;
                         }
                     }
+                    self.env.pop_scope();
+
+                    if result.is_err() {
+                        break;
+                    }
                 }
 
                 self.env.pop_scope();
-                Control::Continue
+                result
             }
 
             Node::Func(func) => {
-                let value = Value::Func(crate::compiler::semantics::value::Func {
-                    name: func.name.clone(),
-                    params: func.params.clone(),
-                    body: func.body.clone(),
-                });
-
-                self.env.define(func.name.clone(), value.clone());
-                Control::Continue
+                self.define_func(func);
+                Ok(())
             }
 
             // literals, identifiers, calls, etc.
            other => {
-                let _ = self.eval_value(other);
-                Control::Continue
+                self.eval_value(other)?;
+                Ok(())
             }
 
         }
 
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+/// Numeric arithmetic.
+///
+/// Both operands must be numeric. The result is `dec` when either operand
+/// is `dec` (the `num` side is promoted), otherwise `num`. Overflow and
+/// division by zero are runtime errors.
+///
+/// Division and modulo truncate toward zero, for `num` and `dec` alike:
+/// `-7 / 2` is `-3`, and the result of `%` takes the sign of the left
+/// operand, so `-7 % 3` is `-1` and `7 % -3` is `1`. `a / b * b + a % b`
+/// is always `a`.
+fn arith(op: ArithOp, lhs: Value, rhs: Value) -> Result<Value, Unwind> {
+    let (a, b) = match (Numeric::from_value(&lhs), Numeric::from_value(&rhs)) {
+        (Some(a), Some(b)) => (a, b),
+        (None, _) => return Err(type_error("arithmetic", "num or dec", &lhs)),
+        (_, None) => return Err(type_error("arithmetic", "num or dec", &rhs)),
+    };
+
+    match (a, b) {
+        (Numeric::Num(a), Numeric::Num(b)) => num_arith(op, a, b),
+        (a, b) => dec_arith(op, a.as_dec(), b.as_dec()),
+    }
+}
+
+/// [`arith`] for compile-time folding: `None` wherever evaluation
+/// would raise a runtime error, so the error is left for runtime.
+pub(crate) fn const_arith(op: ArithOp, lhs: Value, rhs: Value) -> Option<Value> {
+    arith(op, lhs, rhs).ok()
+}
+
+fn num_arith(op: ArithOp, a: i64, b: i64) -> Result<Value, Unwind> {
+    let result = match op {
+        ArithOp::Add => a.checked_add(b),
+        ArithOp::Sub => a.checked_sub(b),
+        ArithOp::Mul => a.checked_mul(b),
+        ArithOp::Div => {
+            if b == 0 {
+                return Err(runtime_error("division by zero"));
+            }
+            a.checked_div(b)
+        }
+        ArithOp::Mod => {
+            if b == 0 {
+                return Err(runtime_error("modulo by zero"));
+            }
+            a.checked_rem(b)
+        }
+    };
+
+    result
+        .map(Value::Num)
+        .ok_or_else(|| runtime_error("arithmetic overflow"))
+}
+
+fn dec_arith(op: ArithOp, a: f64, b: f64) -> Result<Value, Unwind> {
+    let result = match op {
+        ArithOp::Add => a + b,
+        ArithOp::Sub => a - b,
+        ArithOp::Mul => a * b,
+        ArithOp::Div => {
+            if b == 0.0 {
+                return Err(runtime_error("division by zero"));
+            }
+            a / b
+        }
+        ArithOp::Mod => {
+            if b == 0.0 {
+                return Err(runtime_error("modulo by zero"));
+            }
+            a % b
+        }
+    };
+
+    if !result.is_finite() {
+        return Err(runtime_error("arithmetic overflow"));
+    }
+
+    Ok(Numeric::Dec(result).into_value())
+}
+
+/// Ordering comparison `<`, `<=`, `>`, `>=`.
+///
+/// Both operands must be numeric. Two `num`s compare exactly; otherwise
+/// the `num` side is promoted and the operands compare as `dec`.
+fn compare(lhs: Value, rhs: Value, test: fn(Ordering) -> bool) -> Result<Value, Unwind> {
+    let (a, b) = match (Numeric::from_value(&lhs), Numeric::from_value(&rhs)) {
+        (Some(a), Some(b)) => (a, b),
+        (None, _) => return Err(type_error("comparison", "num or dec", &lhs)),
+        (_, None) => return Err(type_error("comparison", "num or dec", &rhs)),
+    };
+
+    let ordering = match (a, b) {
+        (Numeric::Num(a), Numeric::Num(b)) => Some(a.cmp(&b)),
+        (a, b) => a.as_dec().partial_cmp(&b.as_dec()),
+    };
+
+    Ok(Value::Flag(ordering.is_some_and(test)))
+}
+
+/// The truth of an operand that `context` evaluates as a flag.
+///
+/// Every value but a function has a truth; a function is a runtime type
+/// error rather than a panic in [`truth_of`].
+fn flag_of(context: &str, value: &Value) -> Result<bool, Unwind> {
+    match value {
+        Value::Func(_) => Err(type_error(context, "a value", value)),
+        _ => Ok(truth_of(value) == Truth::True),
+    }
+}
+
+/// Membership test `needle :: haystack`.
+///
+/// For text, membership is substring containment: `"ell" :: "hello"` is
+/// `true`. Any other right-hand side is a runtime error.
+fn has(needle: Value, haystack: Value) -> Result<Value, Unwind> {
+    match (&needle, &haystack) {
+        (Value::Text(needle), Value::Text(haystack)) => {
+            Ok(Value::Flag(haystack.contains(needle.as_str())))
+        }
+        (_, Value::Text(_)) => Err(type_error("`::`", "text", &needle)),
+        _ => Err(type_error("`::`", "text", &haystack)),
+    }
+}
+
+/// Functions provided by the runtime.
+///
+/// A builtin is only reached when its name is not bound in the
+/// environment, so user definitions always shadow it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Builtin {
+    /// `slice(text, index)` returns the character at `index`;
+    /// `slice(text, start, end)` returns the characters in `start..end`.
+    Slice,
+
+    /// `num(v)`, `dec(v)`, `flag(v)` and `text(v)` convert one value to
+    /// the named type. The type keywords cannot be bound, so these are
+    /// never shadowed.
+    Convert(Type),
+}
+
+impl Builtin {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "slice" => Some(Builtin::Slice),
+            "num" => Some(Builtin::Convert(Type::Num)),
+            "dec" => Some(Builtin::Convert(Type::Dec)),
+            "flag" => Some(Builtin::Convert(Type::Flag)),
+            "text" => Some(Builtin::Convert(Type::Text)),
+            _ => None,
+        }
+    }
+
+    fn call(self, args: Vec<Value>) -> Result<Value, Unwind> {
+        match self {
+            Builtin::Slice => slice(args),
+            Builtin::Convert(ty) => match <[Value; 1]>::try_from(args) {
+                Ok([value]) => convert(ty, value),
+                Err(args) => Err(runtime_error(format!(
+                    "`{}` expects 1 argument, found {}",
+                    ty.name(),
+                    args.len()
+                ))),
+            },
+        }
+    }
+}
+
+/// Explicit type conversion.
+///
+/// | target | accepts                                                     |
+/// |--------|-------------------------------------------------------------|
+/// | `num`  | `num`; `dec` truncated toward zero; `flag` as 1/0; numeric `text` |
+/// | `dec`  | `num`; `dec`; `flag` as 1.0/0.0; numeric `text`             |
+/// | `flag` | any value but a function, by its truth                      |
+/// | `text` | `num`, `dec`, `flag` and `text`, as they display            |
+///
+/// Text is trimmed before it is parsed. Anything else, including text
+/// that is not a number and a `dec` outside the `num` range, is a
+/// runtime error.
+fn convert(ty: Type, value: Value) -> Result<Value, Unwind> {
+    let context = format!("`{}` conversion", ty.name());
+    let invalid = |value: &Value| {
+        runtime_error(format!("{context}: cannot convert {} `{value}`", value.type_name()))
+    };
+
+    match (ty, &value) {
+        (Type::Num, Value::Num(_)) | (Type::Dec, Value::Dec(_)) => Ok(value),
+        (Type::Num | Type::Dec, Value::Flag(b)) => {
+            let n = Numeric::Num(i64::from(*b));
+            Ok(if ty == Type::Num { n } else { Numeric::Dec(n.as_dec()) }.into_value())
+        }
+
+        (Type::Num, Value::Dec(_)) => match Numeric::from_value(&value) {
+            // `i64::MAX as f64` rounds up to 2^63, which is already out of range
+            Some(Numeric::Dec(d)) if d.is_finite() && d >= i64::MIN as f64 && d < i64::MAX as f64 => {
+                Ok(Value::Num(d.trunc() as i64))
+            }
+            _ => Err(invalid(&value)),
+        },
+        (Type::Num, Value::Text(t)) => t.trim().parse().map(Value::Num).map_err(|_| invalid(&value)),
+
+        (Type::Dec, Value::Num(n)) => Ok(Numeric::Dec(*n as f64).into_value()),
+        (Type::Dec, Value::Text(t)) => match t.trim().parse::<f64>() {
+            Ok(d) if d.is_finite() => Ok(Numeric::Dec(d).into_value()),
+            _ => Err(invalid(&value)),
+        },
+
+        (Type::Flag, _) => flag_of(&context, &value).map(Value::Flag),
+
+        (Type::Text, Value::Num(_) | Value::Dec(_) | Value::Flag(_) | Value::Text(_)) => {
+            Ok(Value::Text(value.to_string()))
+        }
+
+        (Type::Num | Type::Dec, _) => Err(type_error(&context, "num, dec, flag or text", &value)),
+        (Type::Text, _) => Err(type_error(&context, "num, dec, flag or text", &value)),
+    }
+}
+
+/// Character-based text indexing.
+///
+/// Indices count characters, not bytes, so a multi-byte character is
+/// never split. Any index past the end is a runtime error.
+fn slice(args: Vec<Value>) -> Result<Value, Unwind> {
+    let (text, start, end) = match args.as_slice() {
+        [Value::Text(t), Value::Num(i)] => (t, *i, i.checked_add(1)),
+        [Value::Text(t), Value::Num(a), Value::Num(b)] => (t, *a, Some(*b)),
+        _ => {
+            return Err(runtime_error("slice expects (text, num) or (text, num, num)"));
+        }
+    };
+
+    let len = text.chars().count();
+    let out_of_range = || runtime_error(format!("slice index out of range for text of length {len}"));
+
+    let start = usize::try_from(start).map_err(|_| out_of_range())?;
+    let end = end
+        .and_then(|e| usize::try_from(e).ok())
+        .ok_or_else(out_of_range)?;
+
+    if start > end || end > len || (start == end && args.len() == 2) {
+        return Err(out_of_range());
+    }
+
+    Ok(Value::Text(text.chars().skip(start).take(end - start).collect()))
+}
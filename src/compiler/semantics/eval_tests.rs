@@ -1,10 +1,26 @@
-use crate::compiler::ast::{Guard, GuardBranch, Literal, Node};
+use crate::compiler::ast::{Guard, GuardBranch, Literal, Node, NodeSpan, Param, Program, Ret};
+use crate::compiler::error::{Note, Span};
+use crate::compiler::lexer::Lexer;
+use crate::compiler::parser::Parser;
 use crate::compiler::semantics::eval::Evaluator;
 use crate::compiler::semantics::value::Value;
 
+fn parse_program(src: &str) -> Program {
+    let tokens = Lexer::new(src).tokenize().expect("lexing failed");
+    let mut parser = Parser::new(&tokens);
+    parser.parse_program().expect("failed to parse program")
+}
+
+fn eval_source(src: &str) -> Evaluator {
+    let mut ev = Evaluator::new();
+    ev.eval_program(&parse_program(src)).expect("evaluation failed");
+    ev
+}
+
 fn branch(v: Literal) -> GuardBranch {
     GuardBranch {
         expr: Node::Lit(v),
+        span: NodeSpan::default(),
     }
 }
 
@@ -17,10 +33,11 @@ fn guard_assigns_first_truthy_branch() {
             branch(Literal::Num(1)),
             branch(Literal::Num(2)),
         ],
+        span: NodeSpan::default(),
     });
 
     let mut ev = Evaluator::new();
-    ev.eval_node(&node);
+    ev.eval_node(&node).unwrap();
 
     match ev.get("x") {
         Some(Value::Num(n)) => assert_eq!(n, 1),
@@ -38,10 +55,11 @@ fn guard_skips_false_values_until_true() {
             branch(Literal::Text("".into())),
             branch(Literal::Text("ok".into())),
         ],
+        span: NodeSpan::default(),
     });
 
     let mut ev = Evaluator::new();
-    ev.eval_node(&node);
+    ev.eval_node(&node).unwrap();
 
     match ev.get("x") {
         Some(Value::Text(s)) => assert_eq!(s, "ok"),
@@ -58,10 +76,11 @@ fn guard_assigns_void_if_all_branches_false() {
             branch(Literal::Num(0)),
             branch(Literal::Text("".into())),
         ],
+        span: NodeSpan::default(),
     });
 
     let mut ev = Evaluator::new();
-    ev.eval_node(&node);
+    ev.eval_node(&node).unwrap();
 
     match ev.get("x") {
         Some(Value::Void) => {}
@@ -74,10 +93,11 @@ fn guard_single_branch_true() {
     let node = Node::Guard(Guard {
         target: "x".into(),
         branches: vec![branch(Literal::Num(5))],
+        span: NodeSpan::default(),
     });
 
     let mut ev = Evaluator::new();
-    ev.eval_node(&node);
+    ev.eval_node(&node).unwrap();
 
     match ev.get("x") {
         Some(Value::Num(n)) => assert_eq!(n, 5),
@@ -90,13 +110,678 @@ fn guard_single_branch_false_becomes_void() {
     let node = Node::Guard(Guard {
         target: "x".into(),
         branches: vec![branch(Literal::Num(0))],
+        span: NodeSpan::default(),
     });
 
     let mut ev = Evaluator::new();
-    ev.eval_node(&node);
+    ev.eval_node(&node).unwrap();
 
     match ev.get("x") {
         Some(Value::Void) => {}
         other => panic!("expected x = Void, got {:?}", other),
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn nested_block_expression_returns_innermost_value() {
+    let ev = eval_source(
+        "fn f :(x)( ret :[ :[ x + 1 ]: ]:; ):
+         y = f(41);",
+    );
+
+    assert_eq!(ev.get("y"), Some(Value::Num(42)));
+}
+
+#[test]
+fn ret_inside_nested_block_expression_returns_from_function() {
+    let ev = eval_source(
+        "fn f :(x)( y = :[ :[ ret x * 2; ]: ]:; ret 0; ):
+         r = f(7);",
+    );
+
+    assert_eq!(ev.get("r"), Some(Value::Num(14)));
+    assert_eq!(ev.get("y"), None);
+}
+
+#[test]
+fn ret_skips_the_rest_of_the_function_body() {
+    let ev = eval_source(
+        "hits = 0;
+         fn f :()( ret 1; hits = 1; ):
+         r = f();",
+    );
+
+    assert_eq!(ev.get("r"), Some(Value::Num(1)));
+    assert_eq!(ev.get("hits"), Some(Value::Num(0)));
+}
+
+#[test]
+fn function_body_without_ret_falls_through_to_void() {
+    let ev = eval_source(
+        "hits = 0;
+         fn f :()( hits = 1; ):
+         r = f();",
+    );
+
+    assert_eq!(ev.get("r"), Some(Value::Void));
+    assert_eq!(ev.get("hits"), Some(Value::Num(1)));
+}
+
+#[test]
+fn block_expression_bindings_do_not_leak() {
+    let ev = eval_source("x = :[ inner = 2; :[ deeper = 3; 1 + 1 ]: ]:;");
+
+    assert_eq!(ev.get("x"), Some(Value::Num(2)));
+    assert_eq!(ev.get("inner"), None);
+    assert_eq!(ev.get("deeper"), None);
+}
+
+#[test]
+fn strict_names_reject_undefined_read() {
+    let program = parse_program("x = :[ y ]:;");
+
+    let mut ev = Evaluator::new().with_strict_names(true);
+    let err = ev.eval_program(&program).expect_err("expected unknown binding error");
+
+    assert_eq!(err.message, "unknown binding `y`");
+    assert_eq!(err.span, Span { start: 7, end: 8 });
+}
+
+#[test]
+fn runtime_errors_point_at_the_innermost_positioned_node() {
+    let failing_text = |src: &str| {
+        let err = Evaluator::new().eval_program(&parse_program(src)).unwrap_err();
+        src[err.span.start..err.span.end].to_string()
+    };
+
+    // literals record no position, so the statement is the closest
+    assert_eq!(failing_text("x = 1 / 0;"), "x = 1 / 0;");
+    assert_eq!(failing_text("a = 1; b = 0; x = a / b + 1;"), "a / b");
+    assert_eq!(failing_text("s = \"日本\"; c = slice(s, 2);"), "slice(s, 2)");
+    assert_eq!(failing_text("num n = \"1\";"), "num n = \"1\";");
+    assert_eq!(failing_text("fn f :()( ret 1 + \"t\"; ): x = f();"), "ret 1 + \"t\";");
+}
+
+#[test]
+fn lenient_names_read_undefined_as_void() {
+    let ev = eval_source("x = :[ y ]:;");
+
+    assert_eq!(ev.get("x"), Some(Value::Void));
+}
+
+#[test]
+fn slice_index_returns_single_character() {
+    let ev = eval_source("s = \"héllo\"; c = slice(s, 1);");
+
+    assert_eq!(ev.get("c"), Some(Value::Text("é".into())));
+}
+
+#[test]
+fn slice_range_returns_substring() {
+    let ev = eval_source("s = \"héllo\"; sub = slice(s, 1, 4);");
+
+    assert_eq!(ev.get("sub"), Some(Value::Text("éll".into())));
+}
+
+#[test]
+fn slice_out_of_range_is_runtime_error() {
+    let mut ev = Evaluator::new();
+    let err = ev
+        .eval_program(&parse_program("s = \"日本\"; c = slice(s, 2);"))
+        .unwrap_err();
+
+    assert_eq!(err.message, "slice index out of range for text of length 2");
+}
+
+#[test]
+fn mixed_num_and_dec_addition_produces_dec() {
+    let ev = eval_source("x = 2 + 3.5;");
+
+    assert_eq!(ev.get("x"), Some(Value::Dec("5.5".into())));
+}
+
+#[test]
+fn num_times_whole_dec_stays_dec() {
+    let ev = eval_source("x = 2 * 2.0;");
+
+    assert_eq!(ev.get("x"), Some(Value::Dec("4.0".into())));
+}
+
+#[test]
+fn integer_addition_stays_num() {
+    let ev = eval_source("x = 2 + 3;");
+
+    assert_eq!(ev.get("x"), Some(Value::Num(5)));
+}
+
+#[test]
+fn long_flat_chains_evaluate() {
+    let ev = eval_source(&format!("x = {}1;", "1 + ".repeat(999)));
+    assert_eq!(ev.get("x"), Some(Value::Num(1000)));
+
+    let ev = eval_source(&format!("x = {}true;", "false || ".repeat(999)));
+    assert_eq!(ev.get("x"), Some(Value::Flag(true)));
+}
+
+#[test]
+fn dec_arithmetic_rounds_to_the_nearest_f64() {
+    let ev = eval_source("x = 0.1 + 0.2; y = 0.5 + 0.25;");
+
+    assert_eq!(ev.get("x"), Some(Value::Dec("0.30000000000000004".into())));
+    assert_eq!(ev.get("y"), Some(Value::Dec("0.75".into())));
+}
+
+#[test]
+fn num_and_dec_compare_as_f64() {
+    let ev = eval_source(
+        "a = 9007199254740993 == 9007199254740992.0;
+         b = 9007199254740993 == 9007199254740992;",
+    );
+
+    // promoted to f64, the two sides round to the same double
+    assert_eq!(ev.get("a"), Some(Value::Flag(true)));
+    // two nums still compare exactly
+    assert_eq!(ev.get("b"), Some(Value::Flag(false)));
+}
+
+#[test]
+fn unknown_name_suggests_closest_binding() {
+    let mut ev = Evaluator::new().with_strict_names(true);
+    let err = ev
+        .eval_program(&parse_program("price = 10; qty = 2; total = prce + 1;"))
+        .unwrap_err();
+
+    assert_eq!(err.message, "unknown binding `prce`");
+    assert_eq!(err.notes, vec![Note::help("did you mean `price`?", None)]);
+}
+
+#[test]
+fn unrelated_unknown_name_gets_no_suggestion() {
+    let mut ev = Evaluator::new().with_strict_names(true);
+    let err = ev
+        .eval_program(&parse_program("price = 10; total = zzz + 1;"))
+        .unwrap_err();
+
+    assert!(err.notes.is_empty());
+}
+
+#[test]
+fn has_finds_substring_in_text() {
+    let ev = eval_source("x = \"ell\" :: \"hello\";");
+
+    assert_eq!(ev.get("x"), Some(Value::Flag(true)));
+}
+
+#[test]
+fn has_reports_missing_substring() {
+    let ev = eval_source("x = \"z\" :: \"hello\";");
+
+    assert_eq!(ev.get("x"), Some(Value::Flag(false)));
+}
+
+#[test]
+fn has_rejects_non_text_right_hand_side() {
+    let mut ev = Evaluator::new();
+    let err = ev
+        .eval_program(&parse_program("x = \"1\" :: 12;"))
+        .unwrap_err();
+
+    assert_eq!(err.message, "`::`: expected text, found num");
+}
+
+#[test]
+fn arithmetic_type_error_names_offending_type() {
+    let mut ev = Evaluator::new();
+    let err = ev
+        .eval_program(&parse_program("x = 1 + \"a\";"))
+        .unwrap_err();
+
+    assert_eq!(err.message, "arithmetic: expected num or dec, found text");
+}
+
+#[test]
+fn copies_share_a_slot_until_redefined() {
+    let mut ev = Evaluator::new().with_strict_guard_target(true);
+    ev.eval_program(&parse_program("a = 1; b := a; c := b; a ?= 2;"))
+        .expect("evaluation failed");
+
+    assert_eq!(ev.get("b"), Some(Value::Num(2)));
+    assert_eq!(ev.get("c"), Some(Value::Num(2)));
+
+    // a definition replaces the slot, so the copies keep the old value
+    ev.eval_program(&parse_program("a = 3;")).expect("evaluation failed");
+    assert_eq!(ev.get("a"), Some(Value::Num(3)));
+    assert_eq!(ev.get("b"), Some(Value::Num(2)));
+}
+
+#[test]
+fn self_copy_is_a_no_op() {
+    let mut ev = Evaluator::new().with_strict_guard_target(true);
+    ev.eval_program(&parse_program("a = 1; b := a; a := a;"))
+        .expect("evaluation failed");
+    assert_eq!(ev.get("a"), Some(Value::Num(1)));
+
+    // still the slot `b` shares
+    ev.eval_program(&parse_program("a ?= 2;")).expect("evaluation failed");
+    assert_eq!(ev.get("b"), Some(Value::Num(2)));
+}
+
+#[test]
+fn strict_guard_target_rejects_undeclared_target() {
+    let mut ev = Evaluator::new().with_strict_guard_target(true);
+    let err = ev
+        .eval_program(&parse_program("y = 1; x ?= y;"))
+        .unwrap_err();
+
+    assert_eq!(err.message, "guard target `x` is not declared");
+}
+
+#[test]
+fn runtime_error_notes_each_active_call() {
+    let src = "fn g :()( ret 9223372036854775807 + 1; ): fn f :()( ret g(); ): x = f();";
+    let call_site = |call: &str| {
+        let start = src.find(call).unwrap();
+        Some(Span { start, end: start + call.len() })
+    };
+
+    let mut ev = Evaluator::new();
+    let err = ev.eval_program(&parse_program(src)).unwrap_err();
+
+    assert_eq!(
+        err.notes,
+        vec![
+            Note::note("in call to `g`", call_site("g()")),
+            Note::note("in call to `f`", call_site("f()")),
+        ]
+    );
+}
+
+#[test]
+fn copy_and_bind_from_undefined_name_report_the_name() {
+    let mut ev = Evaluator::new();
+    let err = ev.eval_program(&parse_program("a := b;")).unwrap_err();
+    assert_eq!(err.message, "cannot copy from undefined `b`");
+
+    let err = ev.eval_program(&parse_program("a :> c;")).unwrap_err();
+    assert_eq!(err.message, "cannot bind to undefined `c`");
+}
+
+#[test]
+fn strict_guard_target_assigns_declared_target() {
+    let mut ev = Evaluator::new().with_strict_guard_target(true);
+    ev.eval_program(&parse_program("x =; y := x; x ?= 0 : 7;"))
+        .expect("evaluation failed");
+
+    assert_eq!(ev.get("x"), Some(Value::Num(7)));
+    // assignment writes through the existing slot
+    assert_eq!(ev.get("y"), Some(Value::Num(7)));
+}
+
+#[test]
+fn negative_and_expression_defaults_apply_when_arguments_are_missing() {
+    let ev = eval_source("fn f :(x = -1, y = 2 + 3)( ret x * y; ): a = f(); b = f(2);");
+
+    assert_eq!(ev.get("a"), Some(Value::Num(-5)));
+    assert_eq!(ev.get("b"), Some(Value::Num(10)));
+}
+
+#[test]
+fn coalesce_replaces_void() {
+    let ev = eval_source("x = void ?? 5;");
+
+    assert_eq!(ev.get("x"), Some(Value::Num(5)));
+}
+
+#[test]
+fn coalesce_keeps_present_zero() {
+    let ev = eval_source("x = 0 ?? 5;");
+
+    assert_eq!(ev.get("x"), Some(Value::Num(0)));
+}
+
+#[test]
+fn coalesce_replaces_emp() {
+    let ev = eval_source("x = emp ?? \"x\";");
+
+    assert_eq!(ev.get("x"), Some(Value::Text("x".into())));
+}
+
+#[test]
+fn annotated_define_accepts_matching_type() {
+    let ev = eval_source("text s = \"hi\";");
+
+    assert_eq!(ev.get("s"), Some(Value::Text("hi".into())));
+}
+
+#[test]
+fn annotated_define_rejects_other_types() {
+    let mut ev = Evaluator::new();
+    let err = ev
+        .eval_program(&parse_program("num s = \"hi\";"))
+        .unwrap_err();
+
+    assert_eq!(err.message, "definition of `s`: expected num, found text");
+}
+
+#[test]
+fn flag_annotation_accepts_computed_flags() {
+    let ev = eval_source("n = 3; flag small = n < 5; flag both = small && !(n == 4);");
+
+    assert_eq!(ev.get("small"), Some(Value::Flag(true)));
+    assert_eq!(ev.get("both"), Some(Value::Flag(true)));
+}
+
+#[test]
+fn loc_define_does_not_affect_outer_binding() {
+    let ev = eval_source("x = 0; :{ loc x = 1; y = x + 1; }:");
+
+    assert_eq!(ev.get("x"), Some(Value::Num(0)));
+}
+
+#[test]
+fn plain_define_updates_outer_binding() {
+    let ev = eval_source("x = 0; :{ x = 1; }:");
+
+    assert_eq!(ev.get("x"), Some(Value::Num(1)));
+}
+
+#[test]
+fn new_names_stay_in_their_block() {
+    let ev = eval_source(":{ z = 1; loc w = 2; }:");
+
+    assert_eq!(ev.get("z"), None);
+    assert_eq!(ev.get("w"), None);
+}
+
+#[test]
+fn block_statement_runs_without_yielding_a_value() {
+    let mut ev = Evaluator::new();
+    let program = parse_program("x = 0; fn f :()( ret 3; ): :{ y = f(); x = y + 1; }:");
+
+    assert_eq!(ev.eval_program(&program), Ok(Value::Void));
+    assert_eq!(ev.get("x"), Some(Value::Num(4)));
+    assert_eq!(ev.get("y"), None);
+}
+
+#[test]
+fn a_function_escaping_its_block_still_sees_the_block() {
+    let ev = eval_source("g = 0; :{ n = 5; fn inner :()( ret n; ): g := inner; }: r = g();");
+
+    assert_eq!(ev.get("n"), None);
+    assert_eq!(ev.get("r"), Some(Value::Num(5)));
+}
+
+#[test]
+fn block_statement_has_no_value_in_a_statement_list() {
+    let block = parse_program(":{ a = 1; }{ b = a + 1; }:").nodes.remove(0);
+    let mut ev = Evaluator::new();
+
+    assert_eq!(ev.eval_statement(&block), Ok(None));
+    assert_eq!(ev.get("a"), None);
+    assert_eq!(ev.get("b"), None);
+}
+
+#[test]
+fn loc_shadow_is_read_inside_its_block() {
+    let ev = eval_source("x = 0; :{ loc x = 5; x = x + 1; }:");
+
+    // the unqualified update finds the innermost (local) `x`
+    assert_eq!(ev.get("x"), Some(Value::Num(0)));
+}
+
+#[test]
+fn loc_binding_is_confined_to_its_segment() {
+    let ev = eval_source("r =; s =; :{ loc a = 1; b = 2; }{ r = b + 1; s = a ?? 9; }:");
+
+    assert_eq!(ev.get("r"), Some(Value::Num(3)));
+    assert_eq!(ev.get("s"), Some(Value::Num(9)));
+}
+
+#[test]
+fn guard_identifier_branches_read_their_names() {
+    let ev = eval_source("y = 0; z = 2; a ?= z; b ?= y : z; c ?= y;");
+
+    assert_eq!(ev.get("a"), Some(Value::Num(2)));
+    assert_eq!(ev.get("b"), Some(Value::Num(2)));
+    assert_eq!(ev.get("c"), Some(Value::Void));
+}
+
+#[test]
+fn guard_branch_naming_a_function_is_a_type_error() {
+    let err = Evaluator::new()
+        .eval_program(&parse_program("fn f :()( ret 1; ): x ?= f;"))
+        .expect_err("expected type error");
+
+    assert_eq!(err.message, "guard branch: expected a value, found func");
+}
+
+#[test]
+fn guard_branches_call_functions_lazily() {
+    let src = "hits = 0; \
+        fn bump :()( hits = hits + 1; ret 0; ): \
+        fn one :()( ret 1; ):";
+
+    let ev = eval_source(&format!("{src} x ?= one() : bump();"));
+    assert_eq!(ev.get("x"), Some(Value::Num(1)));
+    assert_eq!(ev.get("hits"), Some(Value::Num(0)));
+
+    let ev = eval_source(&format!("{src} x ?= bump() : one() : bump();"));
+    assert_eq!(ev.get("x"), Some(Value::Num(1)));
+    assert_eq!(ev.get("hits"), Some(Value::Num(1)));
+}
+
+#[test]
+fn block_expression_can_be_called() {
+    let ev = eval_source("fn inc :(n)( ret n + 1; ): x = :[ inc ]:(1);");
+
+    assert_eq!(ev.get("x"), Some(Value::Num(2)));
+}
+
+#[test]
+fn equality_operators_follow_value_equality() {
+    let ev = eval_source(
+        "a = void == void; b = emp == emp; c = void == emp; d = void != 0;
+         fn f :()( ret; ): g := f; e = f == g; h = 1 == 1.0;",
+    );
+
+    assert_eq!(ev.get("a"), Some(Value::Flag(true)));
+    assert_eq!(ev.get("b"), Some(Value::Flag(true)));
+    assert_eq!(ev.get("c"), Some(Value::Flag(false)));
+    assert_eq!(ev.get("d"), Some(Value::Flag(true)));
+    assert_eq!(ev.get("e"), Some(Value::Flag(true)));
+    assert_eq!(ev.get("h"), Some(Value::Flag(true)));
+}
+
+#[test]
+fn division_and_modulo_truncate_toward_zero() {
+    let ev = eval_source("a = -7 % 3; b = 7 % -3; c = -7 / 2; d = 7 / -2; e = -7.5 % 2;");
+
+    assert_eq!(ev.get("a"), Some(Value::Num(-1)));
+    assert_eq!(ev.get("b"), Some(Value::Num(1)));
+    assert_eq!(ev.get("c"), Some(Value::Num(-3)));
+    assert_eq!(ev.get("d"), Some(Value::Num(-3)));
+    assert_eq!(ev.get("e"), Some(Value::Dec("-1.5".into())));
+}
+
+#[test]
+fn program_result_is_its_last_call_statement() {
+    let program = parse_program("fn two :()( ret 1 + 1; ): two();");
+
+    assert_eq!(Evaluator::new().eval_program(&program), Ok(Value::Num(2)));
+}
+
+#[test]
+fn program_ending_in_a_binding_results_in_void() {
+    let mut ev = Evaluator::new();
+
+    assert_eq!(ev.eval_program(&parse_program("fn f :()( ret 3; ): f();")), Ok(Value::Num(3)));
+    assert_eq!(ev.eval_program(&parse_program("x = f();")), Ok(Value::Void));
+    assert_eq!(ev.eval_program(&parse_program("")), Ok(Value::Void));
+}
+
+#[test]
+fn guard_skips_branches_after_the_first_truthy_one() {
+    let ev = eval_source(
+        "hits = 0;
+         fn bump :(v)( hits = hits + 1; ret v; ):
+         x ?= bump(0) : bump(5) : bump(6) : bump(7);",
+    );
+
+    assert_eq!(ev.get("x"), Some(Value::Num(5)));
+    // the falsey first branch and the matching second one ran; the rest did not
+    assert_eq!(ev.get("hits"), Some(Value::Num(2)));
+}
+
+#[test]
+fn functions_read_their_definition_site_not_the_call_site() {
+    let ev = eval_source(
+        "x = 1;
+         fn read :()( ret x; ):
+         fn caller :()( loc x = 2; ret read(); ):
+         r = caller();",
+    );
+
+    assert_eq!(ev.get("r"), Some(Value::Num(1)));
+}
+
+#[test]
+fn caller_parameters_do_not_leak_into_callee() {
+    let ev = eval_source(
+        "n = 1;
+         fn add :(m)( ret n + m; ):
+         fn g :(n)( ret add(n); ):
+         r = g(100);",
+    );
+
+    assert_eq!(ev.get("r"), Some(Value::Num(101)));
+}
+
+#[test]
+fn functions_see_later_bindings_of_their_defining_scope() {
+    let ev = eval_source(
+        "fn f :()( ret later; ):
+         later = 3;
+         r = f();",
+    );
+
+    assert_eq!(ev.get("r"), Some(Value::Num(3)));
+}
+
+#[test]
+fn function_values_can_be_passed_and_called() {
+    let ev = eval_source(
+        "fn double :(n)( ret n * 2; ):
+         fn apply :(f, x)( ret f(x); ):
+         r = apply(double, 21);",
+    );
+
+    assert_eq!(ev.get("r"), Some(Value::Num(42)));
+}
+
+#[test]
+fn calling_a_non_function_argument_is_a_type_error() {
+    let src = "fn apply :(f, x)( ret f(x); ): r = apply(3, 4);";
+    let start = src.find("apply(3").unwrap();
+
+    let mut ev = Evaluator::new();
+    let err = ev.eval_program(&parse_program(src)).unwrap_err();
+
+    assert_eq!(err.message, "call target: expected func, found num");
+    assert_eq!(
+        err.notes,
+        vec![Note::note(
+            "in call to `apply`",
+            Some(Span { start, end: start + "apply(3, 4)".len() }),
+        )]
+    );
+}
+
+#[test]
+fn function_values_keep_the_parsed_params_and_body() {
+    let ev = eval_source("fn f :(a, b = 2)( ret a + b; ):");
+
+    let Some(Value::Func(func)) = ev.get("f") else {
+        panic!("expected `f` to be a function");
+    };
+
+    assert_eq!(func.name, "f");
+    assert_eq!(
+        func.params,
+        vec![
+            Param { name: "a".into(), default: None },
+            Param { name: "b".into(), default: Some(Node::Lit(Literal::Num(2))) },
+        ]
+    );
+    assert_eq!(
+        func.body,
+        vec![Node::Ret(Ret {
+            value: Some(Box::new(Node::Add(
+                Box::new(Node::Ident("a".into())),
+                Box::new(Node::Ident("b".into())),
+            ))),
+            span: NodeSpan::default(),
+        })]
+    );
+}
+
+#[test]
+fn type_keywords_convert_when_called() {
+    let ev = eval_source(
+        r#"a = num("7"); b = flag(0); c = text(42); d = dec(2); e = num(-7.9); f = dec(" 1.5 ");"#,
+    );
+
+    assert_eq!(ev.get("a"), Some(Value::Num(7)));
+    assert_eq!(ev.get("b"), Some(Value::Flag(false)));
+    assert_eq!(ev.get("c"), Some(Value::Text("42".into())));
+    assert_eq!(ev.get("d"), Some(Value::Dec("2.0".into())));
+    assert_eq!(ev.get("e"), Some(Value::Num(-7)));
+    assert_eq!(ev.get("f"), Some(Value::Dec("1.5".into())));
+}
+
+#[test]
+fn conversions_reject_wrong_arity_and_bad_input() {
+    let eval_err = |src: &str| {
+        Evaluator::new()
+            .eval_program(&parse_program(src))
+            .expect_err("expected conversion error")
+            .message
+    };
+
+    assert_eq!(eval_err("x = num();"), "`num` expects 1 argument, found 0");
+    assert_eq!(eval_err("x = text(1, 2);"), "`text` expects 1 argument, found 2");
+    assert_eq!(eval_err(r#"x = num("seven");"#), "`num` conversion: cannot convert text `seven`");
+    assert_eq!(eval_err("x = num(void);"), "`num` conversion: expected num, dec, flag or text, found void");
+}
+
+#[test]
+fn ordering_operators_compare_numbers() {
+    let ev = eval_source("a = 1 < 2; b = 2 <= 2; c = 3 > 4; d = 2.5 >= 2; n = 5; e = n - 1 < n;");
+
+    assert_eq!(ev.get("a"), Some(Value::Flag(true)));
+    assert_eq!(ev.get("b"), Some(Value::Flag(true)));
+    assert_eq!(ev.get("c"), Some(Value::Flag(false)));
+    assert_eq!(ev.get("d"), Some(Value::Flag(true)));
+    assert_eq!(ev.get("e"), Some(Value::Flag(true)));
+
+    let err = Evaluator::new()
+        .eval_program(&parse_program("x = \"a\" < 1;"))
+        .expect_err("expected type error");
+    assert_eq!(err.message, "comparison: expected num or dec, found text");
+}
+
+#[test]
+fn logical_operators_use_truth_and_short_circuit() {
+    let ev = eval_source(
+        "a = !0; b = 1 && \"\"; c = void || 2; calls = 0;
+         fn bump :()( calls = calls + 1; ret true; ):
+         d = false && bump(); e = true || bump();",
+    );
+
+    assert_eq!(ev.get("a"), Some(Value::Flag(true)));
+    assert_eq!(ev.get("b"), Some(Value::Flag(false)));
+    assert_eq!(ev.get("c"), Some(Value::Flag(true)));
+    assert_eq!(ev.get("d"), Some(Value::Flag(false)));
+    assert_eq!(ev.get("e"), Some(Value::Flag(true)));
+    assert_eq!(ev.get("calls"), Some(Value::Num(0)));
+}
+
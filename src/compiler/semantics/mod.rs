@@ -2,6 +2,9 @@ pub mod value;
 pub mod truth;
 pub mod eval;
 pub mod env;
+pub mod numeric;
+pub mod suggest;
+pub mod symbols;
 
 #[cfg(test)]
 mod semantic_tests;
@@ -0,0 +1,59 @@
+use crate::compiler::semantics::value::Value;
+
+/// Numeric view of a runtime value.
+///
+/// Arithmetic is performed on `Numeric` rather than on `Value` so that
+/// mixed `num`/`dec` operands can be promoted to a common type first.
+///
+/// Promotion rules:
+/// - `num` op `num` → `num`
+/// - `num` op `dec`, `dec` op `num`, `dec` op `dec` → `dec`
+///
+/// Decimals are binary floating point (`f64`), not exact decimals. Each
+/// result is rounded to the nearest `f64` and printed as the shortest
+/// text that reads back to it, so `0.1 + 0.2` is `0.30000000000000004`.
+/// A `num` promoted to `dec` is exact only up to 2^53 in magnitude.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Numeric {
+    Num(i64),
+    Dec(f64),
+}
+
+impl Numeric {
+    /// View a value as a number.
+    ///
+    /// Returns `None` for non-numeric values and for `dec` text that does
+    /// not parse.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Num(n) => Some(Numeric::Num(*n)),
+            Value::Dec(d) => d.parse::<f64>().ok().map(Numeric::Dec),
+            _ => None,
+        }
+    }
+
+    /// Convert back into a runtime value.
+    ///
+    /// Decimals always keep a fractional part, so `4.0` stays `dec`
+    /// rather than reading like a `num`.
+    pub fn into_value(self) -> Value {
+        match self {
+            Numeric::Num(n) => Value::Num(n),
+            Numeric::Dec(d) => {
+                let mut text = d.to_string();
+                if d.is_finite() && !text.contains('.') {
+                    text.push_str(".0");
+                }
+                Value::Dec(text)
+            }
+        }
+    }
+
+    /// The decimal form of this number.
+    pub fn as_dec(self) -> f64 {
+        match self {
+            Numeric::Num(n) => n as f64,
+            Numeric::Dec(d) => d,
+        }
+    }
+}
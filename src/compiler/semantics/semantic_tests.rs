@@ -1,5 +1,27 @@
+use std::rc::Rc;
+
+use crate::compiler::ast::{Literal, Node, Param};
+use crate::compiler::semantics::env::{Captured, Env, EnvError};
+use crate::compiler::semantics::symbols::Symbols;
 use crate::compiler::semantics::truth::{truth_of, Truth};
-use crate::compiler::semantics::value::Value;
+use crate::compiler::semantics::value::{Func, Value};
+
+#[test]
+fn every_literal_kind_has_a_value() {
+    let cases = [
+        (Literal::Num(-3), Value::Num(-3)),
+        (Literal::Dec("2.5".into()), Value::Dec("2.5".into())),
+        (Literal::Flag(true), Value::Flag(true)),
+        (Literal::Flag(false), Value::Flag(false)),
+        (Literal::Text("hi".into()), Value::Text("hi".into())),
+        (Literal::Void, Value::Void),
+        (Literal::Emp, Value::Emp),
+    ];
+
+    for (lit, value) in cases {
+        assert_eq!(Value::from_literal(&lit), value, "literal {lit}");
+    }
+}
 
 #[test]
 fn flag_truth_evaluates_explicitly() {
@@ -35,3 +57,150 @@ fn text_truth_rules() {
     assert_eq!(truth_of(&Value::Text("a".into())), Truth::True);
     assert_eq!(truth_of(&Value::Text("0".into())), Truth::True);
 }
+
+#[test]
+fn type_names_cover_every_value_kind() {
+    let func = Value::Func(Func {
+        name: "f".into(),
+        params: vec![],
+        body: vec![],
+        captured: Captured::default(),
+    });
+
+    assert_eq!(Value::Num(1).type_name(), "num");
+    assert_eq!(Value::Dec("1.5".into()).type_name(), "dec");
+    assert_eq!(Value::Flag(true).type_name(), "flag");
+    assert_eq!(Value::Text("a".into()).type_name(), "text");
+    assert_eq!(Value::Void.type_name(), "void");
+    assert_eq!(func.type_name(), "func");
+}
+
+#[test]
+fn a_function_stored_in_its_own_scope_is_freed_with_the_env() {
+    let mut env = Env::new();
+    let func = Value::Func(Func {
+        name: "f".into(),
+        params: vec![],
+        body: vec![],
+        captured: env.capture(),
+    });
+    env.define("f", func);
+    let slot = Rc::downgrade(&env.lookup("f").unwrap());
+
+    drop(env);
+
+    assert!(slot.upgrade().is_none());
+}
+
+fn func_named(name: &str) -> Value {
+    Value::Func(Func {
+        name: name.into(),
+        params: vec![],
+        body: vec![],
+        captured: Captured::default(),
+    })
+}
+
+#[test]
+fn func_displays_its_signature() {
+    let func = Value::Func(Func {
+        name: "add".into(),
+        params: vec![
+            Param { name: "a".into(), default: None },
+            Param { name: "b".into(), default: Some(Node::Lit(Literal::Num(1))) },
+        ],
+        body: vec![],
+        captured: Captured::default(),
+    });
+
+    assert_eq!(func.to_string(), "fn add(a, b = 1)");
+    assert_eq!(func_named("f").to_string(), "fn f()");
+}
+
+#[test]
+fn equality_table() {
+    assert!(Value::Num(2).equals(&Value::Num(2)));
+    assert!(Value::Num(1).equals(&Value::Dec("1.0".into())));
+    assert!(!Value::Dec("1.5".into()).equals(&Value::Num(1)));
+    assert!(Value::Flag(false).equals(&Value::Flag(false)));
+    assert!(Value::Text("a".into()).equals(&Value::Text("a".into())));
+
+    assert!(Value::Void.equals(&Value::Void));
+    assert!(Value::Emp.equals(&Value::Emp));
+    assert!(!Value::Void.equals(&Value::Emp));
+
+    assert!(func_named("f").equals(&func_named("f")));
+    assert!(!func_named("f").equals(&func_named("g")));
+
+    assert!(!Value::Void.equals(&Value::Num(0)));
+    assert!(!Value::Text("1".into()).equals(&Value::Num(1)));
+    assert!(!Value::Flag(false).equals(&Value::Void));
+}
+
+#[test]
+fn interning_is_stable_and_resolvable() {
+    let mut symbols = Symbols::new();
+
+    let a = symbols.intern("alpha");
+    let b = symbols.intern("beta");
+
+    assert_eq!(symbols.intern("alpha"), a);
+    assert_ne!(a, b);
+    assert_eq!(symbols.resolve(b), "beta");
+    assert_eq!(symbols.get("gamma"), None);
+    assert_eq!(symbols.len(), 2);
+}
+
+#[test]
+fn assigning_or_copying_an_undefined_name_reports_it() {
+    let mut env = Env::new();
+
+    assert_eq!(
+        env.assign("x", Value::Num(1)),
+        Err(EnvError::Undefined("x".into()))
+    );
+    assert_eq!(
+        env.copy("y", "z"),
+        Err(EnvError::Undefined("z".into()))
+    );
+    assert_eq!(EnvError::Undefined("x".into()).to_string(), "undefined name `x`");
+    assert_eq!(env.get_value("y"), None);
+}
+
+#[test]
+fn env_lookups_go_through_interned_keys() {
+    let mut env = Env::new();
+    env.define("x", Value::Num(1));
+
+    env.push_scope();
+    env.define("x", Value::Num(2));
+    env.copy("y", "x").unwrap();
+
+    let x = env.symbols().get("x").expect("x is interned");
+    assert_eq!(env.lookup_symbol(x).map(|s| s.borrow().value.clone()), Some(Value::Num(2)));
+    assert_eq!(env.get_value("y"), Some(Value::Num(2)));
+    assert_eq!(env.symbols().len(), 2);
+
+    env.pop_scope();
+    assert_eq!(env.get_value("x"), Some(Value::Num(1)));
+    assert_eq!(env.get_value("y"), None);
+}
+
+#[test]
+fn visible_names_prefer_innermost_bindings() {
+    let mut env = Env::new();
+    env.define("outer", Value::Num(1));
+    env.define("shared", Value::Num(1));
+    assert_eq!(env.depth(), 1);
+
+    env.push_scope();
+    env.define("shared", Value::Num(2));
+    env.define("inner", Value::Num(2));
+    assert_eq!(env.depth(), 2);
+
+    assert_eq!(env.visible_names(), vec!["inner", "shared", "outer"]);
+
+    env.pop_scope();
+    assert_eq!(env.depth(), 1);
+    assert_eq!(env.visible_names(), vec!["outer", "shared"]);
+}
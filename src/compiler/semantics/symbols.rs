@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::rc::Rc;
+
+/// Interned identifier.
+///
+/// Cheap to copy, hash and compare. Only meaningful together with the
+/// [`Symbols`] table that produced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Raw id, in interning order starting at 0.
+    pub fn id(self) -> u32 {
+        self.0
+    }
+}
+
+/// Map keyed by [`Symbol`].
+///
+/// Symbols are small sequential ids, so they are used as their own hash
+/// instead of going through the default hasher.
+pub type SymbolMap<V> = HashMap<Symbol, V, BuildHasherDefault<SymbolHasher>>;
+
+/// Hasher for [`SymbolMap`]: the hash of a symbol is its id.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SymbolHasher(u64);
+
+impl Hasher for SymbolHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 << 8) | u64::from(byte);
+        }
+    }
+
+    fn write_u32(&mut self, id: u32) {
+        self.0 = u64::from(id);
+    }
+}
+
+/// Hasher for the names in a [`Symbols`] table (FNV-1a).
+///
+/// Every name read goes through the table, and names are short, so a
+/// simple byte hash is much cheaper than the default one.
+#[derive(Debug, Clone, Copy)]
+pub struct NameHasher(u64);
+
+impl Default for NameHasher {
+    fn default() -> Self {
+        NameHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for NameHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Interning table mapping names to [`Symbol`]s and back.
+///
+/// Each distinct name is stored once, however often it is interned.
+#[derive(Debug, Default)]
+pub struct Symbols {
+    ids: HashMap<Rc<str>, Symbol, BuildHasherDefault<NameHasher>>,
+    names: Vec<Rc<str>>,
+}
+
+impl Symbols {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Symbol for `name`, allocating a new id the first time it is seen.
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&symbol) = self.ids.get(name) {
+            return symbol;
+        }
+
+        let symbol = Symbol(u32::try_from(self.names.len()).expect("symbol table overflow"));
+        let name: Rc<str> = Rc::from(name);
+        self.names.push(Rc::clone(&name));
+        self.ids.insert(name, symbol);
+        symbol
+    }
+
+    /// Symbol for `name` if it has been interned.
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.ids.get(name).copied()
+    }
+
+    /// Name of an interned symbol.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.0 as usize]
+    }
+
+    /// Number of distinct names interned.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
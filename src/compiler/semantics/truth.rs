@@ -22,6 +22,7 @@ impl Truth {
 ///
 /// - `flag(true)`  → true
 /// - `void`         → false
+/// - `emp`          → false
 /// - `num(0)`      → false
 /// - `num(!0)`     → true
 /// - `dec(0.0)`    → false
@@ -36,7 +37,7 @@ pub fn truth_of(value: &Value) -> Truth {
             if *b { Truth::True } else { Truth::False }
         }
 
-        Value::Void => Truth::False,
+        Value::Void | Value::Emp => Truth::False,
 
         Value::Num(n) => {
             if *n == 0 { Truth::False } else { Truth::True }
@@ -1,4 +1,8 @@
+use std::fmt;
+
 use crate::compiler::ast::{Literal, Node, Param};
+use crate::compiler::semantics::env::Captured;
+use crate::compiler::semantics::numeric::Numeric;
 
 /// Runtime value representation.
 ///
@@ -12,7 +16,10 @@ pub enum Value {
     /// Integer value.
     Num(i64),
 
-    /// Decimal value (kept as text to preserve precision).
+    /// Decimal value.
+    ///
+    /// Kept as text, so a literal reads back exactly as written, but
+    /// arithmetic and comparison work on `f64`; see [`Numeric`].
     Dec(String),
 
     /// Boolean value.
@@ -28,6 +35,12 @@ pub enum Value {
     /// - always evaluates to false
     Void,
 
+    /// Empty value.
+    ///
+    /// Absent like `void`; both are replaced by the right-hand side
+    /// of `??`.
+    Emp,
+
     /// User-defined function value.
     ///
     /// Represents a callable function introduced by a `fn` block.
@@ -36,8 +49,9 @@ pub enum Value {
     /// - passed as arguments
     /// - invoked via call expressions
     ///
-    /// Function values do not carry execution state.
-    /// A fresh function-local scope is created on each invocation.
+    /// Functions close over the scopes they are defined in (lexical
+    /// scoping). Each invocation creates a fresh function-local scope on
+    /// top of those; the caller's bindings are not visible.
     ///
     /// Return behavior:
     /// - `ret expr;` returns the evaluated expression
@@ -52,6 +66,9 @@ pub struct Func {
     pub name: String,
     pub params: Vec<Param>,
     pub body: Vec<Node>,
+    /// Scopes visible where the function was defined. Calls run on top
+    /// of these, not on top of the caller's scopes.
+    pub captured: Captured,
 }
 
 
@@ -60,6 +77,9 @@ impl Value {
     ///
     /// This performs no evaluation or coercion.
     /// Truth semantics are handled separately.
+    ///
+    /// Every literal kind has a value; the match is deliberately
+    /// exhaustive so a new kind cannot be added without one.
     pub fn from_literal(lit: &Literal) -> Self {
         match lit {
             Literal::Num(n) => Value::Num(*n),
@@ -67,6 +87,87 @@ impl Value {
             Literal::Flag(b) => Value::Flag(*b),
             Literal::Text(t) => Value::Text(t.clone()),
             Literal::Void => Value::Void,
+            Literal::Emp => Value::Emp,
+        }
+    }
+
+    /// Equality as seen by `==` and `!=`.
+    ///
+    /// Comparing values never fails; values that cannot be equal simply
+    /// compare unequal.
+    ///
+    /// | left          | right         | equal when                       |
+    /// |---------------|---------------|----------------------------------|
+    /// | `num`         | `num`         | same integer                     |
+    /// | `num`/`dec`   | `num`/`dec`   | equal as `f64` (`1 == 1.0`)      |
+    /// | `flag`        | `flag`        | same flag                        |
+    /// | `text`        | `text`        | same characters                  |
+    /// | `void`        | `void`        | always                           |
+    /// | `emp`         | `emp`         | always                           |
+    /// | `void`        | `emp`         | never: they are distinct values  |
+    /// | `func`        | `func`        | same function name               |
+    /// | any other mix |               | never (`void == 0` is `false`)   |
+    ///
+    /// Comparing with a `dec` converts both sides to `f64`, so a `num`
+    /// beyond 2^53 equals the nearest decimal:
+    /// `9007199254740993 == 9007199254740992.0` is `true`.
+    ///
+    /// To treat `void` and `emp` alike, test absence with `??` instead.
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Num(a), Value::Num(b)) => a == b,
+            (Value::Num(_) | Value::Dec(_), Value::Num(_) | Value::Dec(_)) => {
+                match (Numeric::from_value(self), Numeric::from_value(other)) {
+                    (Some(a), Some(b)) => a.as_dec() == b.as_dec(),
+                    _ => false,
+                }
+            }
+            (Value::Flag(a), Value::Flag(b)) => a == b,
+            (Value::Text(a), Value::Text(b)) => a == b,
+            (Value::Void, Value::Void) | (Value::Emp, Value::Emp) => true,
+            (Value::Func(a), Value::Func(b)) => a.name == b.name,
+            _ => false,
+        }
+    }
+
+    /// The Druim name of this value's type, as used in diagnostics.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Num(_) => "num",
+            Value::Dec(_) => "dec",
+            Value::Flag(_) => "flag",
+            Value::Text(_) => "text",
+            Value::Void => "void",
+            Value::Emp => "emp",
+            Value::Func(_) => "func",
+        }
+    }
+}
+
+/// Values print as Druim would spell them, except that text is shown
+/// without quotes and a function shows its signature, `fn add(a, b = 1)`.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Num(n) => write!(f, "{n}"),
+            Value::Dec(d) => f.write_str(d),
+            Value::Flag(b) => write!(f, "{b}"),
+            Value::Text(t) => f.write_str(t),
+            Value::Void => f.write_str("void"),
+            Value::Emp => f.write_str("emp"),
+            Value::Func(func) => {
+                write!(f, "fn {}(", func.name)?;
+                for (i, param) in func.params.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    f.write_str(&param.name)?;
+                    if let Some(default) = &param.default {
+                        write!(f, " = {default}")?;
+                    }
+                }
+                f.write_str(")")
+            }
         }
     }
 }
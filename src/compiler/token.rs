@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TokenKind {
     // ===== Identifiers & literals =====
@@ -13,6 +15,11 @@ pub enum TokenKind {
     KwFlag,
     KwText,
     KwVoid,
+    KwEmp,
+
+    // ===== Keywords (literals) =====
+    KwTrue,
+    KwFalse,
 
     // ===== Keywords (expressions) =====
     KwFn,   // fn
@@ -24,7 +31,7 @@ pub enum TokenKind {
 
     // ===== Colon family =====
     Colon,         // :
-    Has,           // ::
+    Has,           // ::  membership only; Druim has no path syntax
     Present,       // :?
     Bind,          // :>
 
@@ -61,6 +68,9 @@ pub enum TokenKind {
     Copy,          // :=
     Guard,         // ?=
 
+    // ===== Coalescing =====
+    Coalesce,      // ??
+
     // ===== Punctuation =====
     LParen,        // (
     RParen,        // )
@@ -81,9 +91,109 @@ pub enum TokenKind {
     Eof,
 }
 
+/// Human-readable name for diagnostics: the backticked source spelling
+/// for fixed tokens, a word for the rest.
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match canonical_lexeme(*self) {
+            Some(lexeme) => write!(f, "`{lexeme}`"),
+            None => f.write_str(match self {
+                TokenKind::Ident => "identifier",
+                TokenKind::NumLit => "number literal",
+                TokenKind::DecLit => "decimal literal",
+                TokenKind::TextLit => "text literal",
+                TokenKind::Eof => "end of input",
+                _ => unreachable!("fixed tokens have a lexeme"),
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Token {
     pub kind: TokenKind,
     pub lexeme: String,
     pub pos: usize, // byte offset in source
+    /// Byte offset one past the token's last source character.
+    ///
+    /// Not always `pos + lexeme.len()`: a text literal's lexeme is its
+    /// decoded value, while `pos..end` covers the quotes and any escapes
+    /// as written.
+    pub end: usize,
+}
+
+/// The fixed source spelling of a token kind.
+///
+/// Returns `None` for kinds whose lexeme varies (identifiers, literals)
+/// and for `Eof`. This is the single source of operator, delimiter and
+/// keyword spellings for both the lexer and the formatter.
+pub fn canonical_lexeme(kind: TokenKind) -> Option<&'static str> {
+    use TokenKind::*;
+
+    Some(match kind {
+        Ident | NumLit | DecLit | TextLit | Eof => return None,
+
+        KwNum => "num",
+        KwDec => "dec",
+        KwFlag => "flag",
+        KwText => "text",
+        KwVoid => "void",
+        KwEmp => "emp",
+
+        KwTrue => "true",
+        KwFalse => "false",
+
+        KwFn => "fn",
+        KwRet => "ret",
+        KwLoc => "loc",
+
+        Colon => ":",
+        Has => "::",
+        Present => ":?",
+        Bind => ":>",
+
+        Add => "+",
+        Sub => "-",
+        Mul => "*",
+        Div => "/",
+        Mod => "%",
+
+        Eq => "==",
+        Ne => "!=",
+        Lt => "<",
+        Le => "<=",
+        Gt => ">",
+        Ge => ">=",
+
+        And => "&&",
+        Or => "||",
+        Not => "!",
+
+        Pipe => "|>",
+        ArrowR => "->",
+        ArrowL => "<-",
+
+        Define => "=",
+        DefineEmpty => "=;",
+
+        Copy => ":=",
+        Guard => "?=",
+
+        Coalesce => "??",
+
+        LParen => "(",
+        RParen => ")",
+        Comma => ",",
+        Semicolon => ";",
+
+        BlockStart => ":{",
+        BlockEnd => "}:",
+        BlockChain => "}{",
+        ArrayStart => ":[",
+        ArrayEnd => "]:",
+        ArrayChain => "][",
+        FuncStart => ":(",
+        FuncEnd => "):",
+        FuncChain => ")(",
+    })
 }
@@ -0,0 +1,245 @@
+use crate::compiler::ast::{Block, BlockExpr, Call, Func, Node, Program};
+
+/// Read-only traversal of a program.
+///
+/// Every method defaults to the matching `walk_*` function, which
+/// recurses into the node's children. Override only the methods for the
+/// nodes you care about, and call the `walk_*` function from the
+/// override to keep descending.
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_node(&mut self, node: &Node) {
+        walk_node(self, node);
+    }
+
+    fn visit_func(&mut self, func: &Func) {
+        walk_func(self, func);
+    }
+
+    fn visit_call(&mut self, call: &Call) {
+        walk_call(self, call);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_block_expr(&mut self, block: &BlockExpr) {
+        walk_block_expr(self, block);
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(v: &mut V, program: &Program) {
+    for node in &program.nodes {
+        v.visit_node(node);
+    }
+}
+
+pub fn walk_node<V: Visitor + ?Sized>(v: &mut V, node: &Node) {
+    match node {
+        Node::Ident(_)
+        | Node::Lit(_)
+        | Node::DefineEmpty(_)
+        | Node::Copy(_)
+        | Node::Bind(_) => {}
+
+        Node::Not(inner) | Node::Neg(inner) | Node::Local(inner) => v.visit_node(inner),
+
+        Node::Add(l, r)
+        | Node::Sub(l, r)
+        | Node::Mul(l, r)
+        | Node::Div(l, r)
+        | Node::Mod(l, r)
+        | Node::Eq(l, r)
+        | Node::Ne(l, r)
+        | Node::Lt(l, r)
+        | Node::Le(l, r)
+        | Node::Gt(l, r)
+        | Node::Ge(l, r)
+        | Node::And(l, r)
+        | Node::Or(l, r)
+        | Node::Has(l, r)
+        | Node::Present(l, r)
+        | Node::Pipe(l, r)
+        | Node::Coalesce(l, r) => {
+            v.visit_node(l);
+            v.visit_node(r);
+        }
+
+        Node::Ret(ret) => {
+            if let Some(value) = &ret.value {
+                v.visit_node(value);
+            }
+        }
+        Node::Define(def) => v.visit_node(&def.value),
+        Node::Guard(guard) => {
+            for branch in &guard.branches {
+                v.visit_node(&branch.expr);
+            }
+        }
+
+        Node::Block(block) => v.visit_block(block),
+        Node::BlockExpr(block) => v.visit_block_expr(block),
+        Node::Func(func) => v.visit_func(func),
+        Node::Call(call) => v.visit_call(call),
+    }
+}
+
+pub fn walk_func<V: Visitor + ?Sized>(v: &mut V, func: &Func) {
+    for param in &func.params {
+        if let Some(default) = &param.default {
+            v.visit_node(default);
+        }
+    }
+    for node in &func.body {
+        v.visit_node(node);
+    }
+}
+
+pub fn walk_call<V: Visitor + ?Sized>(v: &mut V, call: &Call) {
+    v.visit_node(&call.callee);
+    for arg in &call.args {
+        v.visit_node(arg);
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(v: &mut V, block: &Block) {
+    for segment in &block.segments {
+        for node in &segment.nodes {
+            v.visit_node(node);
+        }
+    }
+}
+
+pub fn walk_block_expr<V: Visitor + ?Sized>(v: &mut V, block: &BlockExpr) {
+    for node in &block.nodes {
+        v.visit_node(node);
+    }
+    if let Some(value) = &block.value {
+        v.visit_node(value);
+    }
+}
+
+/// In-place traversal of a program, for rewrites.
+///
+/// Mirrors [`Visitor`]: each method defaults to the matching
+/// `walk_*_mut` function.
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program);
+    }
+
+    fn visit_node_mut(&mut self, node: &mut Node) {
+        walk_node_mut(self, node);
+    }
+
+    fn visit_func_mut(&mut self, func: &mut Func) {
+        walk_func_mut(self, func);
+    }
+
+    fn visit_call_mut(&mut self, call: &mut Call) {
+        walk_call_mut(self, call);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_block_expr_mut(&mut self, block: &mut BlockExpr) {
+        walk_block_expr_mut(self, block);
+    }
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(v: &mut V, program: &mut Program) {
+    for node in &mut program.nodes {
+        v.visit_node_mut(node);
+    }
+}
+
+pub fn walk_node_mut<V: VisitorMut + ?Sized>(v: &mut V, node: &mut Node) {
+    match node {
+        Node::Ident(_)
+        | Node::Lit(_)
+        | Node::DefineEmpty(_)
+        | Node::Copy(_)
+        | Node::Bind(_) => {}
+
+        Node::Not(inner) | Node::Neg(inner) | Node::Local(inner) => v.visit_node_mut(inner),
+
+        Node::Add(l, r)
+        | Node::Sub(l, r)
+        | Node::Mul(l, r)
+        | Node::Div(l, r)
+        | Node::Mod(l, r)
+        | Node::Eq(l, r)
+        | Node::Ne(l, r)
+        | Node::Lt(l, r)
+        | Node::Le(l, r)
+        | Node::Gt(l, r)
+        | Node::Ge(l, r)
+        | Node::And(l, r)
+        | Node::Or(l, r)
+        | Node::Has(l, r)
+        | Node::Present(l, r)
+        | Node::Pipe(l, r)
+        | Node::Coalesce(l, r) => {
+            v.visit_node_mut(l);
+            v.visit_node_mut(r);
+        }
+
+        Node::Ret(ret) => {
+            if let Some(value) = &mut ret.value {
+                v.visit_node_mut(value);
+            }
+        }
+        Node::Define(def) => v.visit_node_mut(&mut def.value),
+        Node::Guard(guard) => {
+            for branch in &mut guard.branches {
+                v.visit_node_mut(&mut branch.expr);
+            }
+        }
+
+        Node::Block(block) => v.visit_block_mut(block),
+        Node::BlockExpr(block) => v.visit_block_expr_mut(block),
+        Node::Func(func) => v.visit_func_mut(func),
+        Node::Call(call) => v.visit_call_mut(call),
+    }
+}
+
+pub fn walk_func_mut<V: VisitorMut + ?Sized>(v: &mut V, func: &mut Func) {
+    for param in &mut func.params {
+        if let Some(default) = &mut param.default {
+            v.visit_node_mut(default);
+        }
+    }
+    for node in &mut func.body {
+        v.visit_node_mut(node);
+    }
+}
+
+pub fn walk_call_mut<V: VisitorMut + ?Sized>(v: &mut V, call: &mut Call) {
+    v.visit_node_mut(&mut call.callee);
+    for arg in &mut call.args {
+        v.visit_node_mut(arg);
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(v: &mut V, block: &mut Block) {
+    for segment in &mut block.segments {
+        for node in &mut segment.nodes {
+            v.visit_node_mut(node);
+        }
+    }
+}
+
+pub fn walk_block_expr_mut<V: VisitorMut + ?Sized>(v: &mut V, block: &mut BlockExpr) {
+    for node in &mut block.nodes {
+        v.visit_node_mut(node);
+    }
+    if let Some(value) = &mut block.value {
+        v.visit_node_mut(value);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::compiler::ast::{Call, Node, Program};
+use crate::compiler::lexer::Lexer;
+use crate::compiler::parser::Parser;
+use crate::compiler::visit::{walk_call, walk_node_mut, Visitor, VisitorMut};
+
+fn parse_program(src: &str) -> Program {
+    let tokens = Lexer::new(src).tokenize().expect("lexing failed");
+    let mut parser = Parser::new(&tokens);
+    parser.parse_program().expect("failed to parse program")
+}
+
+#[derive(Default)]
+struct CallCounter {
+    calls: usize,
+}
+
+impl Visitor for CallCounter {
+    fn visit_call(&mut self, call: &Call) {
+        self.calls += 1;
+        walk_call(self, call);
+    }
+}
+
+#[test]
+fn counts_calls_at_every_depth() {
+    let program = parse_program(
+        "fn f :(a = g())( ret h(a); ): x = f(f(1)); :{ y = :[ z = k(); z ]:; }:",
+    );
+
+    let mut counter = CallCounter::default();
+    counter.visit_program(&program);
+
+    assert_eq!(counter.calls, 5);
+}
+
+struct RenameIdents;
+
+impl VisitorMut for RenameIdents {
+    fn visit_node_mut(&mut self, node: &mut Node) {
+        if let Node::Ident(ident) = node {
+            ident.name.push('_');
+        }
+        walk_node_mut(self, node);
+    }
+}
+
+#[test]
+fn mutable_visitor_rewrites_nested_nodes() {
+    let mut program = parse_program("x = a + f(b);");
+
+    RenameIdents.visit_program_mut(&mut program);
+
+    assert_eq!(program, parse_program("x = a_ + f_(b_);"));
+}
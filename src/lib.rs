@@ -1 +1,5 @@
+// Diagnostics are returned by value throughout the compiler; they are
+// only built on the error path, so their size is not a concern.
+#![allow(clippy::result_large_err)]
+
 pub mod compiler;